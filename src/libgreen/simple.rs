@@ -71,6 +71,12 @@ impl Runtime for SimpleTask {
     // feet and running.
     fn yield_now(~self, _cur_task: Box<Task>) { fail!() }
     fn maybe_yield(~self, _cur_task: Box<Task>) { fail!() }
+    fn deschedule_timeout(~self, _times: uint, _timeout_ms: u64,
+                          _cur_task: Box<Task>,
+                          _f: |BlockedTask| -> Result<(), BlockedTask>)
+                          -> rtio::IoResult<()> {
+        fail!()
+    }
     fn spawn_sibling(~self,
                      _cur_task: Box<Task>,
                      _opts: TaskOpts,
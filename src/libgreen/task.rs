@@ -18,10 +18,13 @@
 //! contains the rust task itself in order to juggle around ownership of the
 //! values.
 
+use alloc::arc::Arc;
+use libc;
 use std::any::Any;
 use std::mem;
 use std::raw;
 use std::rt::Runtime;
+use std::rt::exclusive::Exclusive;
 use std::rt::local::Local;
 use std::rt::mutex::NativeMutex;
 use std::rt::rtio;
@@ -383,6 +386,75 @@ impl Runtime for GreenTask {
         }
     }
 
+    fn deschedule_timeout(mut ~self, times: uint, timeout_ms: u64,
+                          cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>)
+                          -> rtio::IoResult<()> {
+        // The timeout is implemented with a timer from the local event loop.
+        // I/O objects find their event loop through the local task, so the
+        // timer has to be armed before descheduling, with this task put back
+        // in place for the meantime. The timer is kept alive until we're
+        // running again, at which point dropping it cancels the callback if it
+        // hasn't yet fired.
+        self.put_task(cur_task);
+        self.put();
+        let state = Arc::new(Exclusive::new(TimeoutState {
+            task: None,
+            fired: false,
+        }));
+        let timer = arm_timeout(timeout_ms, state.clone());
+        let task: Box<Task> = Local::take();
+        let mut me = GreenTask::convert(task);
+        let timer = match timer {
+            Ok(timer) => timer,
+            Err(e) => { me.put(); return Err(e) }
+        };
+
+        let mut sched = me.sched.take_unwrap();
+        if me.handle.is_none() {
+            me.handle = Some(sched.make_handle());
+            me.pool_id = sched.pool_id;
+        }
+
+        // The timer owns one more selectable handle to this task than was
+        // asked for, although it only gets hold of it once we've blocked. If
+        // the timer has already fired by then, we've timed out already.
+        sched.deschedule_running_task_and_then(me, |sched, task| {
+            let mut tasks = task.make_selectable(times + 1);
+            let timeout = tasks.next().unwrap();
+            for task in tasks {
+                match f(task) {
+                    Ok(()) => {},
+                    Err(task) => {
+                        task.wake().map(|t| {
+                            sched.enqueue_task(GreenTask::convert(t))
+                        });
+                        break
+                    }
+                }
+            }
+            let timeout = unsafe {
+                let mut state = state.lock();
+                if state.fired {
+                    Some(timeout)
+                } else {
+                    state.task = Some(timeout);
+                    None
+                }
+            };
+            match timeout {
+                Some(task) => {
+                    task.wake().map(|t| {
+                        sched.enqueue_task(GreenTask::convert(t))
+                    });
+                }
+                None => {}
+            }
+        });
+        drop(timer);
+        Ok(())
+    }
+
     fn reawaken(mut ~self, to_wake: Box<Task>) {
         self.put_task(to_wake);
         assert!(self.sched.is_none());
@@ -474,6 +546,51 @@ impl Runtime for GreenTask {
     fn wrap(~self) -> Box<Any> { self as Box<Any> }
 }
 
+// Shared between a task descheduled with a timeout and the callback of its
+// timer. The callback only receives the task's handle once the task has
+// finished blocking, so if it fires before then it leaves a note instead.
+struct TimeoutState {
+    task: Option<BlockedTask>,
+    fired: bool,
+}
+
+struct TimeoutCallback {
+    state: Arc<Exclusive<TimeoutState>>,
+}
+
+impl rtio::Callback for TimeoutCallback {
+    fn call(&mut self) {
+        let task = unsafe {
+            let mut state = self.state.lock();
+            state.fired = true;
+            state.task.take()
+        };
+        match task {
+            Some(task) => { task.wake().map(|t| t.reawaken()); }
+            None => {}
+        }
+    }
+}
+
+// Starts a timer on the local event loop which times out a task after
+// `timeout_ms` milliseconds. This must be called from task context.
+fn arm_timeout(timeout_ms: u64, state: Arc<Exclusive<TimeoutState>>)
+               -> rtio::IoResult<Box<rtio::RtioTimer + Send>> {
+    #[cfg(unix)] use ERROR = libc::ENOSYS;
+    #[cfg(windows)] use ERROR = libc::ERROR_CALL_NOT_IMPLEMENTED;
+
+    let mut timer = match rtio::LocalIo::borrow() {
+        Some(mut io) => try!(io.get().timer_init()),
+        None => return Err(rtio::IoError {
+            code: ERROR as uint,
+            extra: 0,
+            detail: Some("timeouts require an event loop for timers".to_str()),
+        }),
+    };
+    timer.oneshot(timeout_ms, box TimeoutCallback { state: state });
+    Ok(timer)
+}
+
 #[cfg(test)]
 mod tests {
     use std::rt::local::Local;
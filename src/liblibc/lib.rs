@@ -173,6 +173,7 @@ pub use funcs::bsd43::{shutdown};
 #[cfg(unix)] pub use consts::os::sysconf::{_SC_PAGESIZE};
 #[cfg(unix)] pub use consts::os::posix88::{PROT_READ, PROT_WRITE, PROT_EXEC};
 #[cfg(unix)] pub use consts::os::posix88::{MAP_FIXED, MAP_FILE, MAP_ANON, MAP_PRIVATE, MAP_FAILED};
+#[cfg(unix)] pub use consts::os::posix88::{MAP_SHARED, MS_SYNC};
 #[cfg(unix)] pub use consts::os::posix88::{EACCES, EBADF, EINVAL, ENODEV, ENOMEM};
 #[cfg(unix)] pub use consts::os::posix88::{ECONNREFUSED, ECONNRESET, EPERM, EPIPE};
 #[cfg(unix)] pub use consts::os::posix88::{ENOTCONN, ECONNABORTED, EADDRNOTAVAIL, EINTR};
@@ -180,8 +181,12 @@ pub use funcs::bsd43::{shutdown};
 #[cfg(unix)] pub use consts::os::posix88::{ECANCELED, SIGINT, EINPROGRESS};
 #[cfg(unix)] pub use consts::os::posix88::{ENOSYS, ENOTTY, ETIMEDOUT, EMFILE};
 #[cfg(unix)] pub use consts::os::posix88::{SIGTERM, SIGKILL, SIGPIPE, PROT_NONE};
+#[cfg(unix)] pub use consts::os::posix88::{O_NOCTTY, F_SETFD, FD_CLOEXEC};
 #[cfg(unix)] pub use consts::os::posix01::{SIG_IGN};
 #[cfg(unix)] pub use consts::os::bsd44::{AF_UNIX};
+#[cfg(unix)] pub use consts::os::bsd44::{LOCK_SH, LOCK_EX, LOCK_NB, LOCK_UN};
+#[cfg(unix)] pub use consts::os::bsd44::{TIOCGWINSZ, TIOCSWINSZ, TIOCSCTTY};
+#[cfg(unix)] pub use types::os::common::bsd44::{winsize};
 
 #[cfg(unix)] pub use types::os::common::posix01::{pthread_t, timespec, timezone};
 
@@ -192,12 +197,14 @@ pub use funcs::bsd43::{shutdown};
 #[cfg(unix)] pub use funcs::posix88::unistd::{getgid, getuid};
 #[cfg(unix)] pub use funcs::posix88::unistd::{_PC_NAME_MAX, utime, nanosleep, pathconf, link};
 #[cfg(unix)] pub use funcs::posix88::unistd::{chown};
-#[cfg(unix)] pub use funcs::posix88::mman::{mmap, munmap, mprotect};
+#[cfg(unix)] pub use funcs::posix88::mman::{mmap, munmap, mprotect, msync};
 #[cfg(unix)] pub use funcs::posix88::dirent::{opendir, readdir_r, closedir};
 #[cfg(unix)] pub use funcs::posix88::fcntl::{fcntl};
 #[cfg(unix)] pub use funcs::posix01::stat_::{lstat};
 #[cfg(unix)] pub use funcs::posix01::unistd::{fsync, ftruncate};
 #[cfg(unix)] pub use funcs::posix01::unistd::{readlink, symlink};
+#[cfg(unix)] pub use funcs::posix01::stdlib::{posix_openpt, grantpt, unlockpt, ptsname};
+#[cfg(unix)] pub use funcs::bsd44::{flock, ioctl};
 
 #[cfg(windows)] pub use consts::os::c95::{WSAECONNREFUSED, WSAECONNRESET, WSAEACCES};
 #[cfg(windows)] pub use consts::os::c95::{WSAEWOULDBLOCK, WSAENOTCONN, WSAECONNABORTED};
@@ -237,6 +244,8 @@ pub use funcs::bsd43::{shutdown};
 #[cfg(windows)] pub use consts::os::extra::{ERROR_PIPE_BUSY, ERROR_IO_PENDING};
 #[cfg(windows)] pub use consts::os::extra::{ERROR_PIPE_CONNECTED, WAIT_OBJECT_0};
 #[cfg(windows)] pub use consts::os::extra::{ERROR_NOT_FOUND};
+#[cfg(windows)] pub use consts::os::extra::{ERROR_LOCK_VIOLATION};
+#[cfg(windows)] pub use consts::os::extra::{LOCKFILE_FAIL_IMMEDIATELY, LOCKFILE_EXCLUSIVE_LOCK};
 #[cfg(windows)] pub use consts::os::extra::{ERROR_OPERATION_ABORTED};
 #[cfg(windows)] pub use types::os::common::bsd44::{SOCKET};
 #[cfg(windows)] pub use types::os::common::posix01::{stat, utimbuf};
@@ -257,6 +266,7 @@ pub use funcs::bsd43::{shutdown};
 #[cfg(windows)] pub use funcs::extra::kernel32::{UnmapViewOfFile, CloseHandle};
 #[cfg(windows)] pub use funcs::extra::kernel32::{WaitForSingleObject, GetSystemTimeAsFileTime};
 #[cfg(windows)] pub use funcs::extra::kernel32::{QueryPerformanceCounter};
+#[cfg(windows)] pub use funcs::extra::kernel32::{LockFileEx, UnlockFileEx};
 #[cfg(windows)] pub use funcs::extra::kernel32::{WaitForSingleObject, QueryPerformanceFrequency};
 #[cfg(windows)] pub use funcs::extra::kernel32::{GetExitCodeProcess, TerminateProcess};
 #[cfg(windows)] pub use funcs::extra::kernel32::{ReadFile, WriteFile, SetFilePointerEx};
@@ -396,12 +406,18 @@ pub mod types {
                 pub type sighandler_t = size_t;
             }
             pub mod bsd44 {
-                use types::os::arch::c95::{c_char, c_int, c_uint};
+                use types::os::arch::c95::{c_char, c_int, c_uint, c_ushort};
 
                 pub type socklen_t = u32;
                 pub type sa_family_t = u16;
                 pub type in_port_t = u16;
                 pub type in_addr_t = u32;
+                pub struct winsize {
+                    pub ws_row: c_ushort,
+                    pub ws_col: c_ushort,
+                    pub ws_xpixel: c_ushort,
+                    pub ws_ypixel: c_ushort,
+                }
                 pub struct sockaddr {
                     pub sa_family: sa_family_t,
                     pub sa_data: [u8, ..14],
@@ -783,12 +799,18 @@ pub mod types {
                 pub type sighandler_t = size_t;
             }
             pub mod bsd44 {
-                use types::os::arch::c95::{c_char, c_int, c_uint};
+                use types::os::arch::c95::{c_char, c_int, c_uint, c_ushort};
 
                 pub type socklen_t = u32;
                 pub type sa_family_t = u8;
                 pub type in_port_t = u16;
                 pub type in_addr_t = u32;
+                pub struct winsize {
+                    pub ws_row: c_ushort,
+                    pub ws_col: c_ushort,
+                    pub ws_xpixel: c_ushort,
+                    pub ws_ypixel: c_ushort,
+                }
                 pub struct sockaddr {
                     pub sa_len: u8,
                     pub sa_family: sa_family_t,
@@ -1341,12 +1363,18 @@ pub mod types {
             }
 
             pub mod bsd44 {
-                use types::os::arch::c95::{c_char, c_int, c_uint};
+                use types::os::arch::c95::{c_char, c_int, c_uint, c_ushort};
 
                 pub type socklen_t = c_int;
                 pub type sa_family_t = u8;
                 pub type in_port_t = u16;
                 pub type in_addr_t = u32;
+                pub struct winsize {
+                    pub ws_row: c_ushort,
+                    pub ws_col: c_ushort,
+                    pub ws_xpixel: c_ushort,
+                    pub ws_ypixel: c_ushort,
+                }
                 pub struct sockaddr {
                     pub sa_len: u8,
                     pub sa_family: sa_family_t,
@@ -1765,6 +1793,7 @@ pub mod consts {
             pub static ERROR_FILE_NOT_FOUND: c_int = 2;
             pub static ERROR_ACCESS_DENIED: c_int = 5;
             pub static ERROR_INVALID_HANDLE : c_int = 6;
+            pub static ERROR_LOCK_VIOLATION : c_int = 33;
             pub static ERROR_BROKEN_PIPE: c_int = 109;
             pub static ERROR_DISK_FULL : c_int = 112;
             pub static ERROR_CALL_NOT_IMPLEMENTED : c_int = 120;
@@ -1876,6 +1905,9 @@ pub mod consts {
             pub static MOVEFILE_REPLACE_EXISTING: DWORD = 1;
             pub static MOVEFILE_WRITE_THROUGH: DWORD = 8;
 
+            pub static LOCKFILE_FAIL_IMMEDIATELY: DWORD = 1;
+            pub static LOCKFILE_EXCLUSIVE_LOCK: DWORD = 2;
+
             pub static SYMBOLIC_LINK_FLAG_DIRECTORY: DWORD = 1;
 
             pub static FILE_SHARE_DELETE: DWORD = 0x4;
@@ -2018,6 +2050,9 @@ pub mod consts {
             pub static O_CREAT : c_int = 64;
             pub static O_EXCL : c_int = 128;
             pub static O_TRUNC : c_int = 512;
+            pub static O_NOCTTY : c_int = 256;
+            pub static F_SETFD : c_int = 2;
+            pub static FD_CLOEXEC : c_int = 1;
             pub static S_IFIFO : c_int = 4096;
             pub static S_IFCHR : c_int = 8192;
             pub static S_IFBLK : c_int = 24576;
@@ -2230,6 +2265,9 @@ pub mod consts {
             pub static O_CREAT : c_int = 256;
             pub static O_EXCL : c_int = 1024;
             pub static O_TRUNC : c_int = 512;
+            pub static O_NOCTTY : c_int = 2048;
+            pub static F_SETFD : c_int = 2;
+            pub static FD_CLOEXEC : c_int = 1;
             pub static S_IFIFO : c_int = 4096;
             pub static S_IFCHR : c_int = 8192;
             pub static S_IFBLK : c_int = 24576;
@@ -2505,7 +2543,7 @@ pub mod consts {
         #[cfg(target_arch = "x86")]
         #[cfg(target_arch = "x86_64")]
         pub mod bsd44 {
-            use types::os::arch::c95::c_int;
+            use types::os::arch::c95::{c_int, c_ulong};
 
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
@@ -2545,11 +2583,20 @@ pub mod consts {
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
             pub static SHUT_RDWR: c_int = 2;
+
+            pub static LOCK_SH: c_int = 1;
+            pub static LOCK_EX: c_int = 2;
+            pub static LOCK_NB: c_int = 4;
+            pub static LOCK_UN: c_int = 8;
+
+            pub static TIOCGWINSZ: c_ulong = 0x5413;
+            pub static TIOCSWINSZ: c_ulong = 0x5414;
+            pub static TIOCSCTTY: c_ulong = 0x540E;
         }
         #[cfg(target_arch = "mips")]
         #[cfg(target_arch = "mipsel")]
         pub mod bsd44 {
-            use types::os::arch::c95::c_int;
+            use types::os::arch::c95::{c_int, c_ulong};
 
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
@@ -2589,6 +2636,15 @@ pub mod consts {
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
             pub static SHUT_RDWR: c_int = 2;
+
+            pub static LOCK_SH: c_int = 1;
+            pub static LOCK_EX: c_int = 2;
+            pub static LOCK_NB: c_int = 4;
+            pub static LOCK_UN: c_int = 8;
+
+            pub static TIOCGWINSZ: c_ulong = 0x40087468;
+            pub static TIOCSWINSZ: c_ulong = 0x80087467;
+            pub static TIOCSCTTY: c_ulong = 0x5480;
         }
         #[cfg(target_arch = "x86")]
         #[cfg(target_arch = "x86_64")]
@@ -2769,6 +2825,9 @@ pub mod consts {
             pub static O_CREAT : c_int = 512;
             pub static O_EXCL : c_int = 2048;
             pub static O_TRUNC : c_int = 1024;
+            pub static O_NOCTTY : c_int = 32768;
+            pub static F_SETFD : c_int = 2;
+            pub static FD_CLOEXEC : c_int = 1;
             pub static S_IFIFO : c_int = 4096;
             pub static S_IFCHR : c_int = 8192;
             pub static S_IFBLK : c_int = 24576;
@@ -3000,7 +3059,7 @@ pub mod consts {
         pub mod posix08 {
         }
         pub mod bsd44 {
-            use types::os::arch::c95::c_int;
+            use types::os::arch::c95::{c_int, c_ulong};
 
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
@@ -3048,6 +3107,15 @@ pub mod consts {
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
             pub static SHUT_RDWR: c_int = 2;
+
+            pub static LOCK_SH: c_int = 1;
+            pub static LOCK_EX: c_int = 2;
+            pub static LOCK_NB: c_int = 4;
+            pub static LOCK_UN: c_int = 8;
+
+            pub static TIOCGWINSZ: c_ulong = 0x40087468;
+            pub static TIOCSWINSZ: c_ulong = 0x80087467;
+            pub static TIOCSCTTY: c_ulong = 0x20007461;
         }
         pub mod extra {
             use types::os::arch::c95::c_int;
@@ -3158,6 +3226,9 @@ pub mod consts {
             pub static O_CREAT : c_int = 512;
             pub static O_EXCL : c_int = 2048;
             pub static O_TRUNC : c_int = 1024;
+            pub static O_NOCTTY : c_int = 131072;
+            pub static F_SETFD : c_int = 2;
+            pub static FD_CLOEXEC : c_int = 1;
             pub static S_IFIFO : c_int = 4096;
             pub static S_IFCHR : c_int = 8192;
             pub static S_IFBLK : c_int = 24576;
@@ -3388,7 +3459,7 @@ pub mod consts {
         pub mod posix08 {
         }
         pub mod bsd44 {
-            use types::os::arch::c95::c_int;
+            use types::os::arch::c95::{c_int, c_ulong};
 
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
@@ -3434,6 +3505,15 @@ pub mod consts {
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
             pub static SHUT_RDWR: c_int = 2;
+
+            pub static LOCK_SH: c_int = 1;
+            pub static LOCK_EX: c_int = 2;
+            pub static LOCK_NB: c_int = 4;
+            pub static LOCK_UN: c_int = 8;
+
+            pub static TIOCGWINSZ: c_ulong = 0x40087468;
+            pub static TIOCSWINSZ: c_ulong = 0x80087467;
+            pub static TIOCSCTTY: c_ulong = 0x20007461;
         }
         pub mod extra {
             use types::os::arch::c95::c_int;
@@ -4060,6 +4140,17 @@ pub mod funcs {
             }
         }
 
+        pub mod stdlib {
+            use types::os::arch::c95::{c_char, c_int};
+
+            extern {
+                pub fn posix_openpt(flags: c_int) -> c_int;
+                pub fn grantpt(fd: c_int) -> c_int;
+                pub fn unlockpt(fd: c_int) -> c_int;
+                pub fn ptsname(fd: c_int) -> *mut c_char;
+            }
+        }
+
         pub mod signal {
             use types::os::arch::c95::c_int;
             use types::os::common::posix01::sighandler_t;
@@ -4113,6 +4204,9 @@ pub mod funcs {
         pub mod unistd {
         }
 
+        pub mod stdlib {
+        }
+
         pub mod glob {
         }
 
@@ -4212,7 +4306,7 @@ pub mod funcs {
     #[cfg(target_os = "freebsd")]
     pub mod bsd44 {
         use types::common::c95::{c_void};
-        use types::os::arch::c95::{c_char, c_uchar, c_int, c_uint, size_t};
+        use types::os::arch::c95::{c_char, c_uchar, c_int, c_ulong, c_uint, size_t};
 
         extern {
             pub fn sysctl(name: *mut c_int,
@@ -4237,6 +4331,8 @@ pub mod funcs {
                            -> c_int;
             pub fn mincore(addr: *mut c_void, len: size_t, vec: *mut c_uchar)
                            -> c_int;
+            pub fn flock(fd: c_int, operation: c_int) -> c_int;
+            pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
         }
     }
 
@@ -4245,7 +4341,7 @@ pub mod funcs {
     #[cfg(target_os = "android")]
     pub mod bsd44 {
         use types::common::c95::{c_void};
-        use types::os::arch::c95::{c_uchar, c_int, size_t};
+        use types::os::arch::c95::{c_uchar, c_int, c_ulong, size_t};
 
         extern {
             pub fn getdtablesize() -> c_int;
@@ -4253,6 +4349,8 @@ pub mod funcs {
                            -> c_int;
             pub fn mincore(addr: *mut c_void, len: size_t, vec: *mut c_uchar)
                            -> c_int;
+            pub fn flock(fd: c_int, operation: c_int) -> c_int;
+            pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
         }
     }
 
@@ -4434,6 +4532,17 @@ pub mod funcs {
                                         lpNewFilePointer: PLARGE_INTEGER,
                                         dwMoveMethod: DWORD) -> BOOL;
                 pub fn SetEndOfFile(hFile: HANDLE) -> BOOL;
+                pub fn LockFileEx(hFile: HANDLE,
+                                  dwFlags: DWORD,
+                                  dwReserved: DWORD,
+                                  nNumberOfBytesToLockLow: DWORD,
+                                  nNumberOfBytesToLockHigh: DWORD,
+                                  lpOverlapped: LPOVERLAPPED) -> BOOL;
+                pub fn UnlockFileEx(hFile: HANDLE,
+                                    dwReserved: DWORD,
+                                    nNumberOfBytesToUnlockLow: DWORD,
+                                    nNumberOfBytesToUnlockHigh: DWORD,
+                                    lpOverlapped: LPOVERLAPPED) -> BOOL;
 
                 pub fn GetSystemTimeAsFileTime(
                             lpSystemTimeAsFileTime: LPFILETIME);
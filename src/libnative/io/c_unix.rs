@@ -16,6 +16,27 @@ pub use self::select::fd_set;
 pub use self::signal::{sigaction, siginfo, sigset_t};
 pub use self::signal::{SA_ONSTACK, SA_RESTART, SA_RESETHAND, SA_NOCLDSTOP};
 pub use self::signal::{SA_NODEFER, SA_NOCLDWAIT, SA_SIGINFO, SIGCHLD};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::epoll::{epoll_event, epoll_create1, epoll_ctl, epoll_wait};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::epoll::{EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_MOD, EPOLL_CTL_DEL};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::epoll::{EPOLLIN, EPOLLOUT, EPOLLERR, EPOLLHUP};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::inotify::{inotify_event, inotify_init1, inotify_add_watch};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::inotify::{IN_CLOEXEC, IN_MODIFY, IN_ATTRIB, IN_MOVED_FROM};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::inotify::{IN_MOVED_TO, IN_CREATE, IN_DELETE, IN_DELETE_SELF};
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub use self::inotify::{IN_MOVE_SELF, IN_Q_OVERFLOW, IN_IGNORED, IN_ISDIR};
 
 use libc;
 
@@ -53,8 +74,171 @@ pub static MSG_DONTWAIT: libc::c_int = 0x80;
 #[cfg(target_os = "android")]
 pub static MSG_DONTWAIT: libc::c_int = 0x40;
 
+// Marks file descriptors received over a unix socket close-on-exec as they
+// arrive. Elsewhere this has to be done after recvmsg() returns.
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static MSG_CMSG_CLOEXEC: libc::c_int = 0x40000000;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+pub static MSG_CMSG_CLOEXEC: libc::c_int = 0;
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+pub static SO_TYPE: libc::c_int = 0x1008;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static SO_TYPE: libc::c_int = 3;
+
 pub static WNOHANG: libc::c_int = 1;
 
+pub static RLIMIT_CPU: libc::c_int = 0;
+pub static RLIMIT_FSIZE: libc::c_int = 1;
+pub static RLIMIT_DATA: libc::c_int = 2;
+pub static RLIMIT_STACK: libc::c_int = 3;
+pub static RLIMIT_CORE: libc::c_int = 4;
+#[cfg(target_os = "linux", target_arch = "x86")]
+#[cfg(target_os = "linux", target_arch = "x86_64")]
+#[cfg(target_os = "linux", target_arch = "arm")]
+#[cfg(target_os = "android")]
+pub static RLIMIT_NPROC: libc::c_int = 6;
+#[cfg(target_os = "linux", target_arch = "x86")]
+#[cfg(target_os = "linux", target_arch = "x86_64")]
+#[cfg(target_os = "linux", target_arch = "arm")]
+#[cfg(target_os = "android")]
+pub static RLIMIT_NOFILE: libc::c_int = 7;
+#[cfg(target_os = "linux", target_arch = "x86")]
+#[cfg(target_os = "linux", target_arch = "x86_64")]
+#[cfg(target_os = "linux", target_arch = "arm")]
+#[cfg(target_os = "android")]
+pub static RLIMIT_AS: libc::c_int = 9;
+#[cfg(target_os = "linux", target_arch = "mips")]
+#[cfg(target_os = "linux", target_arch = "mipsel")]
+pub static RLIMIT_NOFILE: libc::c_int = 5;
+#[cfg(target_os = "linux", target_arch = "mips")]
+#[cfg(target_os = "linux", target_arch = "mipsel")]
+pub static RLIMIT_AS: libc::c_int = 6;
+#[cfg(target_os = "linux", target_arch = "mips")]
+#[cfg(target_os = "linux", target_arch = "mipsel")]
+pub static RLIMIT_NPROC: libc::c_int = 8;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+pub static RLIMIT_NPROC: libc::c_int = 7;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+pub static RLIMIT_NOFILE: libc::c_int = 8;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+pub static RLIMIT_AS: libc::c_int = 5;
+#[cfg(target_os = "freebsd")]
+pub static RLIMIT_AS: libc::c_int = 10;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub type rlim_t = libc::c_ulong;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+pub type rlim_t = u64;
+#[cfg(target_os = "freebsd")]
+pub type rlim_t = i64;
+
+#[cfg(target_os = "linux", target_arch = "x86")]
+#[cfg(target_os = "linux", target_arch = "x86_64")]
+#[cfg(target_os = "linux", target_arch = "arm")]
+#[cfg(target_os = "android")]
+pub static RLIM_INFINITY: rlim_t = !0;
+#[cfg(target_os = "linux", target_arch = "mips")]
+#[cfg(target_os = "linux", target_arch = "mipsel")]
+pub static RLIM_INFINITY: rlim_t = 0x7fffffff;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+pub static RLIM_INFINITY: rlim_t = 0x7fffffffffffffff;
+
+#[repr(C)]
+pub struct rlimit {
+    pub rlim_cur: rlim_t,
+    pub rlim_max: rlim_t,
+}
+
+// The most buffers a single readv/writev call accepts.
+pub static IOV_MAX: uint = 1024;
+
+#[repr(C)]
+pub struct iovec {
+    pub iov_base: *mut libc::c_void,
+    pub iov_len: libc::size_t,
+}
+
+// The types of the length fields of msghdr and cmsghdr, which glibc declares
+// as size_t while the BSDs follow POSIX.
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+mod msglen {
+    use libc;
+    pub type iovlen_t = libc::size_t;
+    pub type controllen_t = libc::size_t;
+    pub type cmsglen_t = libc::size_t;
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+mod msglen {
+    use libc;
+    pub type iovlen_t = libc::c_int;
+    pub type controllen_t = libc::socklen_t;
+    pub type cmsglen_t = libc::socklen_t;
+}
+
+pub use self::msglen::{iovlen_t, controllen_t, cmsglen_t};
+
+#[repr(C)]
+pub struct msghdr {
+    pub msg_name: *mut libc::c_void,
+    pub msg_namelen: libc::socklen_t,
+    pub msg_iov: *mut iovec,
+    pub msg_iovlen: iovlen_t,
+    pub msg_control: *mut libc::c_void,
+    pub msg_controllen: controllen_t,
+    pub msg_flags: libc::c_int,
+}
+
+#[repr(C)]
+pub struct cmsghdr {
+    pub cmsg_len: cmsglen_t,
+    pub cmsg_level: libc::c_int,
+    pub cmsg_type: libc::c_int,
+}
+
+// Control messages, and the data in them, are padded out to this alignment.
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+pub static CMSG_ALIGN: uint = 4;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[cfg(target_os = "freebsd")]
+pub static CMSG_ALIGN: uint = ::std::uint::BYTES;
+
+pub static SCM_RIGHTS: libc::c_int = 1;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static SO_PEERCRED: libc::c_int = 17;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[repr(C)]
+pub struct ucred {
+    pub pid: libc::pid_t,
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+}
+
 extern {
     pub fn gettimeofday(timeval: *mut libc::timeval,
                         tzp: *mut libc::c_void) -> libc::c_int;
@@ -68,8 +252,17 @@ extern {
                       optname: libc::c_int,
                       optval: *mut libc::c_void,
                       optlen: *mut libc::socklen_t) -> libc::c_int;
-    pub fn ioctl(fd: libc::c_int, req: libc::c_ulong, ...) -> libc::c_int;
 
+    pub fn readv(fd: libc::c_int, iov: *const iovec,
+                 iovcnt: libc::c_int) -> libc::ssize_t;
+    pub fn writev(fd: libc::c_int, iov: *const iovec,
+                  iovcnt: libc::c_int) -> libc::ssize_t;
+    pub fn recvmsg(sockfd: libc::c_int, msg: *mut msghdr,
+                   flags: libc::c_int) -> libc::ssize_t;
+    pub fn sendmsg(sockfd: libc::c_int, msg: *const msghdr,
+                   flags: libc::c_int) -> libc::ssize_t;
+
+    pub fn setrlimit(resource: libc::c_int, rlim: *const rlimit) -> libc::c_int;
 
     pub fn waitpid(pid: libc::pid_t, status: *mut libc::c_int,
                    options: libc::c_int) -> libc::pid_t;
@@ -83,6 +276,37 @@ extern {
     pub fn sigemptyset(set: *mut sigset_t) -> libc::c_int;
 }
 
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+extern {
+    pub fn sendfile(out_fd: libc::c_int, in_fd: libc::c_int,
+                    offset: *mut libc::off_t,
+                    count: libc::size_t) -> libc::ssize_t;
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+extern {
+    pub fn sendfile(fd: libc::c_int, s: libc::c_int, offset: libc::off_t,
+                    len: *mut libc::off_t, hdtr: *mut libc::c_void,
+                    flags: libc::c_int) -> libc::c_int;
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+extern {
+    pub fn getpeereid(s: libc::c_int, euid: *mut libc::uid_t,
+                      egid: *mut libc::gid_t) -> libc::c_int;
+}
+
+#[cfg(target_os = "freebsd")]
+extern {
+    pub fn sendfile(fd: libc::c_int, s: libc::c_int, offset: libc::off_t,
+                    nbytes: libc::size_t, hdtr: *mut libc::c_void,
+                    sbytes: *mut libc::off_t, flags: libc::c_int) -> libc::c_int;
+}
+
 #[cfg(target_os = "macos")]
 #[cfg(target_os = "ios")]
 mod select {
@@ -249,3 +473,79 @@ mod signal {
         pub sa_mask: sigset_t,
     }
 }
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+mod epoll {
+    use libc;
+
+    pub static EPOLL_CLOEXEC: libc::c_int = 0x80000;
+
+    pub static EPOLL_CTL_ADD: libc::c_int = 1;
+    pub static EPOLL_CTL_DEL: libc::c_int = 2;
+    pub static EPOLL_CTL_MOD: libc::c_int = 3;
+
+    pub static EPOLLIN: u32 = 0x001;
+    pub static EPOLLOUT: u32 = 0x004;
+    pub static EPOLLERR: u32 = 0x008;
+    pub static EPOLLHUP: u32 = 0x010;
+
+    // The kernel declares this structure packed on x86 and x86_64 only.
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[packed]
+    pub struct epoll_event {
+        pub events: u32,
+        pub data: u64,
+    }
+
+    #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+    pub struct epoll_event {
+        pub events: u32,
+        pub data: u64,
+    }
+
+    extern {
+        pub fn epoll_create1(flags: libc::c_int) -> libc::c_int;
+        pub fn epoll_ctl(epfd: libc::c_int, op: libc::c_int, fd: libc::c_int,
+                         event: *mut epoll_event) -> libc::c_int;
+        pub fn epoll_wait(epfd: libc::c_int, events: *mut epoll_event,
+                          maxevents: libc::c_int,
+                          timeout: libc::c_int) -> libc::c_int;
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+mod inotify {
+    use libc;
+
+    pub static IN_CLOEXEC: libc::c_int = 0x80000;
+
+    pub static IN_MODIFY: u32 = 0x00000002;
+    pub static IN_ATTRIB: u32 = 0x00000004;
+    pub static IN_MOVED_FROM: u32 = 0x00000040;
+    pub static IN_MOVED_TO: u32 = 0x00000080;
+    pub static IN_CREATE: u32 = 0x00000100;
+    pub static IN_DELETE: u32 = 0x00000200;
+    pub static IN_DELETE_SELF: u32 = 0x00000400;
+    pub static IN_MOVE_SELF: u32 = 0x00000800;
+    pub static IN_Q_OVERFLOW: u32 = 0x00004000;
+    pub static IN_IGNORED: u32 = 0x00008000;
+    pub static IN_ISDIR: u32 = 0x40000000;
+
+    // Followed by `len` bytes of nul-padded name.
+    #[repr(C)]
+    pub struct inotify_event {
+        pub wd: libc::c_int,
+        pub mask: u32,
+        pub cookie: u32,
+        pub len: u32,
+    }
+
+    extern {
+        pub fn inotify_init1(flags: libc::c_int) -> libc::c_int;
+        pub fn inotify_add_watch(fd: libc::c_int, pathname: *const libc::c_char,
+                                 mask: u32) -> libc::c_int;
+    }
+}
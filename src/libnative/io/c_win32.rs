@@ -19,6 +19,7 @@ pub static WSASYS_STATUS_LEN: uint = 128;
 pub static FIONBIO: libc::c_long = 0x8004667e;
 static FD_SETSIZE: uint = 64;
 pub static MSG_DONTWAIT: libc::c_int = 0;
+pub static SO_TYPE: libc::c_int = 0x1008;
 pub static ERROR_ILLEGAL_CHARACTER: libc::c_int = 582;
 pub static ENABLE_ECHO_INPUT: libc::DWORD = 0x4;
 pub static ENABLE_EXTENDED_FLAGS: libc::DWORD = 0x80;
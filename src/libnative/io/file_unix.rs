@@ -14,12 +14,14 @@ use alloc::arc::Arc;
 use libc::{c_int, c_void};
 use libc;
 use std::c_str::CString;
+use std::cmp;
 use std::mem;
 use std::rt::rtio;
 use std::rt::rtio::IoResult;
 
 use io::{retry, keep_going};
 use io::util;
+use super::c;
 
 pub type fd_t = libc::c_int;
 
@@ -28,6 +30,7 @@ struct Inner {
     close_on_drop: bool,
 }
 
+#[deriving(Clone)]
 pub struct FileDesc {
     inner: Arc<Inner>
 }
@@ -79,6 +82,42 @@ impl FileDesc {
         }
     }
 
+    pub fn inner_readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let iovs = util::iovecs_mut(bufs);
+        if iovs.len() == 0 { return Ok(0) }
+        let cnt = cmp::min(iovs.len(), c::IOV_MAX);
+        let ret = retry(|| unsafe {
+            c::readv(self.fd(), iovs.as_ptr(), cnt as c_int) as c_int
+        });
+        if ret == 0 {
+            Err(util::eof())
+        } else if ret < 0 {
+            Err(super::last_error())
+        } else {
+            Ok(ret as uint)
+        }
+    }
+    pub fn inner_writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let mut iovs = util::iovecs(bufs);
+        let mut start = 0;
+        while start < iovs.len() {
+            let ret = {
+                let iovs = iovs.slice_from(start);
+                let cnt = cmp::min(iovs.len(), c::IOV_MAX);
+                retry(|| unsafe {
+                    c::writev(self.fd(), iovs.as_ptr(), cnt as c_int) as c_int
+                })
+            };
+            match ret {
+                -1 => return Err(super::last_error()),
+                0 => return Err(util::eof()),
+                n => start = util::advance_iovecs(iovs.as_mut_slice(), start,
+                                                  n as uint),
+            }
+        }
+        Ok(())
+    }
+
     pub fn fd(&self) -> fd_t { self.inner.fd }
 }
 
@@ -100,10 +139,28 @@ impl rtio::RtioFileStream for FileDesc {
         }
     }
     fn pwrite(&mut self, buf: &[u8], offset: u64) -> IoResult<()> {
-        super::mkerr_libc(retry(|| unsafe {
-            libc::pwrite(self.fd(), buf.as_ptr() as *const _,
-                         buf.len() as libc::size_t, offset as libc::off_t)
-        } as c_int))
+        let mut written = 0;
+        let ret = keep_going(buf, |buf, len| {
+            let offset = offset + written;
+            let ret = unsafe {
+                libc::pwrite(self.fd(), buf as *const libc::c_void,
+                             len as libc::size_t,
+                             offset as libc::off_t) as i64
+            };
+            if ret > 0 { written += ret as u64 }
+            ret
+        });
+        if ret < 0 {
+            Err(super::last_error())
+        } else {
+            Ok(())
+        }
+    }
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        self.inner_readv(bufs).map(|i| i as int)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.inner_writev(bufs)
     }
     fn seek(&mut self, pos: i64, whence: rtio::SeekStyle) -> IoResult<u64> {
         let whence = match whence {
@@ -159,6 +216,20 @@ impl rtio::RtioFileStream for FileDesc {
             _ => Err(super::last_error()),
         }
     }
+
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        let mut op = if exclusive {libc::LOCK_EX} else {libc::LOCK_SH};
+        if !wait { op |= libc::LOCK_NB }
+        match retry(|| unsafe { libc::flock(self.fd(), op) }) {
+            0 => Ok(true),
+            _ if !wait && util::wouldblock() => Ok(false),
+            _ => Err(super::last_error()),
+        }
+    }
+    fn unlock(&mut self) -> IoResult<()> {
+        super::mkerr_libc(retry(|| unsafe { libc::flock(self.fd(), libc::LOCK_UN) }))
+    }
+    fn raw_fd(&self) -> c_int { self.fd() }
 }
 
 impl rtio::RtioPipe for FileDesc {
@@ -168,9 +239,16 @@ impl rtio::RtioPipe for FileDesc {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.inner_write(buf)
     }
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.inner_readv(bufs)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.inner_writev(bufs)
+    }
     fn clone(&self) -> Box<rtio::RtioPipe + Send> {
         box FileDesc { inner: self.inner.clone() } as Box<rtio::RtioPipe + Send>
     }
+    fn raw_fd(&self) -> libc::c_int { self.fd() }
 
     // Only supported on named pipes currently. Note that this doesn't have an
     // impact on the std::io primitives, this is never called via
@@ -280,6 +358,9 @@ impl rtio::RtioFileStream for CFile {
     fn pwrite(&mut self, buf: &[u8], offset: u64) -> IoResult<()> {
         self.flush().and_then(|()| self.fd.pwrite(buf, offset))
     }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.flush().and_then(|()| self.fd.writev(bufs))
+    }
     fn seek(&mut self, pos: i64, style: rtio::SeekStyle) -> IoResult<u64> {
         let whence = match style {
             rtio::SeekSet => libc::SEEK_SET,
@@ -314,6 +395,14 @@ impl rtio::RtioFileStream for CFile {
     fn fstat(&mut self) -> IoResult<rtio::FileStat> {
         self.flush().and_then(|()| self.fd.fstat())
     }
+
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        self.fd.lock(exclusive, wait)
+    }
+    fn unlock(&mut self) -> IoResult<()> {
+        self.flush().and_then(|()| self.fd.unlock())
+    }
+    fn raw_fd(&self) -> c_int { self.fd.fd() }
 }
 
 impl Drop for CFile {
@@ -15,6 +15,7 @@ use libc::{c_int, c_void};
 use libc;
 use std::c_str::CString;
 use std::mem;
+use std::os;
 use std::os::win32::fill_utf16_buf_and_decode;
 use std::ptr;
 use std::rt::rtio;
@@ -29,6 +30,7 @@ struct Inner {
     close_on_drop: bool,
 }
 
+#[deriving(Clone)]
 pub struct FileDesc {
     inner: Arc<Inner>
 }
@@ -192,6 +194,32 @@ impl rtio::RtioFileStream for FileDesc {
             _ => Err(super::last_error()),
         }
     }
+
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        let mut flags = 0;
+        if exclusive { flags |= libc::LOCKFILE_EXCLUSIVE_LOCK }
+        if !wait { flags |= libc::LOCKFILE_FAIL_IMMEDIATELY }
+        // The whole file is locked by locking the largest possible range
+        // starting at offset 0.
+        let mut overlap: libc::OVERLAPPED = unsafe { mem::zeroed() };
+        let ret = unsafe {
+            libc::LockFileEx(self.handle(), flags, 0, !0, !0, &mut overlap)
+        };
+        if ret != 0 {
+            Ok(true)
+        } else if !wait && os::errno() == libc::ERROR_LOCK_VIOLATION as uint {
+            Ok(false)
+        } else {
+            Err(super::last_error())
+        }
+    }
+    fn unlock(&mut self) -> IoResult<()> {
+        let mut overlap: libc::OVERLAPPED = unsafe { mem::zeroed() };
+        super::mkerr_winbool(unsafe {
+            libc::UnlockFileEx(self.handle(), 0, !0, !0, &mut overlap)
+        })
+    }
+    fn raw_fd(&self) -> c_int { self.fd() }
 }
 
 impl rtio::RtioPipe for FileDesc {
@@ -0,0 +1,267 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filesystem change notification for linux, implemented with inotify.
+//!
+//! Each watcher owns an inotify descriptor with one watch per directory it
+//! covers; inotify doesn't watch directory trees by itself, so recursive
+//! watchers add watches for new subdirectories as they appear. The watches are
+//! set up on the calling thread, so that errors are reported by `new`, and the
+//! descriptor is then handed over to a helper thread which reads events for
+//! all of the watchers and calls their callbacks.
+
+use libc;
+use std::c_str::CString;
+use std::cmp;
+use std::collections::HashMap;
+use std::comm;
+use std::mem;
+use std::os;
+use std::ptr;
+use std::rt::rtio;
+use std::rt::rtio::IoResult;
+use std::slice;
+use std::sync::atomics;
+
+use io::c;
+use io::file;
+use io::file::FileDesc;
+use io::helper_thread::Helper;
+use io::util;
+
+helper_init!(static mut HELPER: Helper<Req>)
+
+static WATCH_MASK: u32 = c::IN_CREATE | c::IN_DELETE | c::IN_DELETE_SELF |
+                         c::IN_MODIFY | c::IN_ATTRIB | c::IN_MOVED_FROM |
+                         c::IN_MOVED_TO | c::IN_MOVE_SELF;
+
+pub struct Watcher {
+    id: uint,
+}
+
+struct Inner {
+    id: uint,
+    fd: FileDesc,
+    recursive: bool,
+    // The watch of the path given to `new`. Events about the watched
+    // directories themselves are only reported for this one, the parent
+    // directory reports them for the others.
+    root: libc::c_int,
+    watches: HashMap<libc::c_int, Path>,
+    cb: Box<rtio::FsEventCallback + Send>,
+}
+
+#[allow(visible_private_types)]
+pub enum Req {
+    // Start reading the events of a new watcher.
+    NewWatcher(Box<Inner>),
+
+    // Drop the watcher with the given id, and then acknowledge it on the
+    // channel provided so no more events are delivered once it's gone.
+    RemoveWatcher(uint, Sender<()>),
+}
+
+impl Watcher {
+    pub fn new(path: &CString, recursive: bool,
+               cb: Box<rtio::FsEventCallback + Send>) -> IoResult<Watcher> {
+        let fd = match unsafe { c::inotify_init1(c::IN_CLOEXEC) } {
+            -1 => return Err(super::last_error()),
+            fd => FileDesc::new(fd, true),
+        };
+        try!(util::set_nonblocking(fd.fd(), true));
+
+        static mut ID: atomics::AtomicUint = atomics::INIT_ATOMIC_UINT;
+        let id = unsafe { ID.fetch_add(1, atomics::Relaxed) };
+        let mut inner = box Inner {
+            id: id,
+            fd: fd,
+            recursive: recursive,
+            root: -1,
+            watches: HashMap::new(),
+            cb: cb,
+        };
+        let root = Path::new(path.as_bytes_no_nul());
+        inner.root = try!(inner.add_watch(&root));
+        if recursive {
+            inner.add_children(&root);
+        }
+
+        unsafe {
+            HELPER.boot(|| {}, helper);
+            HELPER.send(NewWatcher(inner));
+        }
+        Ok(Watcher { id: id })
+    }
+}
+
+impl Inner {
+    fn add_watch(&mut self, path: &Path) -> IoResult<libc::c_int> {
+        let wd = path.with_c_str(|p| unsafe {
+            c::inotify_add_watch(self.fd.fd(), p, WATCH_MASK)
+        });
+        if wd == -1 { return Err(super::last_error()) }
+        self.watches.insert(wd, path.clone());
+        Ok(wd)
+    }
+
+    // Watches every directory below `dir`. Errors are ignored, as entries may
+    // disappear while they're being walked.
+    fn add_children(&mut self, dir: &Path) {
+        let children = match file::readdir(&dir.to_c_str()) {
+            Ok(children) => children,
+            Err(..) => return,
+        };
+        for child in children.move_iter() {
+            match file::lstat(&child) {
+                Ok(ref stat) if is_dir(stat) => {}
+                _ => continue,
+            }
+            let child = Path::new(child.as_bytes_no_nul());
+            if self.add_watch(&child).is_ok() {
+                self.add_children(&child);
+            }
+        }
+    }
+
+    fn read_events(&mut self) {
+        // inotify pads each event so that the next one is aligned like the
+        // event structure itself, so the buffer needs the same alignment.
+        let mut buf = [0u32, ..1024];
+        let header = mem::size_of::<c::inotify_event>();
+        loop {
+            let n = unsafe {
+                libc::read(self.fd.fd(), buf.as_mut_ptr() as *mut libc::c_void,
+                           mem::size_of_val(&buf) as libc::size_t)
+            };
+            if n <= 0 { break }
+
+            let base = buf.as_ptr() as *const u8;
+            let mut offset = 0;
+            while offset + header <= n as uint {
+                unsafe {
+                    let event = base.offset(offset as int)
+                                    as *const c::inotify_event;
+                    let (wd, mask, len) = ((*event).wd, (*event).mask,
+                                           (*event).len as uint);
+                    let name = base.offset((offset + header) as int);
+                    slice::raw::buf_as_slice(name, len, |name| {
+                        let end = name.iter().position(|&b| b == 0)
+                                      .unwrap_or(len);
+                        self.handle(wd, mask, name.slice_to(end));
+                    });
+                    offset += header + len;
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, wd: libc::c_int, mask: u32, name: &[u8]) {
+        if mask & c::IN_Q_OVERFLOW != 0 { return }
+        let dir = match self.watches.find(&wd) {
+            Some(dir) => dir.clone(),
+            None => return,
+        };
+        if mask & c::IN_IGNORED != 0 {
+            self.watches.remove(&wd);
+            return
+        }
+        let path = if name.len() == 0 { dir } else { dir.join(name) };
+
+        let kind = if mask & c::IN_CREATE != 0 {
+            rtio::FsCreated
+        } else if mask & c::IN_DELETE != 0 {
+            rtio::FsRemoved
+        } else if mask & (c::IN_MOVED_FROM | c::IN_MOVED_TO) != 0 {
+            rtio::FsRenamed
+        } else if mask & (c::IN_MODIFY | c::IN_ATTRIB) != 0 {
+            rtio::FsModified
+        } else if wd != self.root {
+            return
+        } else if mask & c::IN_DELETE_SELF != 0 {
+            rtio::FsRemoved
+        } else {
+            rtio::FsRenamed
+        };
+
+        if self.recursive && mask & c::IN_ISDIR != 0 &&
+           mask & (c::IN_CREATE | c::IN_MOVED_TO) != 0 {
+            if self.add_watch(&path).is_ok() {
+                self.add_children(&path);
+            }
+        }
+        self.cb.call(rtio::FsEvent { path: path.to_c_str(), kind: kind });
+    }
+}
+
+fn is_dir(stat: &rtio::FileStat) -> bool {
+    stat.kind & libc::S_IFMT as u64 == libc::S_IFDIR as u64
+}
+
+fn helper(input: libc::c_int, messages: Receiver<Req>, _: ()) {
+    let mut fd = FileDesc::new(input, true);
+    let mut active: Vec<Box<Inner>> = Vec::new();
+
+    'outer: loop {
+        let mut set: c::fd_set = unsafe { mem::zeroed() };
+        let mut max = input;
+        c::fd_set(&mut set, input);
+        for inner in active.iter() {
+            c::fd_set(&mut set, inner.fd.fd());
+            max = cmp::max(max, inner.fd.fd());
+        }
+        match unsafe {
+            c::select(max + 1, &mut set, ptr::mut_null(), ptr::mut_null(),
+                      ptr::mut_null())
+        } {
+            -1 if os::errno() == libc::EINTR as int => continue,
+            -1 => fail!("helper thread failed in select() with error: {}",
+                        os::last_os_error()),
+            _ => {}
+        }
+
+        // The descriptors are non-blocking, so they can all just be read
+        // until they run dry.
+        for inner in active.mut_iter() {
+            inner.read_events();
+        }
+
+        loop {
+            match messages.try_recv() {
+                Err(comm::Disconnected) => {
+                    assert!(active.len() == 0);
+                    break 'outer;
+                }
+                Err(comm::Empty) => break,
+                Ok(req) => {
+                    // every request is paired with one byte on the pipe
+                    let mut buf = [0];
+                    assert_eq!(fd.inner_read(buf).ok().unwrap(), 1);
+                    match req {
+                        NewWatcher(inner) => active.push(inner),
+                        RemoveWatcher(id, ack) => {
+                            active.retain(|inner| inner.id != id);
+                            ack.send(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl rtio::RtioFsWatcher for Watcher {}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let (tx, rx) = channel();
+        unsafe { HELPER.send(RemoveWatcher(self.id, tx)); }
+        rx.recv();
+    }
+}
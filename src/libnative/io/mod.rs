@@ -73,6 +73,16 @@ pub mod pipe;
 #[path = "tty_win32.rs"]
 mod tty;
 
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[path = "poll_linux.rs"]
+pub mod poll;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[path = "fs_event_linux.rs"]
+pub mod fs_event;
+
 #[cfg(unix)]    #[path = "c_unix.rs"]  mod c;
 #[cfg(windows)] #[path = "c_win32.rs"] mod c;
 
@@ -201,6 +211,60 @@ impl rtio::IoFactory for IoFactory {
             box s as Box<rtio::RtioPipe + Send>
         })
     }
+    #[cfg(unix)]
+    fn unix_datagram_bind(&mut self, path: Option<&CString>)
+                          -> IoResult<Box<rtio::RtioUnixDatagram + Send>> {
+        pipe::UnixDatagram::bind(path).map(|s| {
+            box s as Box<rtio::RtioUnixDatagram + Send>
+        })
+    }
+    #[cfg(windows)]
+    fn unix_datagram_bind(&mut self, _path: Option<&CString>)
+                          -> IoResult<Box<rtio::RtioUnixDatagram + Send>> {
+        Err(unimpl())
+    }
+    fn tcp_from_raw_fd(&mut self, fd: c_int)
+                       -> IoResult<Box<rtio::RtioTcpStream + Send>> {
+        net::TcpStream::from_raw_fd(fd as net::sock_t).map(|s| {
+            box s as Box<rtio::RtioTcpStream + Send>
+        })
+    }
+    fn tcp_listener_from_raw_fd(&mut self, fd: c_int)
+                                -> IoResult<Box<rtio::RtioTcpListener + Send>> {
+        net::TcpListener::from_raw_fd(fd as net::sock_t).map(|s| {
+            box s as Box<rtio::RtioTcpListener + Send>
+        })
+    }
+    fn udp_from_raw_fd(&mut self, fd: c_int)
+                       -> IoResult<Box<rtio::RtioUdpSocket + Send>> {
+        net::UdpSocket::from_raw_fd(fd as net::sock_t).map(|u| {
+            box u as Box<rtio::RtioUdpSocket + Send>
+        })
+    }
+    #[cfg(unix)]
+    fn unix_from_raw_fd(&mut self, fd: c_int)
+                        -> IoResult<Box<rtio::RtioPipe + Send>> {
+        pipe::UnixStream::from_raw_fd(fd).map(|s| {
+            box s as Box<rtio::RtioPipe + Send>
+        })
+    }
+    #[cfg(windows)]
+    fn unix_from_raw_fd(&mut self, _fd: c_int)
+                        -> IoResult<Box<rtio::RtioPipe + Send>> {
+        Err(unimpl())
+    }
+    #[cfg(unix)]
+    fn unix_listener_from_raw_fd(&mut self, fd: c_int)
+                                 -> IoResult<Box<rtio::RtioUnixListener + Send>> {
+        pipe::UnixListener::from_raw_fd(fd).map(|s| {
+            box s as Box<rtio::RtioUnixListener + Send>
+        })
+    }
+    #[cfg(windows)]
+    fn unix_listener_from_raw_fd(&mut self, _fd: c_int)
+                                 -> IoResult<Box<rtio::RtioUnixListener + Send>> {
+        Err(unimpl())
+    }
     fn get_host_addresses(&mut self, host: Option<&str>, servname: Option<&str>,
                           hint: Option<rtio::AddrinfoHint>)
         -> IoResult<Vec<rtio::AddrinfoInfo>>
@@ -270,12 +334,14 @@ impl rtio::IoFactory for IoFactory {
     }
     fn spawn(&mut self, cfg: rtio::ProcessConfig)
             -> IoResult<(Box<rtio::RtioProcess + Send>,
-                         Vec<Option<Box<rtio::RtioPipe + Send>>>)> {
-        process::Process::spawn(cfg).map(|(p, io)| {
+                         Vec<Option<Box<rtio::RtioPipe + Send>>>,
+                         Option<Box<rtio::RtioPty + Send>>)> {
+        process::Process::spawn(cfg).map(|(p, io, pty)| {
             (box p as Box<rtio::RtioProcess + Send>,
              io.move_iter().map(|p| p.map(|p| {
                  box p as Box<rtio::RtioPipe + Send>
-             })).collect())
+             })).collect(),
+             pty.map(|p| box p as Box<rtio::RtioPty + Send>))
         })
     }
     fn kill(&mut self, pid: libc::pid_t, signum: int) -> IoResult<()> {
@@ -310,8 +376,39 @@ impl rtio::IoFactory for IoFactory {
             })
         }
     }
+    fn pty_open(&mut self)
+                -> IoResult<(Box<rtio::RtioPty + Send>, Box<rtio::RtioPty + Send>)> {
+        process::open_pty().map(|(master, slave)| {
+            (box master as Box<rtio::RtioPty + Send>,
+             box slave as Box<rtio::RtioPty + Send>)
+        })
+    }
     fn signal(&mut self, _signal: int, _cb: Box<rtio::Callback>)
               -> IoResult<Box<rtio::RtioSignal + Send>> {
         Err(unimpl())
     }
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        poll::Poller::new().map(|p| box p as Box<rtio::RtioPoller + Send>)
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        Err(unimpl())
+    }
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<rtio::FsEventCallback + Send>)
+        -> IoResult<Box<rtio::RtioFsWatcher + Send>> {
+        fs_event::Watcher::new(path, recursive, cb).map(|w| {
+            box w as Box<rtio::RtioFsWatcher + Send>
+        })
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    fn fs_watch(&mut self, _path: &CString, _recursive: bool,
+                _cb: Box<rtio::FsEventCallback + Send>)
+        -> IoResult<Box<rtio::RtioFsWatcher + Send>> {
+        Err(unimpl())
+    }
 }
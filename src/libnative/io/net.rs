@@ -10,7 +10,9 @@
 
 use alloc::arc::Arc;
 use libc;
+#[cfg(unix)] use std::cmp;
 use std::mem;
+#[cfg(unix)] use std::os;
 use std::rt::mutex;
 use std::rt::rtio;
 use std::rt::rtio::{IoResult, IoError};
@@ -134,6 +136,22 @@ pub fn getsockopt<T: Copy>(fd: sock_t, opt: libc::c_int,
     }
 }
 
+/// Checks that `fd`, a socket created elsewhere which is being adopted, is of
+/// type `ty`.
+pub fn check_socket_type(fd: sock_t, ty: libc::c_int) -> IoResult<()> {
+    #[cfg(unix)] use ERROR = libc::EINVAL;
+    #[cfg(windows)] use ERROR = libc::WSAEINVAL;
+
+    let actual: libc::c_int = try!(getsockopt(fd, libc::SOL_SOCKET, c::SO_TYPE));
+    if actual == ty { return Ok(()) }
+
+    Err(IoError {
+        code: ERROR as uint,
+        extra: 0,
+        detail: Some("the socket is of the wrong type".to_str()),
+    })
+}
+
 #[cfg(windows)]
 pub fn last_error() -> IoError {
     use std::os;
@@ -295,6 +313,12 @@ impl TcpStream {
         }
     }
 
+    pub fn from_raw_fd(fd: sock_t) -> IoResult<TcpStream> {
+        let ret = TcpStream::new(Inner::new(fd));
+        try!(check_socket_type(fd, libc::SOCK_STREAM));
+        Ok(ret)
+    }
+
     fn new(inner: Inner) -> TcpStream {
         TcpStream {
             inner: Arc::new(inner),
@@ -381,6 +405,39 @@ impl rtio::RtioTcpStream for TcpStream {
             Err(e) => Err(e)
         }
     }
+    #[cfg(unix)]
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        readv(fd, self.read_deadline, bufs, dolock)
+    }
+    #[cfg(unix)]
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        writev(fd, self.write_deadline, bufs, dolock)
+    }
+    #[cfg(unix)]
+    fn sendfile(&mut self, file: libc::c_int, offset: u64,
+                len: u64) -> IoResult<Option<u64>> {
+        // sendfile() can't be told not to block, so write timeouts are left
+        // to the caller's fallback.
+        if self.write_deadline != 0 { return Ok(None) }
+        let fd = self.fd();
+        let mut sent = 0;
+        while sent < len {
+            match retry(|| sendfile(fd, file, offset + sent, len - sent)) {
+                -1 if sent == 0 && sendfile_unsupported() => return Ok(None),
+                -1 if util::wouldblock() => {
+                    try!(util::await(fd, None, util::Writable));
+                }
+                -1 => return Err(last_error()),
+                0 => break,
+                n => sent += n as u64,
+            }
+        }
+        Ok(Some(sent))
+    }
     fn peer_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getpeername)
     }
@@ -429,6 +486,7 @@ impl rtio::RtioSocket for TcpStream {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn raw_fd(&self) -> libc::c_int { self.fd() as libc::c_int }
 }
 
 impl Drop for Inner {
@@ -473,6 +531,12 @@ impl TcpListener {
         }
     }
 
+    pub fn from_raw_fd(fd: sock_t) -> IoResult<TcpListener> {
+        let ret = TcpListener { inner: Inner::new(fd) };
+        try!(check_socket_type(fd, libc::SOCK_STREAM));
+        Ok(ret)
+    }
+
     pub fn fd(&self) -> sock_t { self.inner.fd }
 
     pub fn native_listen(self, backlog: int) -> IoResult<TcpAcceptor> {
@@ -495,6 +559,7 @@ impl rtio::RtioSocket for TcpListener {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn raw_fd(&self) -> libc::c_int { self.fd() as libc::c_int }
 }
 
 pub struct TcpAcceptor {
@@ -530,6 +595,7 @@ impl rtio::RtioSocket for TcpAcceptor {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn raw_fd(&self) -> libc::c_int { self.fd() as libc::c_int }
 }
 
 impl rtio::RtioTcpAcceptor for TcpAcceptor {
@@ -573,6 +639,16 @@ impl UdpSocket {
         }
     }
 
+    pub fn from_raw_fd(fd: sock_t) -> IoResult<UdpSocket> {
+        let ret = UdpSocket {
+            inner: Arc::new(Inner::new(fd)),
+            read_deadline: 0,
+            write_deadline: 0,
+        };
+        try!(check_socket_type(fd, libc::SOCK_DGRAM));
+        Ok(ret)
+    }
+
     pub fn fd(&self) -> sock_t { self.inner.fd }
 
     pub fn set_broadcast(&mut self, on: bool) -> IoResult<()> {
@@ -624,6 +700,7 @@ impl rtio::RtioSocket for UdpSocket {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn raw_fd(&self) -> libc::c_int { self.fd() as libc::c_int }
 }
 
 #[cfg(windows)] type msglen_t = libc::c_int;
@@ -895,3 +972,127 @@ pub fn write<T>(fd: sock_t,
         Ok(written)
     }
 }
+
+// The vectored counterparts of read() and write(), built on recvmsg() and
+// sendmsg() so that MSG_DONTWAIT can be passed in the same way.
+
+#[cfg(unix)]
+pub fn readv<T>(fd: sock_t,
+                deadline: u64,
+                bufs: &mut [&mut [u8]],
+                lock: || -> T) -> IoResult<uint> {
+    let iovs = util::iovecs_mut(bufs);
+    if iovs.len() == 0 { return Ok(0) }
+    let doread = |nb| unsafe {
+        let flags = if nb {c::MSG_DONTWAIT} else {0};
+        let mut msg = util::msghdr(iovs.as_slice());
+        c::recvmsg(fd, &mut msg, flags) as libc::c_int
+    };
+    read(fd, deadline, lock, doread)
+}
+
+#[cfg(unix)]
+pub fn writev<T>(fd: sock_t,
+                 deadline: u64,
+                 bufs: &[&[u8]],
+                 lock: || -> T) -> IoResult<()> {
+    fn sendmsg(fd: sock_t, iovs: &[c::iovec], nb: bool) -> libc::c_int {
+        let flags = if nb {c::MSG_DONTWAIT} else {0};
+        let msg = util::msghdr(iovs);
+        unsafe { c::sendmsg(fd, &msg, flags) as libc::c_int }
+    }
+
+    let mut iovs = util::iovecs(bufs);
+    let mut start = 0;
+    let mut written = 0;
+    let deadline = match deadline {
+        0 => None,
+        n => Some(n),
+    };
+    // Without a deadline the socket is written to in blocking mode, unless
+    // it turns out to be nonblocking (see above), after which the waiting
+    // strategy of write() is used for the rest of the data.
+    let mut blocking = deadline.is_none();
+    while start < iovs.len() {
+        let ret = {
+            let iovs = iovs.slice_from(start);
+            let mut ret = -1;
+            if blocking {
+                ret = retry(|| sendmsg(fd, iovs, false));
+                if ret == -1 && util::wouldblock() { blocking = false; }
+            }
+            if !blocking {
+                match util::await(fd, deadline, util::Writable) {
+                    Err(ref e) if e.code == libc::EOF as uint && written > 0 => {
+                        assert!(deadline.is_some());
+                        return Err(util::short_write(written, "short write"))
+                    }
+                    Err(e) => return Err(e),
+                    Ok(()) => {}
+                }
+                let _guard = lock();
+                ret = retry(|| sendmsg(fd, iovs, deadline.is_some()));
+                if ret == -1 && util::wouldblock() { continue }
+            }
+            ret
+        };
+        match ret {
+            -1 => return Err(last_error()),
+            n => {
+                written += n as uint;
+                start = util::advance_iovecs(iovs.as_mut_slice(), start,
+                                             n as uint);
+            }
+        }
+    }
+    Ok(())
+}
+
+// sendfile() sends up to `len` bytes of `file` from `offset` in one call,
+// returning how many were sent, which can be short before the end of the
+// file, or -1 on error. Single calls are capped at the most linux accepts.
+
+#[cfg(unix)]
+static SENDFILE_MAX: u64 = 0x7ffff000;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+fn sendfile(sock: sock_t, file: libc::c_int, offset: u64,
+            len: u64) -> libc::c_int {
+    let mut offset = offset as libc::off_t;
+    let len = cmp::min(len, SENDFILE_MAX) as libc::size_t;
+    unsafe { c::sendfile(sock, file, &mut offset, len) as libc::c_int }
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+fn sendfile(sock: sock_t, file: libc::c_int, offset: u64,
+            len: u64) -> libc::c_int {
+    let mut sent = cmp::min(len, SENDFILE_MAX) as libc::off_t;
+    let ret = unsafe {
+        c::sendfile(file, sock, offset as libc::off_t, &mut sent,
+                    0 as *mut libc::c_void, 0)
+    };
+    // partial sends are reported along with EAGAIN and EINTR
+    if ret == -1 && sent == 0 {-1} else {sent as libc::c_int}
+}
+
+#[cfg(target_os = "freebsd")]
+fn sendfile(sock: sock_t, file: libc::c_int, offset: u64,
+            len: u64) -> libc::c_int {
+    let mut sent: libc::off_t = 0;
+    let ret = unsafe {
+        c::sendfile(file, sock, offset as libc::off_t,
+                    cmp::min(len, SENDFILE_MAX) as libc::size_t,
+                    0 as *mut libc::c_void, &mut sent, 0)
+    };
+    if ret == -1 && sent == 0 {-1} else {sent as libc::c_int}
+}
+
+// Whether the last sendfile() failed because the file or socket can't be
+// used with it, rather than because of an I/O error.
+#[cfg(unix)]
+fn sendfile_unsupported() -> bool {
+    let err = os::errno() as libc::c_int;
+    err == libc::EINVAL || err == libc::ENOSYS
+}
@@ -10,8 +10,10 @@
 
 use alloc::arc::Arc;
 use libc;
-use std::c_str::CString;
+use std::c_str::{CString, ToCStr};
+use std::cmp;
 use std::mem;
+use std::ptr;
 use std::rt::mutex;
 use std::rt::rtio;
 use std::rt::rtio::{IoResult, IoError};
@@ -107,6 +109,98 @@ fn bind(addr: &CString, ty: libc::c_int) -> IoResult<Inner> {
     }
 }
 
+fn sockaddr_un_to_addr(storage: &libc::sockaddr_storage,
+                      len: uint) -> Option<CString> {
+    let s: &libc::sockaddr_un = unsafe { mem::transmute(storage) };
+    let offset = s.sun_path.as_ptr() as uint - s as *const _ as uint;
+    if len <= offset { return None }
+
+    // The path may or may not include its null terminator
+    let path = s.sun_path.slice_to(cmp::min(len - offset, s.sun_path.len()));
+    let path = path.iter().take_while(|c| **c != 0).map(|c| *c as u8)
+                   .collect::<Vec<u8>>();
+    if path.len() == 0 { None } else { Some(path.as_slice().to_c_str()) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Passing file descriptors and credentials
+////////////////////////////////////////////////////////////////////////////////
+
+fn cmsg_align(len: uint) -> uint {
+    (len + c::CMSG_ALIGN - 1) & !(c::CMSG_ALIGN - 1)
+}
+
+// Allocates space for a control message carrying `nfds` file descriptors,
+// returning the buffer and its length in bytes. The buffer is made of words
+// so that it's suitably aligned for a cmsghdr.
+fn cmsg_buffer(nfds: uint) -> (Vec<libc::size_t>, uint) {
+    let len = cmsg_align(mem::size_of::<c::cmsghdr>()) +
+              cmsg_align(nfds * mem::size_of::<libc::c_int>());
+    let word = mem::size_of::<libc::size_t>();
+    (Vec::from_elem((len + word - 1) / word, 0 as libc::size_t), len)
+}
+
+// Copies the file descriptors passed in the control messages of `msg` to the
+// front of `fds`, returning how many there were. Any which don't fit are
+// closed.
+unsafe fn take_fds(msg: &c::msghdr, fds: &mut [libc::c_int]) -> uint {
+    let hdr = cmsg_align(mem::size_of::<c::cmsghdr>());
+    let control = msg.msg_control as *const u8;
+    let end = msg.msg_controllen as uint;
+    let mut offset = 0;
+    let mut n = 0;
+    while offset + hdr <= end {
+        let cmsg = &*(control.offset(offset as int) as *const c::cmsghdr);
+        let len = cmp::min(cmsg.cmsg_len as uint, end - offset);
+        if len < hdr { break }
+
+        if cmsg.cmsg_level == libc::SOL_SOCKET &&
+           cmsg.cmsg_type == c::SCM_RIGHTS {
+            let data = control.offset((offset + hdr) as int)
+                              as *const libc::c_int;
+            for i in range(0, (len - hdr) / mem::size_of::<libc::c_int>()) {
+                let fd = *data.offset(i as int);
+                if n < fds.len() {
+                    // Already done by MSG_CMSG_CLOEXEC where it's supported
+                    let _ = libc::ioctl(fd, c::FIOCLEX);
+                    fds[n] = fd;
+                    n += 1;
+                } else {
+                    let _ = libc::close(fd);
+                }
+            }
+        }
+        offset += cmsg_align(len);
+    }
+    n
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+fn peer_credentials(fd: fd_t) -> IoResult<rtio::PeerCredentials> {
+    let cred: c::ucred = try!(net::getsockopt(fd, libc::SOL_SOCKET,
+                                              c::SO_PEERCRED));
+    Ok(rtio::PeerCredentials {
+        pid: Some(cred.pid),
+        uid: cred.uid as u64,
+        gid: cred.gid as u64,
+    })
+}
+
+#[cfg(not(target_os = "linux"), not(target_os = "android"))]
+fn peer_credentials(fd: fd_t) -> IoResult<rtio::PeerCredentials> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    match unsafe { c::getpeereid(fd, &mut uid, &mut gid) } {
+        -1 => Err(super::last_error()),
+        _ => Ok(rtio::PeerCredentials {
+            pid: None,
+            uid: uid as u64,
+            gid: gid as u64,
+        })
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Unix Streams
 ////////////////////////////////////////////////////////////////////////////////
@@ -125,6 +219,12 @@ impl UnixStream {
         })
     }
 
+    pub fn from_raw_fd(fd: fd_t) -> IoResult<UnixStream> {
+        let ret = UnixStream::new(Arc::new(Inner::new(fd)));
+        try!(net::check_socket_type(fd, libc::SOCK_STREAM));
+        Ok(ret)
+    }
+
     fn new(inner: Arc<Inner>) -> UnixStream {
         UnixStream {
             inner: inner,
@@ -179,10 +279,101 @@ impl rtio::RtioPipe for UnixStream {
         }
     }
 
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        net::readv(fd, self.read_deadline, bufs, dolock)
+    }
+
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        net::writev(fd, self.write_deadline, bufs, dolock)
+    }
+
+    fn send_with_fds(&mut self, buf: &[u8],
+                     fds: &[libc::c_int]) -> IoResult<()> {
+        // The descriptors can only be sent along with some data
+        if buf.len() == 0 && fds.len() > 0 {
+            return Err(IoError {
+                code: libc::EINVAL as uint,
+                extra: 0,
+                detail: Some("file descriptors must be sent with data".to_str()),
+            })
+        }
+
+        let (mut control, controllen) = cmsg_buffer(fds.len());
+        let hdr = cmsg_align(mem::size_of::<c::cmsghdr>());
+        unsafe {
+            let cmsg = control.as_mut_ptr() as *mut c::cmsghdr;
+            (*cmsg).cmsg_len = (hdr + fds.len() * mem::size_of::<libc::c_int>())
+                                   as c::cmsglen_t;
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = c::SCM_RIGHTS;
+            let data = (cmsg as *mut u8).offset(hdr as int) as *mut libc::c_int;
+            ptr::copy_nonoverlapping_memory(data, fds.as_ptr(), fds.len());
+        }
+
+        // The control message is attached to the first write which succeeds,
+        // and the rest of the data is sent as normal.
+        let mut attached = fds.len() == 0;
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        let dowrite = |nb: bool, buf: *const u8, len: uint| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            let iovs = [c::iovec {
+                iov_base: buf as *mut libc::c_void,
+                iov_len: len as libc::size_t,
+            }];
+            let mut msg = util::msghdr(iovs);
+            if !attached {
+                msg.msg_control = control.as_ptr() as *mut libc::c_void;
+                msg.msg_controllen = controllen as c::controllen_t;
+            }
+            let ret = c::sendmsg(fd, &msg, flags) as i64;
+            if ret > 0 { attached = true; }
+            ret
+        };
+        match net::write(fd, self.write_deadline, buf, true, dolock, dowrite) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn recv_with_fds(&mut self, buf: &mut [u8],
+                     fds: &mut [libc::c_int]) -> IoResult<(uint, uint)> {
+        let (mut control, controllen) = cmsg_buffer(fds.len());
+        let iovs = [c::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len() as libc::size_t,
+        }];
+        let mut msg = util::msghdr(iovs);
+        let n = {
+            let fd = self.fd();
+            let dolock = || self.lock_nonblocking();
+            let doread = |nb| unsafe {
+                let flags = c::MSG_CMSG_CLOEXEC |
+                            if nb {c::MSG_DONTWAIT} else {0};
+                // recvmsg() shrinks the control length to what it received
+                msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+                msg.msg_controllen = controllen as c::controllen_t;
+                c::recvmsg(fd, &mut msg, flags) as libc::c_int
+            };
+            try!(net::read(fd, self.read_deadline, dolock, doread))
+        };
+        Ok((n, unsafe { take_fds(&msg, fds) }))
+    }
+
+    fn peer_credentials(&mut self) -> IoResult<rtio::PeerCredentials> {
+        peer_credentials(self.fd())
+    }
+
     fn clone(&self) -> Box<rtio::RtioPipe + Send> {
         box UnixStream::new(self.inner.clone()) as Box<rtio::RtioPipe + Send>
     }
 
+    fn raw_fd(&self) -> libc::c_int { self.fd() }
+
     fn close_write(&mut self) -> IoResult<()> {
         super::mkerr_libc(unsafe { libc::shutdown(self.fd(), libc::SHUT_WR) })
     }
@@ -202,22 +393,159 @@ impl rtio::RtioPipe for UnixStream {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// Unix Datagrams
+////////////////////////////////////////////////////////////////////////////////
+
+struct DatagramInner {
+    inner: Inner,
+    // As with listeners, the path the socket was bound to is removed once it's
+    // closed.
+    path: Option<CString>,
+}
+
+impl Drop for DatagramInner {
+    fn drop(&mut self) {
+        match self.path {
+            Some(ref path) => unsafe { let _ = libc::unlink(path.as_ptr()); },
+            None => {}
+        }
+    }
+}
+
+pub struct UnixDatagram {
+    inner: Arc<DatagramInner>,
+    read_deadline: u64,
+    write_deadline: u64,
+}
+
+impl UnixDatagram {
+    pub fn bind(addr: Option<&CString>) -> IoResult<UnixDatagram> {
+        let inner = match addr {
+            Some(addr) => try!(bind(addr, libc::SOCK_DGRAM)),
+            None => Inner::new(try!(unix_socket(libc::SOCK_DGRAM))),
+        };
+        Ok(UnixDatagram {
+            inner: Arc::new(DatagramInner {
+                inner: inner,
+                path: addr.map(|addr| addr.clone()),
+            }),
+            read_deadline: 0,
+            write_deadline: 0,
+        })
+    }
+
+    fn fd(&self) -> fd_t { self.inner.inner.fd }
+
+    #[cfg(target_os = "linux")]
+    fn lock_nonblocking(&self) {}
+
+    #[cfg(not(target_os = "linux"))]
+    fn lock_nonblocking<'a>(&'a self) -> net::Guard<'a> {
+        let ret = net::Guard {
+            fd: self.fd(),
+            guard: unsafe { self.inner.inner.lock.lock() },
+        };
+        assert!(util::set_nonblocking(self.fd(), true).is_ok());
+        ret
+    }
+}
+
+impl rtio::RtioUnixDatagram for UnixDatagram {
+    fn recv_from(&mut self, buf: &mut [u8])
+                 -> IoResult<(uint, Option<CString>)> {
+        let fd = self.fd();
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let storagep = &mut storage as *mut _ as *mut libc::sockaddr;
+        let mut addrlen: libc::socklen_t =
+                mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        let dolock = || self.lock_nonblocking();
+        let n = try!(net::read(fd, self.read_deadline, dolock, |nb| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::recvfrom(fd,
+                           buf.as_mut_ptr() as *mut libc::c_void,
+                           buf.len() as libc::size_t,
+                           flags,
+                           storagep,
+                           &mut addrlen) as libc::c_int
+        }));
+        Ok((n, sockaddr_un_to_addr(&storage, addrlen as uint)))
+    }
+
+    fn send_to(&mut self, buf: &[u8], dst: &CString) -> IoResult<()> {
+        let (dst, dstlen) = try!(addr_to_sockaddr_un(dst));
+        let dstp = &dst as *const _ as *const libc::sockaddr;
+        let dstlen = dstlen as libc::socklen_t;
+
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        let dowrite = |nb, buf: *const u8, len: uint| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::sendto(fd,
+                         buf as *const libc::c_void,
+                         len as libc::size_t,
+                         flags,
+                         dstp,
+                         dstlen) as i64
+        };
+
+        let n = try!(net::write(fd, self.write_deadline, buf, false, dolock,
+                                dowrite));
+        if n != buf.len() {
+            Err(util::short_write(n, "couldn't send entire packet at once"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn clone(&self) -> Box<rtio::RtioUnixDatagram + Send> {
+        box UnixDatagram {
+            inner: self.inner.clone(),
+            read_deadline: 0,
+            write_deadline: 0,
+        } as Box<rtio::RtioUnixDatagram + Send>
+    }
+
+    fn raw_fd(&self) -> libc::c_int { self.fd() }
+
+    fn set_timeout(&mut self, timeout: Option<u64>) {
+        let deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+        self.read_deadline = deadline;
+        self.write_deadline = deadline;
+    }
+    fn set_read_timeout(&mut self, timeout: Option<u64>) {
+        self.read_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+    fn set_write_timeout(&mut self, timeout: Option<u64>) {
+        self.write_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Unix Listener
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct UnixListener {
     inner: Inner,
-    path: CString,
+    // The path the listener was bound to, which is removed when it's closed.
+    // This isn't known for sockets which were adopted.
+    path: Option<CString>,
 }
 
 impl UnixListener {
     pub fn bind(addr: &CString) -> IoResult<UnixListener> {
         bind(addr, libc::SOCK_STREAM).map(|fd| {
-            UnixListener { inner: fd, path: addr.clone() }
+            UnixListener { inner: fd, path: Some(addr.clone()) }
         })
     }
 
+    pub fn from_raw_fd(fd: fd_t) -> IoResult<UnixListener> {
+        let ret = UnixListener { inner: Inner::new(fd), path: None };
+        try!(net::check_socket_type(fd, libc::SOCK_STREAM));
+        Ok(ret)
+    }
+
     fn fd(&self) -> fd_t { self.inner.fd }
 
     pub fn native_listen(self, backlog: int) -> IoResult<UnixAcceptor> {
@@ -234,6 +562,8 @@ impl rtio::RtioUnixListener for UnixListener {
             box a as Box<rtio::RtioUnixAcceptor + Send>
         })
     }
+
+    fn raw_fd(&self) -> libc::c_int { self.fd() }
 }
 
 pub struct UnixAcceptor {
@@ -270,6 +600,8 @@ impl rtio::RtioUnixAcceptor for UnixAcceptor {
     fn set_timeout(&mut self, timeout: Option<u64>) {
         self.deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
     }
+
+    fn raw_fd(&self) -> libc::c_int { self.fd() }
 }
 
 impl Drop for UnixListener {
@@ -277,8 +609,9 @@ impl Drop for UnixListener {
         // Unlink the path to the socket to ensure that it doesn't linger. We're
         // careful to unlink the path before we close the file descriptor to
         // prevent races where we unlink someone else's path.
-        unsafe {
-            let _ = libc::unlink(self.path.as_ptr());
+        match self.path {
+            Some(ref path) => unsafe { let _ = libc::unlink(path.as_ptr()); },
+            None => {}
         }
     }
 }
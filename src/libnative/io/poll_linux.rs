@@ -0,0 +1,98 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness polling for linux, implemented with epoll.
+//!
+//! Descriptors are registered level-triggered, so a descriptor which is left
+//! ready (for example because not all of its data was read) will show up again
+//! in the next call to `poll`. The token of each registration is stored in the
+//! `data` field of its epoll event.
+
+use libc;
+use std::cmp;
+use std::i32;
+use std::mem;
+use std::rt::rtio;
+use std::rt::rtio::IoResult;
+
+use io::c;
+use io::file::FileDesc;
+
+// The most events collected from the kernel in a single call to epoll_wait.
+static MAX_EVENTS: uint = 256;
+
+pub struct Poller {
+    fd: FileDesc,
+}
+
+impl Poller {
+    pub fn new() -> IoResult<Poller> {
+        match unsafe { c::epoll_create1(c::EPOLL_CLOEXEC) } {
+            -1 => Err(super::last_error()),
+            fd => Ok(Poller { fd: FileDesc::new(fd, true) }),
+        }
+    }
+
+    fn ctl(&mut self, op: libc::c_int, fd: libc::c_int, token: uint,
+           readable: bool, writable: bool) -> IoResult<()> {
+        let mut events = 0;
+        if readable { events |= c::EPOLLIN }
+        if writable { events |= c::EPOLLOUT }
+        let mut event = c::epoll_event { events: events, data: token as u64 };
+        super::mkerr_libc(unsafe {
+            c::epoll_ctl(self.fd.fd(), op, fd, &mut event)
+        })
+    }
+}
+
+impl rtio::RtioPoller for Poller {
+    fn register(&mut self, fd: libc::c_int, token: uint,
+                readable: bool, writable: bool) -> IoResult<()> {
+        self.ctl(c::EPOLL_CTL_ADD, fd, token, readable, writable)
+    }
+
+    fn reregister(&mut self, fd: libc::c_int, token: uint,
+                  readable: bool, writable: bool) -> IoResult<()> {
+        self.ctl(c::EPOLL_CTL_MOD, fd, token, readable, writable)
+    }
+
+    fn deregister(&mut self, fd: libc::c_int) -> IoResult<()> {
+        // Kernels before 2.6.9 require a non-null event even though it is
+        // ignored.
+        self.ctl(c::EPOLL_CTL_DEL, fd, 0, false, false)
+    }
+
+    fn poll(&mut self, events: &mut Vec<rtio::PollEvent>,
+            timeout_ms: Option<u64>) -> IoResult<()> {
+        let timeout = match timeout_ms {
+            Some(ms) => cmp::min(ms, i32::MAX as u64) as libc::c_int,
+            None => -1,
+        };
+        let mut ready: [c::epoll_event, ..MAX_EVENTS] = unsafe { mem::zeroed() };
+        let n = super::retry(|| unsafe {
+            c::epoll_wait(self.fd.fd(), ready.as_mut_ptr(),
+                          MAX_EVENTS as libc::c_int, timeout)
+        });
+        if n == -1 {
+            return Err(super::last_error())
+        }
+
+        for event in ready.slice_to(n as uint).iter() {
+            let flags = event.events;
+            events.push(rtio::PollEvent {
+                token: event.data as uint,
+                readable: flags & c::EPOLLIN != 0,
+                writable: flags & c::EPOLLOUT != 0,
+                hangup: flags & (c::EPOLLHUP | c::EPOLLERR) != 0,
+            });
+        }
+        Ok(())
+    }
+}
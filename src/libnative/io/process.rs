@@ -15,6 +15,7 @@ use std::io;
 use std::mem;
 use std::os;
 use std::ptr;
+use std::rt::pty;
 use std::rt::rtio::{ProcessConfig, IoResult, IoError};
 use std::rt::rtio;
 
@@ -64,8 +65,12 @@ impl Process {
     /// Creates a new process using native process-spawning abilities provided
     /// by the OS. Operations on this process will be blocking instead of using
     /// the runtime for sleeping just this current task.
+    ///
+    /// All of the stdio streams configured as `CreatePty` share a single
+    /// pseudo-terminal, the master end of which is returned alongside the
+    /// pipes.
     pub fn spawn(cfg: ProcessConfig)
-        -> IoResult<(Process, Vec<Option<file::FileDesc>>)>
+        -> IoResult<(Process, Vec<Option<file::FileDesc>>, Option<Pty>)>
     {
         // right now we only handle stdin/stdout/stderr.
         if cfg.extra_io.len() > 0 {
@@ -73,7 +78,8 @@ impl Process {
         }
 
         fn get_io(io: rtio::StdioContainer,
-                  ret: &mut Vec<Option<file::FileDesc>>)
+                  ret: &mut Vec<Option<file::FileDesc>>,
+                  pty: &mut Option<(Pty, Pty)>)
             -> IoResult<Option<file::FileDesc>>
         {
             match io {
@@ -92,14 +98,24 @@ impl Process {
                     ret.push(Some(ours));
                     Ok(Some(theirs))
                 }
+                rtio::CreatePty => {
+                    if pty.is_none() {
+                        *pty = Some(try!(open_pty()));
+                    }
+                    let &(_, ref slave) = pty.get_ref();
+                    ret.push(None);
+                    Ok(Some(file::FileDesc::new(slave.fd.fd(), false)))
+                }
             }
         }
 
         let mut ret_io = Vec::new();
-        let res = spawn_process_os(cfg,
-                                   try!(get_io(cfg.stdin, &mut ret_io)),
-                                   try!(get_io(cfg.stdout, &mut ret_io)),
-                                   try!(get_io(cfg.stderr, &mut ret_io)));
+        let mut pty = None;
+        let stdin = try!(get_io(cfg.stdin, &mut ret_io, &mut pty));
+        let stdout = try!(get_io(cfg.stdout, &mut ret_io, &mut pty));
+        let stderr = try!(get_io(cfg.stderr, &mut ret_io, &mut pty));
+        let ctty = pty.as_ref().map(|&(_, ref slave)| slave.fd.fd());
+        let res = spawn_process_os(cfg, stdin, stdout, stderr, ctty);
 
         match res {
             Ok(res) => {
@@ -110,7 +126,9 @@ impl Process {
                     exit_signal: None,
                     deadline: 0,
                 };
-                Ok((p, ret_io))
+                // The child has its own copies of the slave, which have to be
+                // the only ones left for the master to see it hang up.
+                Ok((p, ret_io, pty.map(|(master, _slave)| master)))
             }
             Err(e) => Err(e)
         }
@@ -222,6 +240,58 @@ fn pipe() -> IoResult<(file::FileDesc, file::FileDesc)> {
     }
 }
 
+/// One end of a pseudo-terminal.
+pub struct Pty {
+    fd: file::FileDesc,
+}
+
+/// Opens a new pseudo-terminal, returning its master and slave ends.
+#[cfg(unix)]
+pub fn open_pty() -> IoResult<(Pty, Pty)> {
+    let (master, slave) = try!(pty::open(super::last_error));
+    Ok((Pty { fd: file::FileDesc::new(master, true) },
+        Pty { fd: file::FileDesc::new(slave, true) }))
+}
+
+#[cfg(windows)]
+pub fn open_pty() -> IoResult<(Pty, Pty)> {
+    Err(super::unimpl())
+}
+
+impl rtio::RtioPty for Pty {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        match self.fd.inner_read(buf) {
+            Err(ref e) if pty::is_hangup(e.code) => Err(util::eof()),
+            ret => ret,
+        }
+    }
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.fd.inner_write(buf)
+    }
+    fn clone(&self) -> Box<rtio::RtioPty + Send> {
+        box Pty { fd: self.fd.clone() } as Box<rtio::RtioPty + Send>
+    }
+    fn raw_fd(&self) -> c_int { self.fd.fd() }
+
+    #[cfg(unix)]
+    fn set_winsize(&mut self, width: int, height: int) -> IoResult<()> {
+        pty::set_winsize(self.fd.fd(), width, height, super::last_error)
+    }
+    #[cfg(windows)]
+    fn set_winsize(&mut self, _width: int, _height: int) -> IoResult<()> {
+        Err(super::unimpl())
+    }
+
+    #[cfg(unix)]
+    fn get_winsize(&mut self) -> IoResult<(int, int)> {
+        pty::get_winsize(self.fd.fd(), super::last_error)
+    }
+    #[cfg(windows)]
+    fn get_winsize(&mut self) -> IoResult<(int, int)> {
+        Err(super::unimpl())
+    }
+}
+
 #[cfg(windows)]
 unsafe fn killpid(pid: pid_t, signal: int) -> IoResult<()> {
     let handle = libc::OpenProcess(libc::PROCESS_TERMINATE |
@@ -276,7 +346,8 @@ struct SpawnProcessResult {
 fn spawn_process_os(cfg: ProcessConfig,
                     in_fd: Option<file::FileDesc>,
                     out_fd: Option<file::FileDesc>,
-                    err_fd: Option<file::FileDesc>)
+                    err_fd: Option<file::FileDesc>,
+                    _ctty: Option<c_int>)
                  -> IoResult<SpawnProcessResult> {
     use libc::types::os::arch::extra::{DWORD, HANDLE, STARTUPINFO};
     use libc::consts::os::extra::{
@@ -304,6 +375,14 @@ fn spawn_process_os(cfg: ProcessConfig,
             detail: Some("unsupported gid/uid requested on windows".to_str()),
         })
     }
+    if cfg.process_group.is_some() || cfg.rlimits.len() > 0 {
+        return Err(IoError {
+            code: libc::ERROR_CALL_NOT_IMPLEMENTED as uint,
+            extra: 0,
+            detail: Some("unsupported process group/resource limits \
+                          requested on windows".to_str()),
+        })
+    }
 
     unsafe {
         let mut si = zeroed_startupinfo();
@@ -505,10 +584,11 @@ fn make_command_line(prog: &CString, args: &[CString]) -> String {
 fn spawn_process_os(cfg: ProcessConfig,
                     in_fd: Option<file::FileDesc>,
                     out_fd: Option<file::FileDesc>,
-                    err_fd: Option<file::FileDesc>)
+                    err_fd: Option<file::FileDesc>,
+                    ctty: Option<c_int>)
                 -> IoResult<SpawnProcessResult>
 {
-    use libc::funcs::posix88::unistd::{fork, dup2, close, chdir, execvp};
+    use libc::funcs::posix88::unistd::{fork, dup2, close, chdir, execvp, setpgid};
     use libc::funcs::bsd44::getdtablesize;
     use io::c;
 
@@ -530,8 +610,30 @@ fn spawn_process_os(cfg: ProcessConfig,
         environ = envp;
     }
 
+    // Anything that doesn't fit in an rlim_t, u64::MAX included, is no limit
+    fn rlim(limit: u64) -> c::rlim_t {
+        if limit >= c::RLIM_INFINITY as u64 {
+            c::RLIM_INFINITY
+        } else {
+            limit as c::rlim_t
+        }
+    }
+
+    fn rlimit_resource(resource: rtio::RlimitResource) -> c_int {
+        match resource {
+            rtio::RlimitCpuTime => c::RLIMIT_CPU,
+            rtio::RlimitFileSize => c::RLIMIT_FSIZE,
+            rtio::RlimitDataSize => c::RLIMIT_DATA,
+            rtio::RlimitStackSize => c::RLIMIT_STACK,
+            rtio::RlimitCoreSize => c::RLIMIT_CORE,
+            rtio::RlimitProcesses => c::RLIMIT_NPROC,
+            rtio::RlimitOpenFiles => c::RLIMIT_NOFILE,
+            rtio::RlimitAddressSpace => c::RLIMIT_AS,
+        }
+    }
+
     unsafe fn set_cloexec(fd: c_int) {
-        let ret = c::ioctl(fd, c::FIOCLEX);
+        let ret = libc::ioctl(fd, c::FIOCLEX);
         assert_eq!(ret, 0);
     }
 
@@ -624,6 +726,19 @@ fn spawn_process_os(cfg: ProcessConfig,
 
             rustrt::rust_unset_sigprocmask();
 
+            // A child attached to a pseudo-terminal leads a new session which
+            // has the terminal as its controlling terminal, like a login
+            // shell would.
+            match ctty {
+                Some(fd) => {
+                    if libc::setsid() == -1 ||
+                       libc::ioctl(fd, libc::TIOCSCTTY, 0 as c_int) == -1 {
+                        fail(&mut output);
+                    }
+                }
+                None => {}
+            }
+
             // If a stdio file descriptor is set to be ignored (via a -1 file
             // descriptor), then we don't actually close it, but rather open
             // up /dev/null into that file descriptor. Otherwise, the first file
@@ -662,6 +777,18 @@ fn spawn_process_os(cfg: ProcessConfig,
                 }
             }
 
+            // Limits are applied while we may still have the privileges to
+            // raise them.
+            for &(resource, soft, hard) in cfg.rlimits.iter() {
+                let limit = c::rlimit {
+                    rlim_cur: rlim(soft),
+                    rlim_max: rlim(hard),
+                };
+                if c::setrlimit(rlimit_resource(resource), &limit) != 0 {
+                    fail(&mut output);
+                }
+            }
+
             match cfg.gid {
                 Some(u) => {
                     if libc::setgid(u as libc::gid_t) != 0 {
@@ -697,6 +824,17 @@ fn spawn_process_os(cfg: ProcessConfig,
                 // error, but ignore it anyway.
                 let _ = libc::setsid();
             }
+            match cfg.process_group {
+                // A new session has already put the child in a group of its
+                // own, and its leader can't move to another one.
+                Some(0) if cfg.detach || ctty.is_some() => {}
+                Some(pgid) => {
+                    if setpgid(0, pgid) != 0 {
+                        fail(&mut output);
+                    }
+                }
+                None => {}
+            }
             if !dirp.is_null() && chdir(dirp) == -1 {
                 fail(&mut output);
             }
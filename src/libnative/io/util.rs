@@ -9,6 +9,7 @@
 // except according to those terms.
 
 use libc;
+#[cfg(unix)] use std::cmp;
 use std::mem;
 use std::os;
 use std::ptr;
@@ -74,7 +75,7 @@ pub fn wouldblock() -> bool {
 #[cfg(unix)]
 pub fn set_nonblocking(fd: net::sock_t, nb: bool) -> IoResult<()> {
     let set = nb as libc::c_int;
-    super::mkerr_libc(retry(|| unsafe { c::ioctl(fd, c::FIONBIO, &set) }))
+    super::mkerr_libc(retry(|| unsafe { libc::ioctl(fd, c::FIONBIO, &set) }))
 }
 
 #[cfg(windows)]
@@ -189,3 +190,55 @@ pub fn await(fd: net::sock_t, deadline: Option<u64>,
         _ => Ok(()),
     }
 }
+
+/// Describes `bufs` as a list of iovecs, leaving out any empty buffers.
+#[cfg(unix)]
+pub fn iovecs(bufs: &[&[u8]]) -> Vec<c::iovec> {
+    bufs.iter().filter(|buf| buf.len() > 0).map(|buf| {
+        c::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len() as libc::size_t,
+        }
+    }).collect()
+}
+
+#[cfg(unix)]
+pub fn iovecs_mut(bufs: &mut [&mut [u8]]) -> Vec<c::iovec> {
+    bufs.mut_iter().filter(|buf| buf.len() > 0).map(|buf| {
+        c::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len() as libc::size_t,
+        }
+    }).collect()
+}
+
+/// A message header for sendmsg() or recvmsg() carrying as many of `iovs` as
+/// fit in a single call.
+#[cfg(unix)]
+pub fn msghdr(iovs: &[c::iovec]) -> c::msghdr {
+    let mut msg: c::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = iovs.as_ptr() as *mut c::iovec;
+    msg.msg_iovlen = cmp::min(iovs.len(), c::IOV_MAX) as c::iovlen_t;
+    msg
+}
+
+/// Consumes `n` bytes from the front of `iovs[start..]` after a short write,
+/// returning the index of the first iovec with data left in it.
+#[cfg(unix)]
+pub fn advance_iovecs(iovs: &mut [c::iovec], mut start: uint,
+                      mut n: uint) -> uint {
+    while n > 0 {
+        let iov = &mut iovs[start];
+        let len = iov.iov_len as uint;
+        if n < len {
+            iov.iov_base = unsafe {
+                (iov.iov_base as *mut u8).offset(n as int) as *mut libc::c_void
+            };
+            iov.iov_len = (len - n) as libc::size_t;
+            break
+        }
+        n -= len;
+        start += 1;
+    }
+    start
+}
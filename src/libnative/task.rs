@@ -16,6 +16,7 @@
 
 use std::any::Any;
 use std::mem;
+use std::num::Saturating;
 use std::rt::bookkeeping;
 use std::rt::local::Local;
 use std::rt::mutex::NativeMutex;
@@ -23,6 +24,7 @@ use std::rt::rtio;
 use std::rt::stack;
 use std::rt::task::{Task, BlockedTask, TaskOpts};
 use std::rt::thread::Thread;
+use std::rt::time;
 use std::rt;
 
 use io;
@@ -248,6 +250,70 @@ impl rt::Runtime for Ops {
         Local::put(cur_task);
     }
 
+    // This is the same as `deschedule` above, except that one more selectable
+    // handle than was asked for is created and held onto by this function.
+    // Once the deadline has passed we attempt to wake ourselves up through that
+    // handle. If that succeeds then nobody else owns the task and we can stop
+    // waiting, otherwise some other thread has already taken ownership and is
+    // about to signal us, so we wait (without a timeout) for it to do so.
+    fn deschedule_timeout(mut ~self, times: uint, timeout_ms: u64,
+                          mut cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>)
+                          -> rtio::IoResult<()> {
+        let me = &mut *self as *mut Ops;
+        cur_task.put_runtime(self);
+
+        unsafe {
+            let cur_task_dupe = &mut *cur_task as *mut Task;
+            let task = BlockedTask::block(cur_task);
+
+            let mut iter = task.make_selectable(times + 1);
+            let mut timeout = iter.next();
+            let deadline = time::precise_time_ms().saturating_add(timeout_ms);
+            let guard = (*me).lock.lock();
+            (*me).awoken = false;
+
+            match iter.map(f).filter_map(|a| a.err()).next() {
+                None => {}
+                Some(task) => {
+                    match task.wake() {
+                        Some(task) => {
+                            mem::forget(task);
+                            (*me).awoken = true;
+                        }
+                        None => {}
+                    }
+                }
+            }
+            while !(*me).awoken {
+                let now = time::precise_time_ms();
+                match timeout.take() {
+                    Some(handle) => {
+                        if now < deadline {
+                            timeout = Some(handle);
+                            guard.wait_timeout(deadline - now);
+                        } else {
+                            match handle.wake() {
+                                Some(task) => {
+                                    mem::forget(task);
+                                    (*me).awoken = true;
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    None => guard.wait(),
+                }
+            }
+            // re-acquire ownership of the task
+            cur_task = mem::transmute(cur_task_dupe);
+        }
+
+        // put the task back in TLS, and everything is as it once was.
+        Local::put(cur_task);
+        Ok(())
+    }
+
     // See the comments on `deschedule` for why the task is forgotten here, and
     // why it's valid to do so.
     fn reawaken(mut ~self, mut to_wake: Box<Task>) {
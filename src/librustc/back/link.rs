@@ -51,6 +51,8 @@ pub enum OutputType {
     OutputTypeLlvmAssembly,
     OutputTypeObject,
     OutputTypeExe,
+    OutputTypeMetadata,
+    OutputTypeDepInfo,
 }
 
 pub fn llvm_err(sess: &Session, msg: String) -> ! {
@@ -98,15 +100,18 @@ pub mod write {
     use driver::session::Session;
     use driver::config;
     use lib::llvm::llvm;
-    use lib::llvm::{ModuleRef, TargetMachineRef, PassManagerRef};
+    use lib::llvm::{ContextRef, ModuleRef, TargetMachineRef, PassManagerRef};
     use lib;
     use util::common::time;
     use syntax::abi;
 
-    use std::c_str::ToCStr;
-    use std::io::{Command};
-    use libc::{c_uint, c_int};
+    use std::c_str::{ToCStr, CString};
+    use std::io::{Command, File};
+    use libc::{c_char, c_uint, c_int, size_t};
+    use std::ptr;
     use std::str;
+    use std::task::TaskBuilder;
+    use std::sync::Arc;
 
     // On android, we by default compile for armv7 processors. This enables
     // things like double word CAS instructions (rather than emulating them)
@@ -140,73 +145,29 @@ pub mod write {
                       trans: &CrateTranslation,
                       output_types: &[OutputType],
                       output: &OutputFilenames) {
-        let llmod = trans.module;
-        let llcx = trans.context;
         unsafe {
             configure_llvm(sess);
 
+            if trans.modules.len() > 1 {
+                run_parallel_passes(sess, trans, output_types, output);
+                if sess.time_llvm_passes() { llvm::LLVMRustPrintPassTimings(); }
+                return
+            }
+            let (llcx, llmod) = *trans.modules.get(0);
+
             if sess.opts.cg.save_temps {
                 output.with_extension("no-opt.bc").with_c_str(|buf| {
                     llvm::LLVMWriteBitcodeToFile(llmod, buf);
                 })
             }
 
-            let opt_level = match sess.opts.optimize {
-              config::No => lib::llvm::CodeGenLevelNone,
-              config::Less => lib::llvm::CodeGenLevelLess,
-              config::Default => lib::llvm::CodeGenLevelDefault,
-              config::Aggressive => lib::llvm::CodeGenLevelAggressive,
-            };
-            let use_softfp = sess.opts.cg.soft_float;
-
-            // FIXME: #11906: Omitting frame pointers breaks retrieving the value of a parameter.
-            // FIXME: #11954: mac64 unwinding may not work with fp elim
-            let no_fp_elim = (sess.opts.debuginfo != NoDebugInfo) ||
-                             (sess.targ_cfg.os == abi::OsMacos &&
-                              sess.targ_cfg.arch == abi::X86_64);
-
-            // OSX has -dead_strip, which doesn't rely on ffunction_sections
-            // FIXME(#13846) this should be enabled for windows
-            let ffunction_sections = sess.targ_cfg.os != abi::OsMacos &&
-                                     sess.targ_cfg.os != abi::OsWin32;
-            let fdata_sections = ffunction_sections;
-
-            let reloc_model = match sess.opts.cg.relocation_model.as_slice() {
-                "pic" => lib::llvm::RelocPIC,
-                "static" => lib::llvm::RelocStatic,
-                "default" => lib::llvm::RelocDefault,
-                "dynamic-no-pic" => lib::llvm::RelocDynamicNoPic,
-                _ => {
-                    sess.err(format!("{} is not a valid relocation mode",
-                                     sess.opts
-                                         .cg
-                                         .relocation_model).as_slice());
-                    sess.abort_if_errors();
-                    return;
-                }
+            let tm_config = match target_machine_config(sess) {
+                Some(config) => config,
+                None => return,
             };
 
-            let tm = sess.targ_cfg
-                         .target_strs
-                         .target_triple
-                         .as_slice()
-                         .with_c_str(|t| {
-                sess.opts.cg.target_cpu.as_slice().with_c_str(|cpu| {
-                    target_feature(sess).with_c_str(|features| {
-                        llvm::LLVMRustCreateTargetMachine(
-                            t, cpu, features,
-                            lib::llvm::CodeModelDefault,
-                            reloc_model,
-                            opt_level,
-                            true /* EnableSegstk */,
-                            use_softfp,
-                            no_fp_elim,
-                            ffunction_sections,
-                            fdata_sections,
-                        )
-                    })
-                })
-            });
+            let opt_level = tm_config.opt_level;
+            let tm = tm_config.create();
 
             // Create the two optimizing pass managers. These mirror what clang
             // does, and are by populated by LLVM's default PassManagerBuilder.
@@ -270,25 +231,6 @@ pub mod write {
                 }
             }
 
-            // A codegen-specific pass manager is used to generate object
-            // files for an LLVM module.
-            //
-            // Apparently each of these pass managers is a one-shot kind of
-            // thing, so we create a new one for each type of output. The
-            // pass manager passed to the closure should be ensured to not
-            // escape the closure itself, and the manager should only be
-            // used once.
-            fn with_codegen(tm: TargetMachineRef, llmod: ModuleRef,
-                            no_builtins: bool, f: |PassManagerRef|) {
-                unsafe {
-                    let cpm = llvm::LLVMCreatePassManager();
-                    llvm::LLVMRustAddAnalysisPasses(tm, cpm, llmod);
-                    llvm::LLVMRustAddLibraryInfo(cpm, llmod, no_builtins);
-                    f(cpm);
-                    llvm::LLVMDisposePassManager(cpm);
-                }
-            }
-
             let mut object_file = None;
             let mut needs_metadata = false;
             for output_type in output_types.iter() {
@@ -330,6 +272,7 @@ pub mod write {
                         object_file = Some(output.temp_path(OutputTypeObject));
                         needs_metadata = true;
                     }
+                    OutputTypeMetadata | OutputTypeDepInfo => {}
                 }
             }
 
@@ -363,6 +306,374 @@ pub mod write {
         }
     }
 
+    // A codegen-specific pass manager is used to generate object files for an
+    // LLVM module.
+    //
+    // Apparently each of these pass managers is a one-shot kind of thing, so
+    // we create a new one for each type of output. The pass manager passed to
+    // the closure should be ensured to not escape the closure itself, and the
+    // manager should only be used once.
+    fn with_codegen(tm: TargetMachineRef, llmod: ModuleRef,
+                    no_builtins: bool, f: |PassManagerRef|) {
+        unsafe {
+            let cpm = llvm::LLVMCreatePassManager();
+            llvm::LLVMRustAddAnalysisPasses(tm, cpm, llmod);
+            llvm::LLVMRustAddLibraryInfo(cpm, llmod, no_builtins);
+            f(cpm);
+            llvm::LLVMDisposePassManager(cpm);
+        }
+    }
+
+    /// The parameters a target machine is created from. Unlike the target
+    /// machine itself, these can be shared with the tasks optimizing codegen
+    /// units in parallel, each of which needs a target machine of its own.
+    struct TargetMachineConfig {
+        triple: String,
+        cpu: String,
+        features: String,
+        reloc_model: lib::llvm::RelocMode,
+        opt_level: lib::llvm::CodeGenOptLevel,
+        use_softfp: bool,
+        no_fp_elim: bool,
+        ffunction_sections: bool,
+        fdata_sections: bool,
+    }
+
+    impl TargetMachineConfig {
+        unsafe fn create(&self) -> TargetMachineRef {
+            self.triple.as_slice().with_c_str(|t| {
+                self.cpu.as_slice().with_c_str(|cpu| {
+                    self.features.as_slice().with_c_str(|features| {
+                        llvm::LLVMRustCreateTargetMachine(
+                            t, cpu, features,
+                            lib::llvm::CodeModelDefault,
+                            self.reloc_model,
+                            self.opt_level,
+                            true /* EnableSegstk */,
+                            self.use_softfp,
+                            self.no_fp_elim,
+                            self.ffunction_sections,
+                            self.fdata_sections,
+                        )
+                    })
+                })
+            })
+        }
+    }
+
+    fn target_machine_config(sess: &Session) -> Option<TargetMachineConfig> {
+        let opt_level = match sess.opts.optimize {
+          config::No => lib::llvm::CodeGenLevelNone,
+          config::Less => lib::llvm::CodeGenLevelLess,
+          config::Default => lib::llvm::CodeGenLevelDefault,
+          config::Aggressive => lib::llvm::CodeGenLevelAggressive,
+        };
+
+        // FIXME: #11906: Omitting frame pointers breaks retrieving the value of a parameter.
+        // FIXME: #11954: mac64 unwinding may not work with fp elim
+        let no_fp_elim = (sess.opts.debuginfo != NoDebugInfo) ||
+                         (sess.targ_cfg.os == abi::OsMacos &&
+                          sess.targ_cfg.arch == abi::X86_64);
+
+        // OSX has -dead_strip, which doesn't rely on ffunction_sections
+        // FIXME(#13846) this should be enabled for windows
+        let ffunction_sections = sess.targ_cfg.os != abi::OsMacos &&
+                                 sess.targ_cfg.os != abi::OsWin32;
+        let fdata_sections = ffunction_sections;
+
+        let reloc_model = match sess.opts.cg.relocation_model.as_slice() {
+            "pic" => lib::llvm::RelocPIC,
+            "static" => lib::llvm::RelocStatic,
+            "default" => lib::llvm::RelocDefault,
+            "dynamic-no-pic" => lib::llvm::RelocDynamicNoPic,
+            _ => {
+                sess.err(format!("{} is not a valid relocation mode",
+                                 sess.opts
+                                     .cg
+                                     .relocation_model).as_slice());
+                sess.abort_if_errors();
+                return None;
+            }
+        };
+
+        Some(TargetMachineConfig {
+            triple: sess.targ_cfg.target_strs.target_triple.clone(),
+            cpu: sess.opts.cg.target_cpu.clone(),
+            features: target_feature(sess).to_string(),
+            reloc_model: reloc_model,
+            opt_level: opt_level,
+            use_softfp: sess.opts.cg.soft_float,
+            no_fp_elim: no_fp_elim,
+            ffunction_sections: ffunction_sections,
+            fdata_sections: fdata_sections,
+        })
+    }
+
+    /// Everything the task optimizing and emitting a codegen unit needs,
+    /// shared between all of the tasks.
+    struct UnitConfig {
+        tm: TargetMachineConfig,
+        passes: Vec<String>,
+        prepopulate_passes: bool,
+        verify: bool,
+        no_builtins: bool,
+        save_temps: bool,
+        emit_bitcode: bool,
+    }
+
+    // Optimizes and emits each of the codegen units trans split the crate
+    // into on a task of its own, then combines the resulting object files
+    // into the single object file the linker expects. Each unit's module
+    // lives in an LLVM context of its own, so the tasks don't share any LLVM
+    // state. Functions can be inlined within a unit, but not across units.
+    unsafe fn run_parallel_passes(sess: &Session,
+                                  trans: &CrateTranslation,
+                                  output_types: &[OutputType],
+                                  output: &OutputFilenames) {
+        let tm_config = match target_machine_config(sess) {
+            Some(config) => config,
+            None => return,
+        };
+
+        let mut object_file = None;
+        let mut needs_metadata = false;
+        for output_type in output_types.iter() {
+            match *output_type {
+                OutputTypeObject => {
+                    object_file = Some(output.path(OutputTypeObject));
+                }
+                OutputTypeExe => {
+                    object_file = Some(output.temp_path(OutputTypeObject));
+                    needs_metadata = true;
+                }
+                _ => {}
+            }
+        }
+
+        // The metadata module belongs to the context of the last unit, so it
+        // has to be emitted before that unit's task takes the context over.
+        if needs_metadata {
+            let tm = tm_config.create();
+            time(sess.time_passes(), "codegen passes", (), |()| {
+                with_codegen(tm, trans.metadata_module,
+                             trans.no_builtins, |cpm| {
+                    let out = output.temp_path(OutputTypeObject)
+                                    .with_extension("metadata.o");
+                    write_output_file(sess, tm, cpm,
+                                      trans.metadata_module, &out,
+                                      lib::llvm::ObjectFile);
+                })
+            });
+            llvm::LLVMRustDisposeTargetMachine(tm);
+        }
+        llvm::LLVMDisposeModule(trans.metadata_module);
+
+        let object_file = match object_file {
+            Some(path) => path,
+            None => {
+                for &(llcx, llmod) in trans.modules.iter() {
+                    llvm::LLVMDisposeModule(llmod);
+                    llvm::LLVMContextDispose(llcx);
+                }
+                return
+            }
+        };
+
+        // Unknown passes are reported here rather than once per unit.
+        let pm = llvm::LLVMCreatePassManager();
+        let passes = sess.opts.cg.passes.iter().filter(|pass| {
+            let known = pass.as_slice().with_c_str(|s| {
+                llvm::LLVMRustAddPass(pm, s)
+            });
+            if !known {
+                sess.warn(format!("unknown pass {}, ignoring",
+                                  *pass).as_slice());
+            }
+            known
+        }).map(|pass| pass.clone()).collect();
+        llvm::LLVMDisposePassManager(pm);
+
+        let config = Arc::new(UnitConfig {
+            tm: tm_config,
+            passes: passes,
+            prepopulate_passes: !sess.opts.cg.no_prepopulate_passes,
+            verify: !sess.no_verify(),
+            no_builtins: trans.no_builtins,
+            save_temps: sess.opts.cg.save_temps,
+            emit_bitcode: sess.opts.cg.save_temps ||
+                          (sess.crate_types.borrow().contains(&config::CrateTypeRlib) &&
+                           sess.opts.output_types.contains(&OutputTypeExe)),
+        });
+
+        let units = trans.modules.len();
+        let objects: Vec<Path> = range(0, units).map(|unit| {
+            object_file.with_extension(format!("{}.o", unit))
+        }).collect();
+        let bitcodes: Vec<Path> = range(0, units).map(|unit| {
+            output.temp_path(OutputTypeBitcode)
+                  .with_extension(format!("{}.bc", unit))
+        }).collect();
+
+        time(sess.time_passes(), "parallel llvm passes", (), |()| {
+            let (tx, rx) = channel();
+            for (unit, &(llcx, llmod)) in trans.modules.iter().enumerate() {
+                let config = config.clone();
+                let tx = tx.clone();
+                let object = objects.get(unit).clone();
+                let bitcode = bitcodes.get(unit).clone();
+                TaskBuilder::new().named(format!("codegen-unit-{}", unit))
+                                  .spawn(proc() {
+                    let result = unsafe {
+                        optimize_and_emit_unit(&*config, llcx, llmod,
+                                               &object, &bitcode)
+                    };
+                    tx.send((unit, result));
+                });
+            }
+            drop(tx);
+
+            for _ in range(0, units) {
+                match rx.recv_opt() {
+                    Ok((_, Ok(()))) => {}
+                    Ok((unit, Err(e))) => {
+                        sess.err(format!("failed to emit codegen unit {}: {}",
+                                         unit, e).as_slice());
+                    }
+                    Err(()) => {
+                        sess.bug("a codegen unit's task failed");
+                    }
+                }
+            }
+        });
+        sess.abort_if_errors();
+
+        time(sess.time_passes(), "combining codegen units", (), |()| {
+            link_objects(sess, objects.as_slice(), &object_file);
+            if config.emit_bitcode {
+                link_bitcode(sess, bitcodes.as_slice(),
+                             &output.temp_path(OutputTypeBitcode));
+            }
+        });
+        if !sess.opts.cg.save_temps {
+            for path in objects.iter() {
+                super::remove(sess, path);
+            }
+            if config.emit_bitcode {
+                for path in bitcodes.iter() {
+                    super::remove(sess, path);
+                }
+            }
+        }
+    }
+
+    // Optimizes and emits a single codegen unit, disposing of its module and
+    // context afterwards. This runs on a task of its own, so errors are
+    // returned rather than reported to the session.
+    unsafe fn optimize_and_emit_unit(config: &UnitConfig,
+                                     llcx: ContextRef,
+                                     llmod: ModuleRef,
+                                     object: &Path,
+                                     bitcode: &Path) -> Result<(), String> {
+        if config.save_temps {
+            bitcode.with_extension("no-opt.bc").with_c_str(|buf| {
+                llvm::LLVMWriteBitcodeToFile(llmod, buf);
+            })
+        }
+
+        let tm = config.tm.create();
+
+        let fpm = llvm::LLVMCreateFunctionPassManagerForModule(llmod);
+        let mpm = llvm::LLVMCreatePassManager();
+        if config.verify {
+            "verify".with_c_str(|s| llvm::LLVMRustAddPass(fpm, s));
+        }
+        if config.prepopulate_passes {
+            llvm::LLVMRustAddAnalysisPasses(tm, fpm, llmod);
+            llvm::LLVMRustAddAnalysisPasses(tm, mpm, llmod);
+            populate_llvm_passes(fpm, mpm, llmod, config.tm.opt_level,
+                                 config.no_builtins);
+        }
+        for pass in config.passes.iter() {
+            pass.as_slice().with_c_str(|s| llvm::LLVMRustAddPass(mpm, s));
+        }
+        llvm::LLVMRustRunFunctionPassManager(fpm, llmod);
+        llvm::LLVMRunPassManager(mpm, llmod);
+        llvm::LLVMDisposePassManager(fpm);
+        llvm::LLVMDisposePassManager(mpm);
+
+        if config.emit_bitcode {
+            bitcode.with_c_str(|buf| {
+                llvm::LLVMWriteBitcodeToFile(llmod, buf);
+            })
+        }
+
+        let mut written = false;
+        object.with_c_str(|output| {
+            with_codegen(tm, llmod, config.no_builtins, |cpm| {
+                written = llvm::LLVMRustWriteOutputFile(
+                    tm, cpm, llmod, output, lib::llvm::ObjectFile);
+            })
+        });
+        llvm::LLVMRustDisposeTargetMachine(tm);
+        llvm::LLVMDisposeModule(llmod);
+        llvm::LLVMContextDispose(llcx);
+
+        if written {
+            Ok(())
+        } else {
+            Err(llvm_err_msg("could not write output"))
+        }
+    }
+
+    // Like `llvm_err`, for tasks which can't report to the session.
+    unsafe fn llvm_err_msg(msg: &str) -> String {
+        let cstr = llvm::LLVMRustGetLastError();
+        if cstr == ptr::null() {
+            msg.to_string()
+        } else {
+            let err = CString::new(cstr, true);
+            let err = str::from_utf8_lossy(err.as_bytes());
+            format!("{}: {}", msg, err.as_slice())
+        }
+    }
+
+    // Combines the object files of the codegen units into a single
+    // relocatable object file.
+    fn link_objects(sess: &Session, inputs: &[Path], output: &Path) {
+        let (pname, mut cmd) = super::linker_cmd(sess);
+        cmd.arg("-nostdlib").arg("-Wl,-r").arg("-o").arg(output).args(inputs);
+        super::run_linker(sess, pname.as_slice(), &cmd);
+    }
+
+    // Combines the bitcode of the codegen units into the single bitcode file
+    // which is inserted into rlibs for LTO.
+    unsafe fn link_bitcode(sess: &Session, inputs: &[Path], output: &Path) {
+        let llcx = llvm::LLVMContextCreate();
+        let llmod = "bitcode".with_c_str(|name| {
+            llvm::LLVMModuleCreateWithNameInContext(name, llcx)
+        });
+        for path in inputs.iter() {
+            let bc = match File::open(path).read_to_end() {
+                Ok(bc) => bc,
+                Err(e) => {
+                    sess.fatal(format!("failed to read {}: {}",
+                                       path.display(), e).as_slice())
+                }
+            };
+            if !llvm::LLVMRustLinkInExternalBitcode(llmod,
+                                                    bc.as_ptr() as *const c_char,
+                                                    bc.len() as size_t) {
+                super::llvm_err(sess, format!("failed to load bc of {}",
+                                              path.display()));
+            }
+        }
+        output.with_c_str(|buf| {
+            llvm::LLVMWriteBitcodeToFile(llmod, buf);
+        });
+        llvm::LLVMDisposeModule(llmod);
+        llvm::LLVMContextDispose(llcx);
+    }
+
     pub fn run_assembler(sess: &Session, outputs: &OutputFilenames) {
         let pname = super::get_cc_prog(sess);
         let mut cmd = Command::new(pname.as_slice());
@@ -789,6 +1100,10 @@ pub fn get_cc_prog(sess: &Session) -> String {
         Some(ref linker) => return linker.to_string(),
         None => {}
     }
+    match sess.targ_cfg.target_opts.linker {
+        Some(ref linker) => return linker.to_string(),
+        None => {}
+    }
 
     // In the future, FreeBSD will use clang as default compiler.
     // It would be flexible to use cc (system's default C compiler)
@@ -826,6 +1141,9 @@ pub fn link_binary(sess: &Session,
                    crate_name: &str) -> Vec<Path> {
     let mut out_filenames = Vec::new();
     for &crate_type in sess.crate_types.borrow().iter() {
+        if crate_type != config::CrateTypeRlib {
+            check_no_metadata_only_crates(sess);
+        }
         if invalid_output_for_target(sess, crate_type) {
             sess.bug(format!("invalid output type `{}` for target os `{}`",
                              crate_type, sess.targ_cfg.os).as_slice());
@@ -848,6 +1166,53 @@ pub fn link_binary(sess: &Session,
 }
 
 
+// Crates built with `--emit=metadata` contain no code, so they can only be
+// used to compile other libraries, not linked.
+fn check_no_metadata_only_crates(sess: &Session) {
+    let crates = sess.cstore.get_used_crates(cstore::RequireStatic);
+    for &(cnum, ref path) in crates.iter() {
+        match *path {
+            Some(ref p) if p.filename_str().unwrap().ends_with(".rmeta") => {
+                let name = sess.cstore.get_crate_data(cnum).name.clone();
+                sess.err(format!("crate `{}` only contains metadata ({}), \
+                                  the rlib is needed to link it",
+                                 name, p.display()).as_slice());
+            }
+            _ => {}
+        }
+    }
+    sess.abort_if_errors();
+}
+
+/// Returns the name of the metadata-only artifact written by
+/// `--emit=metadata`, in the directory of `out_filename`.
+pub fn filename_for_metadata(sess: &Session, name: &str,
+                             out_filename: &Path) -> Path {
+    let libname = format!("{}{}", name, sess.opts.cg.extra_filename);
+    out_filename.with_filename(format!("lib{}.rmeta", libname))
+}
+
+/// Writes `metadata` out as a metadata-only artifact. This is an rlib without
+/// any object files or bytecode, which is enough to compile crates depending
+/// on this one.
+pub fn write_metadata_file(sess: &Session, metadata: &[u8], out_filename: &Path) {
+    let tmpdir = TempDir::new("rustc").expect("needs a temp dir");
+    let file = tmpdir.path().join(METADATA_FILENAME);
+    match fs::File::create(&file).write(metadata) {
+        Ok(..) => {}
+        Err(e) => {
+            sess.err(format!("failed to write {}: {}",
+                             file.display(),
+                             e).as_slice());
+            sess.abort_if_errors();
+        }
+    }
+    if out_filename.exists() {
+        remove(sess, out_filename);
+    }
+    Archive::create(sess, out_filename, &file);
+}
+
 /// Returns default crate type for target
 ///
 /// Default crate type is used when crate type isn't provided neither
@@ -869,6 +1234,7 @@ pub fn invalid_output_for_target(sess: &Session,
                                  crate_type: config::CrateType) -> bool {
     match (sess.targ_cfg.os, crate_type) {
         (abi::OsiOS, config::CrateTypeDylib) => true,
+        (_, config::CrateTypeDylib) => !sess.targ_cfg.target_opts.dynamic_linking,
         _ => false
     }
 }
@@ -1127,11 +1493,7 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
                  obj_filename: &Path, out_filename: &Path) {
     let tmpdir = TempDir::new("rustc").expect("needs a temp dir");
 
-    // The invocations of cc share some flags across platforms
-    let pname = get_cc_prog(sess);
-    let mut cmd = Command::new(pname.as_slice());
-
-    cmd.args(sess.targ_cfg.target_strs.cc_args.as_slice());
+    let (pname, mut cmd) = linker_cmd(sess);
     link_args(&mut cmd, sess, dylib, tmpdir.path(),
               trans, obj_filename, out_filename);
 
@@ -1142,8 +1504,37 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
     // May have not found libraries in the right formats.
     sess.abort_if_errors();
 
-    // Invoke the system linker
-    debug!("{}", &cmd);
+    run_linker(sess, pname.as_slice(), &cmd);
+
+
+    // On OSX, debuggers need this utility to get run to do some munging of
+    // the symbols
+    if (sess.targ_cfg.os == abi::OsMacos || sess.targ_cfg.os == abi::OsiOS)
+        && (sess.opts.debuginfo != NoDebugInfo) {
+            match Command::new("dsymutil").arg(out_filename).status() {
+                Ok(..) => {}
+                Err(e) => {
+                    sess.err(format!("failed to run dsymutil: {}", e).as_slice());
+                    sess.abort_if_errors();
+                }
+            }
+        }
+}
+
+// The invocations of cc share some flags across platforms, and across the
+// different things the linker is used for.
+fn linker_cmd(sess: &Session) -> (String, Command) {
+    let pname = get_cc_prog(sess);
+    let mut cmd = Command::new(pname.as_slice());
+
+    cmd.args(sess.targ_cfg.target_strs.cc_args.as_slice());
+    cmd.args(sess.targ_cfg.target_opts.pre_link_args.as_slice());
+    (pname, cmd)
+}
+
+// Invokes the system linker, aborting if it fails.
+fn run_linker(sess: &Session, pname: &str, cmd: &Command) {
+    debug!("{}", cmd);
     let prog = time(sess.time_passes(), "running linker", (), |()| cmd.output());
     match prog {
         Ok(prog) => {
@@ -1151,7 +1542,7 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
                 sess.err(format!("linking with `{}` failed: {}",
                                  pname,
                                  prog.status).as_slice());
-                sess.note(format!("{}", &cmd).as_slice());
+                sess.note(format!("{}", cmd).as_slice());
                 let mut output = prog.error.clone();
                 output.push_all(prog.output.as_slice());
                 sess.note(str::from_utf8(output.as_slice()).unwrap()
@@ -1166,20 +1557,6 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
             sess.abort_if_errors();
         }
     }
-
-
-    // On OSX, debuggers need this utility to get run to do some munging of
-    // the symbols
-    if (sess.targ_cfg.os == abi::OsMacos || sess.targ_cfg.os == abi::OsiOS)
-        && (sess.opts.debuginfo != NoDebugInfo) {
-            match Command::new("dsymutil").arg(out_filename).status() {
-                Ok(..) => {}
-                Err(e) => {
-                    sess.err(format!("failed to run dsymutil: {}", e).as_slice());
-                    sess.abort_if_errors();
-                }
-            }
-        }
 }
 
 fn link_args(cmd: &mut Command,
@@ -1213,17 +1590,22 @@ fn link_args(cmd: &mut Command,
     // all contents of this library. This way we're guaranteed that the linker
     // will include the __morestack symbol 100% of the time, always resolving
     // references to it even if the object above didn't use it.
-    match sess.targ_cfg.os {
-        abi::OsMacos | abi::OsiOS => {
-            let morestack = lib_path.join("libmorestack.a");
+    //
+    // Targets without stack growth (see `back::target`) don't need any of
+    // this.
+    if sess.targ_cfg.target_opts.morestack {
+        match sess.targ_cfg.os {
+            abi::OsMacos | abi::OsiOS => {
+                let morestack = lib_path.join("libmorestack.a");
 
-            let mut v = "-Wl,-force_load,".as_bytes().to_owned();
-            v.push_all(morestack.as_vec());
-            cmd.arg(v.as_slice());
-        }
-        _ => {
-            cmd.args(["-Wl,--whole-archive", "-lmorestack",
-                      "-Wl,--no-whole-archive"]);
+                let mut v = "-Wl,-force_load,".as_bytes().to_owned();
+                v.push_all(morestack.as_vec());
+                cmd.arg(v.as_slice());
+            }
+            _ => {
+                cmd.args(["-Wl,--whole-archive", "-lmorestack",
+                          "-Wl,--no-whole-archive"]);
+            }
         }
     }
 
@@ -1401,6 +1783,7 @@ fn link_args(cmd: &mut Command,
     for arg in sess.cstore.get_used_link_args().borrow().iter() {
         cmd.arg(arg.as_slice());
     }
+    cmd.args(sess.targ_cfg.target_opts.post_link_args.as_slice());
 }
 
 // # Native library linking
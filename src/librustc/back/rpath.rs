@@ -47,11 +47,12 @@ pub fn get_rpath_flags(sess: &Session, out_filename: &Path) -> Vec<String> {
         l.map(|p| p.clone())
     }).collect::<Vec<_>>();
 
+    let target_triple = sess.targ_cfg.target_strs.target_triple.as_slice();
     let rpaths = get_rpaths(os,
                             sysroot,
                             output,
                             libs.as_slice(),
-                            sess.opts.target_triple.as_slice());
+                            target_triple);
     flags.push_all(rpaths_to_flags(rpaths.as_slice()).as_slice());
     flags
 }
@@ -0,0 +1,211 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Target specification files
+//!
+//! In addition to the target triples the compiler knows about, `--target` may
+//! name a JSON file describing the target. This makes it possible to compile
+//! for a target (a custom bare-metal board, say) without teaching rustc about
+//! it first. A specification looks like:
+//!
+//! ```json
+//! {
+//!     "llvm-target": "thumbv7m-none-eabi",
+//!     "data-layout": "e-p:32:32:32-i1:8:8-i8:8:8-i16:16:16-i32:32:32-...",
+//!     "target-pointer-width": "32",
+//!     "arch": "arm",
+//!     "os": "linux",
+//!     "target-family": "unix",
+//!     "linker": "arm-none-eabi-gcc",
+//!     "pre-link-args": ["-mcpu=cortex-m3", "-mthumb"],
+//!     "post-link-args": ["-Tlayout.ld"],
+//!     "dynamic-linking": false,
+//!     "morestack": false
+//! }
+//! ```
+//!
+//! The first five keys are required. `arch` and `os` must be one of the
+//! architectures and operating systems rustc knows how to generate code for,
+//! as they determine calling conventions and the like. The remaining keys are
+//! optional and default to the values used for the built-in targets.
+
+use std::io::File;
+use serialize::json;
+use syntax::abi;
+
+/// Target-dependent settings which are only ever overridden by a target
+/// specification file. The built-in targets all use `default_options()`.
+#[deriving(Clone)]
+pub struct TargetOptions {
+    /// Linker to invoke instead of the system default, `-C linker` takes
+    /// precedence over this.
+    pub linker: Option<String>,
+    /// Arguments passed to the linker before any others.
+    pub pre_link_args: Vec<String>,
+    /// Arguments passed to the linker after all others.
+    pub post_link_args: Vec<String>,
+    /// Value of `cfg(target_family)`, derived from the OS when `None`.
+    pub target_family: Option<String>,
+    /// Whether the target supports dylibs.
+    pub dynamic_linking: bool,
+    /// Whether functions get split-stack prologues and `libmorestack` is
+    /// linked in.
+    pub morestack: bool,
+}
+
+pub fn default_options() -> TargetOptions {
+    TargetOptions {
+        linker: None,
+        pre_link_args: Vec::new(),
+        post_link_args: Vec::new(),
+        target_family: None,
+        dynamic_linking: true,
+        morestack: true,
+    }
+}
+
+/// The contents of a target specification file.
+pub struct TargetSpec {
+    pub llvm_target: String,
+    pub data_layout: String,
+    pub pointer_width: uint,
+    pub arch: abi::Architecture,
+    pub os: abi::Os,
+    pub options: TargetOptions,
+}
+
+/// Returns whether `target` (the argument to `--target`) names a target
+/// specification file rather than a target triple.
+pub fn is_target_spec(target: &str) -> bool {
+    target.ends_with(".json")
+}
+
+/// Loads and validates the target specification file at `path`.
+pub fn load(path: &Path) -> Result<TargetSpec, String> {
+    let contents = match File::open(path).read_to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(format!("couldn't read target specification {}: {}",
+                               path.display(), e))
+        }
+    };
+    let obj = match json::from_str(contents.as_slice()) {
+        Ok(obj) => obj,
+        Err(e) => {
+            return Err(format!("invalid target specification {}: {}",
+                               path.display(), e))
+        }
+    };
+    from_json(&obj).map_err(|e| {
+        format!("invalid target specification {}: {}", path.display(), e)
+    })
+}
+
+fn from_json(obj: &json::Json) -> Result<TargetSpec, String> {
+    if !obj.is_object() {
+        return Err("expected a JSON object".to_string())
+    }
+
+    let llvm_target = try!(req_str(obj, "llvm-target"));
+    let data_layout = try!(req_str(obj, "data-layout"));
+    let pointer_width = try!(req_str(obj, "target-pointer-width"));
+    let pointer_width = match pointer_width.as_slice() {
+        "32" => 32,
+        "64" => 64,
+        w => {
+            return Err(format!("unsupported target pointer width `{}`", w))
+        }
+    };
+    let arch = match try!(req_str(obj, "arch")).as_slice() {
+        "x86" => abi::X86,
+        "x86_64" => abi::X86_64,
+        "arm" => abi::Arm,
+        "mips" => abi::Mips,
+        "mipsel" => abi::Mipsel,
+        a => return Err(format!("unknown architecture `{}`", a)),
+    };
+    let os = match try!(req_str(obj, "os")).as_slice() {
+        "win32" => abi::OsWin32,
+        "macos" => abi::OsMacos,
+        "linux" => abi::OsLinux,
+        "android" => abi::OsAndroid,
+        "freebsd" => abi::OsFreebsd,
+        "ios" => abi::OsiOS,
+        o => return Err(format!("unknown operating system `{}`", o)),
+    };
+
+    let defaults = default_options();
+    let options = TargetOptions {
+        linker: try!(opt_str(obj, "linker")),
+        pre_link_args: try!(opt_list(obj, "pre-link-args")),
+        post_link_args: try!(opt_list(obj, "post-link-args")),
+        target_family: try!(opt_str(obj, "target-family")),
+        dynamic_linking: try!(opt_bool(obj, "dynamic-linking",
+                                       defaults.dynamic_linking)),
+        morestack: try!(opt_bool(obj, "morestack", defaults.morestack)),
+    };
+
+    Ok(TargetSpec {
+        llvm_target: llvm_target,
+        data_layout: data_layout,
+        pointer_width: pointer_width,
+        arch: arch,
+        os: os,
+        options: options,
+    })
+}
+
+fn req_str(obj: &json::Json, name: &str) -> Result<String, String> {
+    match obj.find(&name.to_string()) {
+        Some(v) => match v.as_string() {
+            Some(s) => Ok(s.to_string()),
+            None => Err(format!("`{}` must be a string", name)),
+        },
+        None => Err(format!("missing required field `{}`", name)),
+    }
+}
+
+fn opt_str(obj: &json::Json, name: &str) -> Result<Option<String>, String> {
+    match obj.find(&name.to_string()) {
+        Some(..) => req_str(obj, name).map(|s| Some(s)),
+        None => Ok(None),
+    }
+}
+
+fn opt_bool(obj: &json::Json, name: &str,
+            default: bool) -> Result<bool, String> {
+    match obj.find(&name.to_string()) {
+        Some(v) => match v.as_boolean() {
+            Some(b) => Ok(b),
+            None => Err(format!("`{}` must be a boolean", name)),
+        },
+        None => Ok(default),
+    }
+}
+
+fn opt_list(obj: &json::Json, name: &str) -> Result<Vec<String>, String> {
+    let list = match obj.find(&name.to_string()) {
+        Some(v) => match v.as_list() {
+            Some(list) => list,
+            None => return Err(format!("`{}` must be a list", name)),
+        },
+        None => return Ok(Vec::new()),
+    };
+    let mut ret = Vec::new();
+    for v in list.iter() {
+        match v.as_string() {
+            Some(s) => ret.push(s.to_string()),
+            None => {
+                return Err(format!("`{}` must only contain strings", name))
+            }
+        }
+    }
+    Ok(ret)
+}
@@ -0,0 +1,168 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The error codes rustc emits, and the explanations printed for them by
+//! `rustc --explain`.
+//!
+//! Codes are never reused: when an error goes away its code is retired
+//! rather than handed to a new error.
+
+register_diagnostics!(
+    E0001: r##"
+This error suggests that the expression arm corresponding to the noted pattern
+will never be reached as for all possible values of the expression being
+matched, one of the preceding patterns will match.
+
+This means that perhaps some of the preceding patterns are too general, this
+one is too specific or the ordering is incorrect.
+
+```
+match x {
+    Some(_) => 1u,
+    None => 2u,
+    Some(5) => 3u, // unreachable, the first arm matches every `Some`
+}
+```
+"##,
+
+    E0002: r##"
+This error indicates that an empty match expression is invalid because the
+type it is matching on is non-empty (there exist values of this type). In
+safe code it is impossible to create an instance of an empty type, so empty
+match expressions are almost never desired. This error is typically fixed by
+adding one or more cases to the match expression.
+
+An example of an empty type is `enum Empty { }`.
+"##,
+
+    E0003: r##"
+Not-a-Number (NaN) values cannot be compared for equality and hence can never
+match the input to a match expression. To match against NaN values, you
+should instead use the `is_nan` method in a guard, as in:
+
+```
+match number {
+    x if x.is_nan() => { /* ... */ }
+    _ => { /* ... */ }
+}
+```
+"##,
+
+    E0004: r##"
+This error indicates that the compiler cannot guarantee a matching pattern for
+one or more possible inputs to a match expression. Guaranteed matches are
+required in order to assign values to match expressions, or alternatively,
+determine the flow of execution.
+
+If you encounter this error you must alter your patterns so that every
+possible value of the input type is matched. For types with a small number of
+variants (like enums) you should probably cover all cases explicitly.
+Alternatively, the underscore `_` wildcard pattern can be added after all
+other patterns to match "anything else".
+"##,
+
+    E0005: r##"
+Patterns used to bind names in `let` statements and `for` loops must be
+irrefutable, that is, they must match every possible value of the type being
+bound. If a pattern may fail to match, such as `Some(x)` for a value of type
+`Option<T>`, use a `match` which covers the remaining cases instead:
+
+```
+let x = Some(1i);
+let Some(y) = x; // error, `None` isn't covered
+
+match x {
+    Some(y) => { /* ... */ }
+    None => { /* ... */ }
+}
+```
+"##,
+
+    E0006: r##"
+Patterns used to bind the arguments of a function or closure must be
+irrefutable, that is, they must match every possible value of the argument's
+type. Take the argument as a whole and `match` on it in the body instead:
+
+```
+fn foo(Some(x): Option<int>) { } // error, `None` isn't covered
+
+fn foo(x: Option<int>) {
+    match x {
+        Some(x) => { /* ... */ }
+        None => { /* ... */ }
+    }
+}
+```
+"##,
+
+    E0007: r##"
+This error indicates that the bindings in a match arm would require a value
+to be moved into more than one location, thus violating unique ownership.
+Code like the following is invalid as it requires the entire `Option<String>`
+to be moved into a variable called `op_string` while simultaneously requiring
+the inner `String` to be moved into a variable called `s`.
+
+```
+let x = Some("s".to_string());
+match x {
+    op_string @ Some(s) => { /* ... */ }
+    None => { /* ... */ }
+}
+```
+
+Bind by reference instead, e.g. `op_string @ Some(ref s)`.
+"##,
+
+    E0008: r##"
+Names bound in match arms retain their type in pattern guards. As such, if a
+name is bound by move in a pattern, it should also be moved to wherever it is
+referenced in the pattern guard code. Doing so however would prevent the name
+from being available in the body of the match arm. Consider the following:
+
+```
+match Some("hi".to_string()) {
+    Some(s) if s.len() == 0 => { /* ... */ }
+    _ => { /* ... */ }
+}
+```
+
+The variable `s` has type `String`, and its use in the guard is as a variable
+of type `String`. The guard code effectively executes in a separate scope to
+the body of the arm, so the value would be moved into this anonymous scope and
+therefore become unavailable in the body of the arm. Bind by reference
+instead, e.g. `Some(ref s) if s.len() == 0`.
+"##,
+
+    E0009: r##"
+In a pattern, all values that aren't implicitly copyable have to be bound the
+same way. The goal here is to avoid binding simultaneously by-move and by-ref.
+
+This limitation may be removed in a future version of Rust.
+
+```
+struct X { x: (), }
+
+let x = Some((X { x: () }, X { x: () }));
+match x {
+    Some((y, ref z)) => {}, // error, `y` is bound by-move and `z` by-ref
+    None => fail!()
+}
+```
+
+Bind all of the pattern's values the same way instead:
+
+```
+match x {
+    Some((ref y, ref z)) => {},
+    None => fail!()
+}
+```
+"##
+)
@@ -17,6 +17,7 @@ use driver::session::Session;
 
 use back;
 use back::link;
+use back::target;
 use back::target_strs;
 use back::{arm, x86, x86_64, mips, mipsel};
 use lint;
@@ -28,6 +29,7 @@ use syntax::attr;
 use syntax::attr::AttrMetaMethods;
 use syntax::diagnostic::{ColorConfig, Auto, Always, Never};
 use syntax::parse;
+use syntax::parse::token;
 use syntax::parse::token::InternedString;
 
 use std::collections::{HashSet, HashMap};
@@ -44,6 +46,7 @@ pub struct Config {
     pub target_strs: target_strs::t,
     pub int_type: IntTy,
     pub uint_type: UintTy,
+    pub target_opts: target::TargetOptions,
 }
 
 #[deriving(Clone, PartialEq)]
@@ -61,6 +64,12 @@ pub enum DebugInfoLevel {
     FullDebugInfo,
 }
 
+#[deriving(Clone, PartialEq)]
+pub enum ErrorOutputType {
+    HumanReadableOutput,
+    JsonOutput,
+}
+
 #[deriving(Clone)]
 pub struct Options {
     // The crate config requested for the session, which may be combined
@@ -95,8 +104,11 @@ pub struct Options {
     pub print_metas: (bool, bool),
     pub cg: CodegenOptions,
     pub color: ColorConfig,
+    pub error_format: ErrorOutputType,
     pub externs: HashMap<String, Vec<String>>,
     pub crate_name: Option<String>,
+    /// The directory to write the output of `-Z save-analysis` to.
+    pub save_analysis_dir: Option<Path>,
 }
 
 /// Some reasonable defaults
@@ -122,8 +134,10 @@ pub fn basic_options() -> Options {
         print_metas: (false, false),
         cg: basic_codegen_options(),
         color: Auto,
+        error_format: HumanReadableOutput,
         externs: HashMap::new(),
         crate_name: None,
+        save_analysis_dir: None,
     }
 }
 
@@ -179,7 +193,9 @@ debugging_opts!(
         AST_JSON,
         AST_JSON_NOEXPAND,
         LS,
-        SAVE_ANALYSIS
+        SAVE_ANALYSIS,
+        PRINT_MONO_ITEMS,
+        SAVE_ANALYSIS_JSON
     ]
     0
 )
@@ -215,7 +231,11 @@ pub fn debugging_opts_map() -> Vec<(&'static str, &'static str, u64)> {
      ("ast-json-noexpand", "Print the pre-expansion AST as JSON and halt", AST_JSON_NOEXPAND),
      ("ls", "List the symbols defined by a library crate", LS),
      ("save-analysis", "Write syntax and type analysis information \
-                        in addition to normal output", SAVE_ANALYSIS))
+                        in addition to normal output", SAVE_ANALYSIS),
+     ("print-mono-items", "Print the monomorphized and cross-crate inlined \
+                           functions, by LLVM instruction count", PRINT_MONO_ITEMS),
+     ("save-analysis-json", "Write syntax and type analysis information \
+                             as JSON in addition to normal output", SAVE_ANALYSIS_JSON))
 }
 
 /// Declare a macro that will define all CodegenOptions fields and parsers all
@@ -286,6 +306,13 @@ macro_rules! cgoptions(
             }
         }
 
+        fn parse_uint(slot: &mut uint, v: Option<&str>) -> bool {
+            match v.and_then(from_str) {
+                Some(i) => { *slot = i; true },
+                None => false,
+            }
+        }
+
     }
 ) )
 
@@ -326,6 +353,8 @@ cgoptions!(
          "metadata to mangle symbol names with"),
     extra_filename: String = ("".to_string(), parse_string,
          "extra data to put in each output filename"),
+    codegen_units: uint = (1, parse_uint,
+         "divide the crate into N units to optimize and emit in parallel"),
 )
 
 pub fn build_codegen_options(matches: &getopts::Matches) -> CodegenOptions
@@ -360,6 +389,9 @@ pub fn build_codegen_options(matches: &getopts::Matches) -> CodegenOptions
                                 key).as_slice());
         }
     }
+    if cg.codegen_units == 0 {
+        early_error("codegen option `codegen-units` must be at least 1");
+    }
     return cg;
 }
 
@@ -379,17 +411,25 @@ pub fn default_configuration(sess: &Session) -> ast::CrateConfig {
 
     // ARM is bi-endian, however using NDK seems to default
     // to little-endian unless a flag is provided.
-    let (end,arch,wordsz) = match sess.targ_cfg.arch {
-        abi::X86 =>    ("little", "x86",    "32"),
-        abi::X86_64 => ("little", "x86_64", "64"),
-        abi::Arm =>    ("little", "arm",    "32"),
-        abi::Mips =>   ("big",    "mips",   "32"),
-        abi::Mipsel => ("little", "mipsel", "32")
+    let (end,arch) = match sess.targ_cfg.arch {
+        abi::X86 =>    ("little", "x86"),
+        abi::X86_64 => ("little", "x86_64"),
+        abi::Arm =>    ("little", "arm"),
+        abi::Mips =>   ("big",    "mips"),
+        abi::Mipsel => ("little", "mipsel")
+    };
+
+    let wordsz = match sess.targ_cfg.int_type {
+        ast::TyI64 => "64",
+        _ => "32"
     };
 
-    let fam = match sess.targ_cfg.os {
-        abi::OsWin32 => InternedString::new("windows"),
-        _ => InternedString::new("unix")
+    let fam = match sess.targ_cfg.target_opts.target_family {
+        Some(ref fam) => token::intern_and_get_ident(fam.as_slice()),
+        None => match sess.targ_cfg.os {
+            abi::OsWin32 => InternedString::new("windows"),
+            _ => InternedString::new("unix")
+        }
     };
 
     let mk = attr::mk_name_value_item_str;
@@ -466,7 +506,38 @@ static architecture_abis : &'static [(&'static str, abi::Architecture)] = &[
     ("mipsel", abi::Mipsel),
     ("mips",   abi::Mips)];
 
+/// Builds the target configuration from a target specification file, see
+/// `back::target` for the format.
+fn build_target_config_from_spec(path: &Path) -> Config {
+    let spec = match target::load(path) {
+        Ok(spec) => spec,
+        Err(e) => early_error(e.as_slice())
+    };
+    let (int_type, uint_type) = match spec.pointer_width {
+        64 => (ast::TyI64, ast::TyU64),
+        _ => (ast::TyI32, ast::TyU32)
+    };
+    Config {
+        os: spec.os,
+        arch: spec.arch,
+        target_strs: target_strs::t {
+            module_asm: "".to_string(),
+            data_layout: spec.data_layout,
+            target_triple: spec.llvm_target,
+            cc_args: Vec::new(),
+        },
+        int_type: int_type,
+        uint_type: uint_type,
+        target_opts: spec.options,
+    }
+}
+
 pub fn build_target_config(sopts: &Options) -> Config {
+    if target::is_target_spec(sopts.target_triple.as_slice()) {
+        let path = Path::new(sopts.target_triple.as_slice());
+        return build_target_config_from_spec(&path);
+    }
+
     let os = match get_os(sopts.target_triple.as_slice()) {
       Some(os) => os,
       None => early_error("unknown operating system")
@@ -499,6 +570,7 @@ pub fn build_target_config(sopts: &Options) -> Config {
         target_strs: target_strs,
         int_type: int_type,
         uint_type: uint_type,
+        target_opts: target::default_options(),
     }
 }
 
@@ -512,7 +584,7 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
                                     for the compiler to emit",
                  "[bin|lib|rlib|dylib|staticlib]"),
         optmulti("", "emit", "Comma separated list of types of output for the compiler to emit",
-                 "[asm|bc|ir|obj|link]"),
+                 "[asm|bc|ir|obj|link|metadata|dep-info]"),
         optopt("", "crate-name", "Specify the name of the crate being built",
                "NAME"),
         optflag("", "print-crate-name", "Output the crate name and exit"),
@@ -537,7 +609,9 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
                    valid types are: `normal` (un-annotated source),
                    `expanded` (crates expanded),
                    `typed` (crates expanded, with type annotations),
-                   `expanded,identified` (fully parenthesized, AST nodes with IDs), or
+                   `expanded,identified` (fully parenthesized, AST nodes with IDs),
+                   `expanded,hygiene` (with syntax contexts and macro expansions
+                   annotated), or
                    `flowgraph=<nodeid>` (graphviz formatted flowgraph for node)",
                  "TYPE"),
         optflagopt("", "dep-info",
@@ -547,7 +621,8 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
         optflag("", "test", "Build a test harness"),
         optopt("", "target", "Target triple cpu-manufacturer-kernel[-os]
                             to compile for (see chapter 3.4 of http://www.sourceware.org/autobook/
-                            for details)", "TRIPLE"),
+                            for details), or the path to a target specification
+                            file ending in `.json`", "TRIPLE"),
         optmulti("W", "warn", "Set lint warnings", "OPT"),
         optmulti("A", "allow", "Set lint allowed", "OPT"),
         optmulti("D", "deny", "Set lint denied", "OPT"),
@@ -555,12 +630,19 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
         optmulti("C", "codegen", "Set a codegen option", "OPT[=VALUE]"),
         optmulti("Z", "", "Set internal debugging options", "FLAG"),
         optflagopt("v", "version", "Print version info and exit", "verbose"),
+        optopt("", "explain", "Print a detailed explanation of an error code and exit",
+               "CODE"),
         optopt("", "color", "Configure coloring of output:
             auto   = colorize, if output goes to a tty (default);
             always = always colorize output;
             never  = never colorize output", "auto|always|never"),
+        optopt("", "error-format", "Configure the format of diagnostics:
+            human = human readable text, optionally colorized (default);
+            json  = one JSON object per diagnostic", "human|json"),
         optmulti("", "extern", "Specify where an external rust library is located",
                  "PATH"),
+        optopt("", "save-analysis-dir", "Write the output of -Z save-analysis \
+                                         and -Z save-analysis-json to <dir>", "DIR"),
     )
 }
 
@@ -646,6 +728,8 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
                     "bc"   => link::OutputTypeBitcode,
                     "obj"  => link::OutputTypeObject,
                     "link" => link::OutputTypeExe,
+                    "metadata" => link::OutputTypeMetadata,
+                    "dep-info" => link::OutputTypeDepInfo,
                     _ => {
                         early_error(format!("unknown emission type: `{}`",
                                             part).as_slice())
@@ -655,11 +739,14 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
             }
         }
     };
-    output_types.as_mut_slice().sort();
-    output_types.dedup();
     if output_types.len() == 0 {
         output_types.push(link::OutputTypeExe);
     }
+    if matches.opt_present("dep-info") {
+        output_types.push(link::OutputTypeDepInfo);
+    }
+    output_types.as_mut_slice().sort();
+    output_types.dedup();
 
     let sysroot_opt = matches.opt_str("sysroot").map(|m| Path::new(m));
     let target = matches.opt_str("target").unwrap_or(
@@ -717,7 +804,7 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
 
     let cfg = parse_cfgspecs(matches.opt_strs("cfg"));
     let test = matches.opt_present("test");
-    let write_dependency_info = (matches.opt_present("dep-info"),
+    let write_dependency_info = (output_types.contains(&link::OutputTypeDepInfo),
                                  matches.opt_str("dep-info")
                                         .map(|p| Path::new(p)));
 
@@ -728,7 +815,28 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         early_warn("the --crate-file-name argument has been renamed to \
                     --print-file-name");
     }
-    let cg = build_codegen_options(matches);
+    let mut cg = build_codegen_options(matches);
+
+    // Splitting the crate into codegen units only works for outputs which can
+    // be combined afterwards, that is object files and linked products.
+    if cg.codegen_units > 1 {
+        let whole_module = output_types.iter().any(|t| {
+            match *t {
+                link::OutputTypeBitcode | link::OutputTypeAssembly |
+                link::OutputTypeLlvmAssembly => true,
+                _ => false,
+            }
+        });
+        if debugging_opts & LTO != 0 {
+            early_warn("ignoring -C codegen-units, link-time optimization \
+                        requires a single codegen unit");
+            cg.codegen_units = 1;
+        } else if whole_module || cg.no_integrated_as {
+            early_warn("ignoring -C codegen-units, only object files and \
+                        linked outputs can be emitted in parallel");
+            cg.codegen_units = 1;
+        }
+    }
 
     let color = match matches.opt_str("color").as_ref().map(|s| s.as_slice()) {
         Some("auto")   => Auto,
@@ -744,6 +852,20 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         }
     };
 
+    let error_format = match matches.opt_str("error-format").as_ref()
+                                .map(|s| s.as_slice()) {
+        Some("human") => HumanReadableOutput,
+        Some("json")  => JsonOutput,
+
+        None => HumanReadableOutput,
+
+        Some(arg) => {
+            early_error(format!("argument for --error-format must be human \
+                                 or json (instead was `{}`)",
+                                arg).as_slice())
+        }
+    };
+
     let mut externs = HashMap::new();
     for arg in matches.opt_strs("extern").iter() {
         let mut parts = arg.as_slice().splitn('=', 1);
@@ -760,6 +882,7 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
     }
 
     let crate_name = matches.opt_str("crate-name");
+    let save_analysis_dir = matches.opt_str("save-analysis-dir").map(|d| Path::new(d));
 
     Options {
         crate_types: crate_types,
@@ -782,8 +905,10 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         print_metas: print_metas,
         cg: cg,
         color: color,
+        error_format: error_format,
         externs: externs,
         crate_name: crate_name,
+        save_analysis_dir: save_analysis_dir,
     }
 }
 
@@ -13,7 +13,7 @@ use back::link;
 use driver::session::Session;
 use driver::{config, PpMode};
 use driver::{PpmFlowGraph, PpmExpanded, PpmExpandedIdentified, PpmTyped};
-use driver::{PpmIdentified};
+use driver::{PpmIdentified, PpmExpandedHygiene};
 use front;
 use lib::llvm::{ContextRef, ModuleRef};
 use metadata::common::LinkMeta;
@@ -35,12 +35,15 @@ use dot = graphviz;
 
 use serialize::{json, Encodable};
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io;
 use std::io::fs;
 use std::io::MemReader;
 use syntax::ast;
 use syntax::attr;
 use syntax::attr::{AttrMetaMethods};
+use syntax::codemap::{CodeMap, Span, MacroBang, MacroAttribute};
 use syntax::parse;
 use syntax::parse::token;
 use syntax::print::{pp, pprust};
@@ -94,6 +97,10 @@ pub fn compile_input(sess: Session,
                                                    ast_map, id);
         phase_save_analysis(&analysis.ty_cx.sess, &expanded_crate, &analysis, outdir);
         if stop_after_phase_3(&analysis.ty_cx.sess) { return; }
+        if only_metadata_requested(&analysis.ty_cx.sess) {
+            phase_4_write_metadata(&expanded_crate, analysis, &outputs);
+            return;
+        }
         let (tcx, trans) = phase_4_translate_to_llvm(expanded_crate, analysis);
 
         // Discard interned strings as they are no longer required.
@@ -102,8 +109,13 @@ pub fn compile_input(sess: Session,
         (outputs, trans, tcx.sess)
     };
     phase_5_run_llvm_passes(&sess, &trans, &outputs);
-    if stop_after_phase_5(&sess) { return; }
-    phase_6_link_output(&sess, &trans, &outputs);
+    if !stop_after_phase_5(&sess) {
+        phase_6_link_output(&sess, &trans, &outputs);
+    }
+    if sess.opts.output_types.contains(&link::OutputTypeMetadata) {
+        write_metadata_output(&sess, trans.metadata.as_slice(), &outputs,
+                              trans.link.crate_name.as_slice());
+    }
 }
 
 /**
@@ -212,9 +224,22 @@ pub fn phase_2_configure_and_expand(sess: &Session,
 
     let mut registry = Registry::new(&krate);
 
+    // The crate providing each syntax extension, so that the crates whose
+    // extensions end up being used can be told apart from the unused ones.
+    let mut ext_crates = HashMap::new();
     time(time_passes, "plugin registration", (), |_| {
-        for &registrar in registrars.iter() {
+        for &(crate_name, registrar) in registrars.iter() {
+            let (nexts, npasses) = (registry.syntax_exts.len(),
+                                    registry.lint_passes.len());
             registrar(&mut registry);
+            for &(name, _) in registry.syntax_exts.slice_from(nexts).iter() {
+                ext_crates.insert(name, crate_name.name);
+            }
+            // There's no telling whether a lint pass ends up being useful,
+            // so registering one counts as a use.
+            if registry.lint_passes.len() > npasses {
+                sess.used_plugin_crates.borrow_mut().insert(crate_name.name);
+            }
         }
     });
 
@@ -251,11 +276,23 @@ pub fn phase_2_configure_and_expand(sess: &Session,
                 deriving_hash_type_parameter: sess.features.default_type_params.get(),
                 crate_name: crate_name.to_string(),
             };
-            syntax::ext::expand::expand_crate(&sess.parse_sess,
-                                              cfg,
-                                              macros,
-                                              syntax_exts,
-                                              krate)
+            let (krate, uses) =
+                syntax::ext::expand::expand_crate_with_uses(&sess.parse_sess,
+                                                            cfg,
+                                                            macros,
+                                                            syntax_exts,
+                                                            krate);
+            let mut used = sess.used_plugin_crates.borrow_mut();
+            for crate_name in uses.macro_crates.iter() {
+                used.insert(crate_name.name);
+            }
+            for name in uses.extensions.iter() {
+                match ext_crates.find(name) {
+                    Some(&crate_name) => { used.insert(crate_name); }
+                    None => {}
+                }
+            }
+            krate
         }
     );
 
@@ -410,7 +447,7 @@ pub fn phase_save_analysis(sess: &Session,
                            krate: &ast::Crate,
                            analysis: &CrateAnalysis,
                            odir: &Option<Path>) {
-    if (sess.opts.debugging_opts & config::SAVE_ANALYSIS) == 0 {
+    if !sess.save_analysis() {
         return;
     }
     time(sess.time_passes(), "save analysis", krate, |krate|
@@ -418,8 +455,8 @@ pub fn phase_save_analysis(sess: &Session,
 }
 
 pub struct CrateTranslation {
-    pub context: ContextRef,
-    pub module: ModuleRef,
+    /// The LLVM context and module of each codegen unit.
+    pub modules: Vec<(ContextRef, ModuleRef)>,
     pub metadata_module: ModuleRef,
     pub link: LinkMeta,
     pub metadata: Vec<u8>,
@@ -442,6 +479,32 @@ pub fn phase_4_translate_to_llvm(krate: ast::Crate,
          trans::base::trans_crate(krate, analysis))
 }
 
+/// Encode the crate's metadata without translating the crate, and write it
+/// out for `--emit=metadata`.
+pub fn phase_4_write_metadata(krate: &ast::Crate,
+                              analysis: CrateAnalysis,
+                              outputs: &OutputFilenames) {
+    let time_passes = analysis.ty_cx.sess.time_passes();
+    let name = analysis.name.clone();
+
+    let (tcx, metadata) = time(time_passes, "metadata encoding", analysis,
+                               |analysis| {
+        trans::base::trans_metadata(krate, analysis)
+    });
+    write_metadata_output(&tcx.sess, metadata.as_slice(), outputs,
+                          name.as_slice());
+}
+
+fn write_metadata_output(sess: &Session,
+                         metadata: &[u8],
+                         outputs: &OutputFilenames,
+                         crate_name: &str) {
+    let out = link::filename_for_metadata(sess, crate_name,
+                                          &outputs.path(link::OutputTypeMetadata));
+    time(sess.time_passes(), "writing metadata", (), |_|
+         link::write_metadata_file(sess, metadata, &out));
+}
+
 /// Run LLVM itself, producing a bitcode file, assembly file or object file
 /// as a side effect.
 pub fn phase_5_run_llvm_passes(sess: &Session,
@@ -504,9 +567,21 @@ pub fn stop_after_phase_2(sess: &Session) -> bool {
         debug!("invoked with --no-analysis, returning early from compile_input");
         return true;
     }
+    if sess.opts.output_types.iter().all(|&i| i == link::OutputTypeDepInfo) {
+        debug!("only emitting dep-info, returning early from compile_input");
+        return true;
+    }
     return sess.opts.debugging_opts & config::AST_JSON != 0;
 }
 
+/// Returns whether nothing but metadata (and dep-info) was requested, in
+/// which case the crate doesn't need to be translated.
+pub fn only_metadata_requested(sess: &Session) -> bool {
+    sess.opts.output_types.iter().all(|&i| {
+        i == link::OutputTypeMetadata || i == link::OutputTypeDepInfo
+    })
+}
+
 pub fn stop_after_phase_5(sess: &Session) -> bool {
     if !sess.opts.output_types.iter().any(|&i| i == link::OutputTypeExe) {
         debug!("not building executable, returning early from compile_input");
@@ -515,11 +590,10 @@ pub fn stop_after_phase_5(sess: &Session) -> bool {
     return false;
 }
 
-fn write_out_deps(sess: &Session,
-                  input: &Input,
-                  outputs: &OutputFilenames,
-                  id: &str) {
-
+// Returns the paths of all files the compilation will produce.
+fn collect_output_filenames(sess: &Session,
+                            outputs: &OutputFilenames,
+                            id: &str) -> Vec<Path> {
     let mut out_filenames = Vec::new();
     for output_type in sess.opts.output_types.iter() {
         let file = outputs.path(*output_type);
@@ -531,9 +605,30 @@ fn write_out_deps(sess: &Session,
                     out_filenames.push(p);
                 }
             }
+            link::OutputTypeMetadata => {
+                out_filenames.push(link::filename_for_metadata(sess, id, &file));
+            }
+            link::OutputTypeDepInfo => {}
             _ => { out_filenames.push(file); }
         }
     }
+    out_filenames
+}
+
+fn write_out_deps(sess: &Session,
+                  input: &Input,
+                  outputs: &OutputFilenames,
+                  id: &str) {
+
+    let mut out_filenames = collect_output_filenames(sess, outputs, id);
+    // If only dep-info was requested, name what a regular build would produce
+    if out_filenames.is_empty() {
+        let file = outputs.temp_path(link::OutputTypeExe);
+        for output in sess.crate_types.borrow().iter() {
+            out_filenames.push(link::filename_for_input(sess, *output,
+                                                        id, &file));
+        }
+    }
 
     // Write out dependency rules to the dep-info file if requested with
     // --dep-info
@@ -541,9 +636,13 @@ fn write_out_deps(sess: &Session,
         // Use filename from --dep-file argument if given
         (true, Some(ref filename)) => filename.clone(),
         // Use default filename: crate source filename with extension replaced
-        // by ".d"
+        // by ".d", unless dep-info is the only output and a filename was
+        // given with -o
         (true, None) => match *input {
-            FileInput(..) => outputs.with_extension("d"),
+            FileInput(..) if sess.opts.output_types.len() == 1 => {
+                outputs.path(link::OutputTypeDepInfo)
+            }
+            FileInput(..) => outputs.temp_path(link::OutputTypeDepInfo),
             StrInput(..) => {
                 sess.warn("can not write --dep-info without a filename \
                            when compiling stdin.");
@@ -609,6 +708,75 @@ impl pprust::PpAnn for IdentifiedAnnotation {
                 try!(pp::space(&mut s.s));
                 s.synth_comment(format!("pat {}", pat.id))
             }
+            pprust::NodeIdent(_) | pprust::NodeName(_) => Ok(())
+        }
+    }
+}
+
+/// Annotates identifiers with their name and syntax context, and marks the
+/// start of the code produced by each macro expansion with the macro's name
+/// and call site.
+struct HygieneAnnotation<'a> {
+    codemap: &'a CodeMap,
+    // The expansion each node being printed came from, as (call site, macro
+    // name), innermost last. A comment is only printed when a node comes from
+    // a different expansion than its parent.
+    expansions: RefCell<Vec<Option<(Span, String)>>>,
+}
+
+impl<'a> HygieneAnnotation<'a> {
+    fn expansion_of(&self, sp: Span) -> Option<(Span, String)> {
+        sp.expn_info.map(|info| {
+            let name = match info.callee.format {
+                MacroBang => format!("{}!", info.callee.name),
+                MacroAttribute => format!("#[{}]", info.callee.name),
+            };
+            (info.call_site, name)
+        })
+    }
+}
+
+impl<'a> pprust::PpAnn for HygieneAnnotation<'a> {
+    fn pre(&self,
+           s: &mut pprust::State,
+           node: pprust::AnnNode) -> io::IoResult<()> {
+        let sp = match node {
+            pprust::NodeItem(item) => item.span,
+            pprust::NodeBlock(blk) => blk.span,
+            pprust::NodeExpr(expr) => expr.span,
+            pprust::NodePat(pat) => pat.span,
+            pprust::NodeIdent(_) | pprust::NodeName(_) => return Ok(())
+        };
+        let expansion = self.expansion_of(sp);
+        let is_new = match expansion {
+            Some(_) => self.expansions.borrow().last() != Some(&expansion),
+            None => false,
+        };
+        if is_new {
+            let (call_site, ref name) = *expansion.get_ref();
+            try!(s.synth_comment(format!("expansion of {} at {}", name,
+                                         self.codemap.span_to_str(call_site))));
+            try!(pp::space(&mut s.s));
+        }
+        self.expansions.borrow_mut().push(expansion);
+        Ok(())
+    }
+    fn post(&self,
+            s: &mut pprust::State,
+            node: pprust::AnnNode) -> io::IoResult<()> {
+        match node {
+            pprust::NodeIdent(&ast::Ident { name, ctxt }) => {
+                try!(pp::space(&mut s.s));
+                s.synth_comment(format!("{}#{}", name, ctxt))
+            }
+            pprust::NodeName(&name) => {
+                try!(pp::space(&mut s.s));
+                s.synth_comment(name.to_str())
+            }
+            _ => {
+                self.expansions.borrow_mut().pop();
+                Ok(())
+            }
         }
     }
 }
@@ -655,7 +823,8 @@ pub fn pretty_print_input(sess: Session,
     let id = link::find_crate_name(Some(&sess), krate.attrs.as_slice(), input);
 
     let (krate, ast_map, is_expanded) = match ppm {
-        PpmExpanded | PpmExpandedIdentified | PpmTyped | PpmFlowGraph(_) => {
+        PpmExpanded | PpmExpandedIdentified | PpmExpandedHygiene | PpmTyped |
+        PpmFlowGraph(_) => {
             let (krate, ast_map)
                 = match phase_2_configure_and_expand(&sess, krate,
                                                      id.as_slice()) {
@@ -696,6 +865,20 @@ pub fn pretty_print_input(sess: Session,
                                 &IdentifiedAnnotation,
                                 is_expanded)
         }
+        PpmExpandedHygiene => {
+            let annotation = HygieneAnnotation {
+                codemap: sess.codemap(),
+                expansions: RefCell::new(Vec::new()),
+            };
+            pprust::print_crate(sess.codemap(),
+                                sess.diagnostic(),
+                                &krate,
+                                src_name.to_string(),
+                                &mut rdr,
+                                out,
+                                &annotation,
+                                is_expanded)
+        }
         PpmTyped => {
             let ast_map = ast_map.expect("--pretty=typed missing ast_map");
             let analysis = phase_3_run_analysis_passes(sess, &krate, ast_map, id);
@@ -877,6 +1060,8 @@ impl OutputFilenames {
             link::OutputTypeLlvmAssembly => base.with_extension("ll"),
             link::OutputTypeObject => base.with_extension("o"),
             link::OutputTypeExe => base,
+            link::OutputTypeMetadata => base.with_extension("rmeta"),
+            link::OutputTypeDepInfo => base.with_extension("d"),
         }
     }
 
@@ -915,7 +1100,12 @@ pub fn build_output_filenames(input: &Input,
         }
 
         Some(ref out_file) => {
-            let ofile = if sess.opts.output_types.len() > 1 {
+            // The dep-info file is named after the output, so it doesn't
+            // count as one of multiple outputs.
+            let outputs = sess.opts.output_types.iter().filter(|&&i| {
+                i != link::OutputTypeDepInfo
+            }).count();
+            let ofile = if outputs > 1 {
                 sess.warn("ignoring specified output filename because multiple \
                            outputs were requested");
                 None
@@ -26,6 +26,7 @@ use std::task::TaskBuilder;
 use syntax::ast;
 use syntax::parse;
 use syntax::diagnostic::Emitter;
+use syntax::diagnostics::registry::Registry;
 
 use getopts;
 
@@ -286,6 +287,20 @@ pub fn handle_options(mut args: Vec<String>) -> Option<getopts::Matches> {
         }
     }
 
+    match matches.opt_str("explain") {
+        Some(ref code) => {
+            match diagnostics_registry().find_description(code.as_slice()) {
+                Some(description) => println!("{}", description),
+                None => {
+                    early_error(format!("no extended information for {}",
+                                        code).as_slice())
+                }
+            }
+            return None;
+        }
+        None => {}
+    }
+
     Some(matches)
 }
 
@@ -331,6 +346,7 @@ pub enum PpMode {
     PpmTyped,
     PpmIdentified,
     PpmExpandedIdentified,
+    PpmExpandedHygiene,
     PpmFlowGraph(ast::NodeId),
 }
 
@@ -343,6 +359,7 @@ pub fn parse_pretty(sess: &Session, name: &str) -> PpMode {
         (None, "expanded")     => PpmExpanded,
         (None, "typed")        => PpmTyped,
         (None, "expanded,identified") => PpmExpandedIdentified,
+        (None, "expanded,hygiene") => PpmExpandedHygiene,
         (None, "identified")   => PpmIdentified,
         (arg, "flowgraph") => {
              match arg.and_then(from_str) {
@@ -358,7 +375,8 @@ pub fn parse_pretty(sess: &Session, name: &str) -> PpMode {
             sess.fatal(format!(
                 "argument to `pretty` must be one of `normal`, \
                  `expanded`, `flowgraph=<nodeid>`, `typed`, `identified`, \
-                 or `expanded,identified`; got {}", name).as_slice());
+                 `expanded,identified`, or `expanded,hygiene`; got {}",
+                name).as_slice());
         }
     }
 }
@@ -382,15 +400,20 @@ fn parse_crate_attrs(sess: &Session, input: &Input) ->
     result.move_iter().collect()
 }
 
+/// Returns the explanations of all the error codes rustc can emit.
+pub fn diagnostics_registry() -> Registry {
+    Registry::new(::diagnostics::DIAGNOSTICS)
+}
+
 pub fn early_error(msg: &str) -> ! {
     let mut emitter = diagnostic::EmitterWriter::stderr(diagnostic::Auto);
-    emitter.emit(None, msg, diagnostic::Fatal);
+    emitter.emit(None, msg, None, diagnostic::Fatal);
     fail!(diagnostic::FatalError);
 }
 
 pub fn early_warn(msg: &str) {
     let mut emitter = diagnostic::EmitterWriter::stderr(diagnostic::Auto);
-    emitter.emit(None, msg, diagnostic::Warning);
+    emitter.emit(None, msg, None, diagnostic::Warning);
 }
 
 pub fn list_metadata(sess: &Session, path: &Path,
@@ -437,6 +460,7 @@ fn monitor(f: proc():Send) {
                     emitter.emit(
                         None,
                         "unexpected failure",
+                        None,
                         diagnostic::Bug);
                 }
 
@@ -447,7 +471,7 @@ fn monitor(f: proc():Send) {
                     "run with `RUST_BACKTRACE=1` for a backtrace".to_string(),
                 ];
                 for note in xs.iter() {
-                    emitter.emit(None, note.as_slice(), diagnostic::Note)
+                    emitter.emit(None, note.as_slice(), None, diagnostic::Note)
                 }
 
                 match r.read_to_str() {
@@ -457,6 +481,7 @@ fn monitor(f: proc():Send) {
                                      format!("failed to read internal \
                                               stderr: {}",
                                              e).as_slice(),
+                                     None,
                                      diagnostic::Error)
                     }
                 }
@@ -27,6 +27,7 @@ use syntax::{ast, codemap};
 
 use std::os;
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 
 
 pub struct Session {
@@ -49,6 +50,9 @@ pub struct Session {
     pub crate_types: RefCell<Vec<config::CrateType>>,
     pub crate_metadata: RefCell<Vec<String>>,
     pub features: front::feature_gate::Features,
+    /// Names of the `#[phase(plugin)]` crates whose macros or syntax
+    /// extensions were used during expansion.
+    pub used_plugin_crates: RefCell<HashSet<ast::Name>>,
 
     /// The maximum recursion limit for potentially infinitely recursive
     /// operations such as auto-dereference and monomorphization.
@@ -65,6 +69,9 @@ impl Session {
     pub fn span_err(&self, sp: Span, msg: &str) {
         self.diagnostic().span_err(sp, msg)
     }
+    pub fn span_err_with_code(&self, sp: Span, msg: &str, code: &str) {
+        self.diagnostic().span_err_with_code(sp, msg, code)
+    }
     pub fn err(&self, msg: &str) {
         self.diagnostic().handler().err(msg)
     }
@@ -80,6 +87,9 @@ impl Session {
     pub fn span_warn(&self, sp: Span, msg: &str) {
         self.diagnostic().span_warn(sp, msg)
     }
+    pub fn span_warn_with_code(&self, sp: Span, msg: &str, code: &str) {
+        self.diagnostic().span_warn_with_code(sp, msg, code)
+    }
     pub fn warn(&self, msg: &str) {
         self.diagnostic().handler().warn(msg)
     }
@@ -95,6 +105,15 @@ impl Session {
     pub fn note(&self, msg: &str) {
         self.diagnostic().handler().note(msg)
     }
+    pub fn span_help(&self, sp: Span, msg: &str) {
+        self.diagnostic().span_help(sp, msg)
+    }
+    pub fn fileline_help(&self, sp: Span, msg: &str) {
+        self.diagnostic().fileline_help(sp, msg)
+    }
+    pub fn help(&self, msg: &str) {
+        self.diagnostic().handler().help(msg)
+    }
     pub fn span_bug(&self, sp: Span, msg: &str) -> ! {
         self.diagnostic().span_bug(sp, msg)
     }
@@ -176,6 +195,15 @@ impl Session {
     pub fn show_span(&self) -> bool {
         self.debugging_opt(config::SHOW_SPAN)
     }
+    pub fn print_mono_items(&self) -> bool {
+        self.debugging_opt(config::PRINT_MONO_ITEMS)
+    }
+    pub fn save_analysis(&self) -> bool {
+        self.debugging_opt(config::SAVE_ANALYSIS) || self.save_analysis_json()
+    }
+    pub fn save_analysis_json(&self) -> bool {
+        self.debugging_opt(config::SAVE_ANALYSIS_JSON)
+    }
     pub fn sysroot<'a>(&'a self) -> &'a Path {
         match self.opts.maybe_sysroot {
             Some (ref sysroot) => sysroot,
@@ -184,8 +212,11 @@ impl Session {
         }
     }
     pub fn target_filesearch<'a>(&'a self) -> filesearch::FileSearch<'a> {
+        // When compiling with a target specification file `opts.target_triple`
+        // is its path, the target's libraries are found under the LLVM triple.
+        let triple = self.targ_cfg.target_strs.target_triple.as_slice();
         filesearch::FileSearch::new(self.sysroot(),
-                                    self.opts.target_triple.as_slice(),
+                                    triple,
                                     &self.opts.addl_lib_search_paths)
     }
     pub fn host_filesearch<'a>(&'a self) -> filesearch::FileSearch<'a> {
@@ -200,8 +231,13 @@ pub fn build_session(sopts: config::Options,
                      local_crate_source_file: Option<Path>)
                      -> Session {
     let codemap = codemap::CodeMap::new();
-    let diagnostic_handler =
-        diagnostic::default_handler(sopts.color);
+    let emitter: Box<diagnostic::Emitter + Send> = match sopts.error_format {
+        config::HumanReadableOutput => {
+            box diagnostic::EmitterWriter::stderr(sopts.color)
+        }
+        config::JsonOutput => box diagnostic::JsonEmitter::stderr(),
+    };
+    let diagnostic_handler = diagnostic::mk_handler(emitter);
     let span_diagnostic_handler =
         diagnostic::mk_span_handler(diagnostic_handler, codemap);
 
@@ -246,6 +282,7 @@ pub fn build_session_(sopts: config::Options,
         crate_types: RefCell::new(Vec::new()),
         crate_metadata: RefCell::new(Vec::new()),
         features: front::feature_gate::Features::new(),
+        used_plugin_crates: RefCell::new(HashSet::new()),
         recursion_limit: Cell::new(64),
     };
 
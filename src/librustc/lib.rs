@@ -41,9 +41,11 @@ extern crate getopts;
 extern crate graphviz;
 extern crate libc;
 extern crate serialize;
-extern crate syntax;
 extern crate time;
 #[phase(plugin, link)] extern crate log;
+#[phase(plugin, link)] extern crate syntax;
+
+pub mod diagnostics;
 
 pub mod middle {
     pub mod def;
@@ -103,6 +105,7 @@ pub mod back {
     pub mod mipsel;
     pub mod rpath;
     pub mod svh;
+    pub mod target;
     pub mod target_strs;
     pub mod x86;
     pub mod x86_64;
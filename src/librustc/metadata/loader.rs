@@ -137,6 +137,10 @@
 //! the file is indeed a rust library. The same `crate-name` can be specified
 //! twice to specify the rlib/dylib pair.
 //!
+//! The path may also name a `.rmeta` file produced by `--emit=metadata`, which
+//! is treated as an rlib. As it contains no code, it can be used to compile
+//! libraries but not to link executables or dylibs.
+//!
 //! ## Enabling "multiple versions"
 //!
 //! This basically boils down to the ability to specify arbitrary packages to
@@ -637,7 +641,8 @@ impl<'a> Context<'a> {
                 return false;
             }
             let file = loc.filename_str().unwrap();
-            if file.starts_with("lib") && file.ends_with(".rlib") {
+            if file.starts_with("lib") && (file.ends_with(".rlib") ||
+                                           file.ends_with(".rmeta")) {
                 return true
             } else {
                 match dylibname {
@@ -659,7 +664,7 @@ impl<'a> Context<'a> {
         let mut rlibs = HashSet::new();
         let mut dylibs = HashSet::new();
         for loc in locs {
-            if loc.filename_str().unwrap().ends_with(".rlib") {
+            if is_rlib(&loc) {
                 rlibs.insert(loc.clone());
             } else {
                 dylibs.insert(loc.clone());
@@ -718,6 +723,13 @@ impl ArchiveMetadata {
     pub fn as_slice<'a>(&'a self) -> &'a [u8] { self.data }
 }
 
+// Metadata-only crates (see `--emit=metadata`) are rlibs without any code,
+// and can stand in for one when compiling a library.
+fn is_rlib(path: &Path) -> bool {
+    let file = path.filename_str().unwrap();
+    file.ends_with(".rlib") || file.ends_with(".rmeta")
+}
+
 // Just a small wrapper to time how long reading metadata takes.
 fn get_metadata_section(os: abi::Os, filename: &Path) -> Result<MetadataBlob, String> {
     let start = time::precise_time_ns();
@@ -731,7 +743,7 @@ fn get_metadata_section_imp(os: abi::Os, filename: &Path) -> Result<MetadataBlob
     if !filename.exists() {
         return Err(format!("no such file: '{}'", filename.display()));
     }
-    if filename.filename_str().unwrap().ends_with(".rlib") {
+    if is_rlib(filename) {
         // Use ArchiveRO for speed here, it's backed by LLVM and uses mmap
         // internally to read the file. We also avoid even using a memcpy by
         // just keeping the archive along while the metadata is in use.
@@ -157,7 +157,8 @@ impl<'a, O:DataFlowOperator> pprust::PpAnn for DataFlowContext<'a, O> {
             pprust::NodeExpr(expr) => expr.id,
             pprust::NodeBlock(blk) => blk.id,
             pprust::NodeItem(_) => 0,
-            pprust::NodePat(pat) => pat.id
+            pprust::NodePat(pat) => pat.id,
+            pprust::NodeIdent(_) | pprust::NodeName(_) => 0,
         };
 
         if self.has_bitset(id) {
@@ -456,6 +456,10 @@ struct Module {
     // `extern crate`.
     external_module_children: RefCell<HashMap<Name, Rc<Module>>>,
 
+    // For the root module of an external crate, the `extern crate` item which
+    // loaded it.
+    extern_crate_id: Option<NodeId>,
+
     // The anonymous children of this node. Anonymous children are pseudo-
     // modules that are implicitly created around items contained within
     // blocks.
@@ -502,6 +506,7 @@ impl Module {
             children: RefCell::new(HashMap::new()),
             imports: RefCell::new(Vec::new()),
             external_module_children: RefCell::new(HashMap::new()),
+            extern_crate_id: None,
             anonymous_children: RefCell::new(NodeMap::new()),
             import_resolutions: RefCell::new(HashMap::new()),
             glob_count: Cell::new(0),
@@ -850,6 +855,10 @@ struct Resolver<'a> {
     emit_errors: bool,
 
     used_imports: HashSet<(NodeId, Namespace)>,
+
+    // The `extern crate` items that some path resolved through, used to find
+    // those which are never used.
+    used_extern_crates: HashSet<NodeId>,
 }
 
 struct BuildReducedGraphVisitor<'a, 'b> {
@@ -936,6 +945,7 @@ impl<'a> Resolver<'a> {
             export_map2: RefCell::new(NodeMap::new()),
             trait_map: NodeMap::new(),
             used_imports: HashSet::new(),
+            used_extern_crates: HashSet::new(),
             external_exports: DefIdSet::new(),
             last_private: NodeMap::new(),
 
@@ -1491,11 +1501,13 @@ impl<'a> Resolver<'a> {
                     self.external_exports.insert(def_id);
                     let parent_link =
                         ModuleParentLink(parent.module().downgrade(), name);
-                    let external_module = Rc::new(Module::new(parent_link,
-                                                              Some(def_id),
-                                                              NormalModuleKind,
-                                                              false,
-                                                              true));
+                    let mut external_module = Module::new(parent_link,
+                                                          Some(def_id),
+                                                          NormalModuleKind,
+                                                          false,
+                                                          true);
+                    external_module.extern_crate_id = Some(node_id);
+                    let external_module = Rc::new(external_module);
                     debug!("(build reduced graph for item) found extern `{}`",
                             self.module_to_str(&*external_module));
                     parent.module().external_module_children.borrow_mut()
@@ -2361,6 +2373,7 @@ impl<'a> Resolver<'a> {
                     Some(module) => {
                         debug!("(resolving single import) found external \
                                 module");
+                        self.record_extern_crate_use(&*module);
                         let name_bindings =
                             Rc::new(Resolver::create_name_bindings_from_module(
                                 module));
@@ -2901,6 +2914,7 @@ impl<'a> Resolver<'a> {
             match module_.external_module_children.borrow().find_copy(&name.name) {
                 None => {}
                 Some(module) => {
+                    self.record_extern_crate_use(&*module);
                     let name_bindings =
                         Rc::new(Resolver::create_name_bindings_from_module(module));
                     debug!("lower name bindings succeeded");
@@ -3176,6 +3190,7 @@ impl<'a> Resolver<'a> {
             match module_.external_module_children.borrow().find_copy(&name) {
                 None => {}
                 Some(module) => {
+                    self.record_extern_crate_use(&*module);
                     let name_bindings =
                         Rc::new(Resolver::create_name_bindings_from_module(module));
                     return Success((Target::new(module_, name_bindings), false));
@@ -4679,6 +4694,7 @@ impl<'a> Resolver<'a> {
                                    .find_copy(&name) {
                 None => {}
                 Some(module) => {
+                    self.record_extern_crate_use(&*module);
                     match module.def_id.get() {
                         None => {} // Continue.
                         Some(def_id) => {
@@ -5426,7 +5442,9 @@ impl<'a> Resolver<'a> {
         if vi.span == DUMMY_SP { return }
 
         match vi.node {
-            ViewItemExternCrate(..) => {} // ignore
+            ViewItemExternCrate(name, _, id) => {
+                self.check_for_unused_extern_crate(name, id, vi.span)
+            }
             ViewItemUse(ref p) => {
                 match p.node {
                     ViewPathSimple(_, _, id) => self.finalize_import(id, p.span),
@@ -5450,6 +5468,33 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    // Records that a path resolved through the root module of an external
+    // crate, which makes the `extern crate` item that loaded it used.
+    fn record_extern_crate_use(&mut self, module: &Module) {
+        match module.extern_crate_id {
+            Some(id) => { self.used_extern_crates.insert(id); }
+            None => {}
+        }
+    }
+
+    // A crate can be linked in, loaded as a plugin, or both. The `extern
+    // crate` item is unused if neither was made use of: no path resolved
+    // through the item and none of the crate's macros or syntax extensions
+    // were invoked.
+    fn check_for_unused_extern_crate(&mut self, name: Ident, id: NodeId,
+                                     span: Span) {
+        if self.session.used_plugin_crates.borrow().contains(&name.name) {
+            return
+        }
+        if self.used_extern_crates.contains(&id) {
+            return
+        }
+        self.session.add_lint(lint::builtin::UNUSED_EXTERN_CRATES,
+                              id,
+                              span,
+                              "unused extern crate".to_string());
+    }
+
     // We have information about whether `use` (import) directives are actually used now.
     // If an import is not used at all, we signal a lint error. If an import is only used
     // for a single namespace, we remove the other namespace from the recorded privacy
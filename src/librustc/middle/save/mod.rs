@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! Output a CSV file containing the output from rustc's analysis. The data is
+//! Output a CSV or JSON file containing the output from rustc's analysis. The data is
 //! primarily designed to be used as input to the DXR tool, specifically its
 //! Rust plugin. It could also be used by IDEs or other code browsing, search, or
 //! cross-referencing tools.
@@ -23,9 +23,19 @@
 //!
 //! SpanUtils is used to manipulate spans. In particular, to extract sub-spans
 //! from spans (e.g., the span for `bar` from the above example path).
-//! Recorder is used for recording the output in csv format. FmtStrs separates
-//! the format of the output away from extracting it from the compiler.
+//! Recorder is used for recording the output in csv or JSON format. FmtStrs
+//! separates the format of the output away from extracting it from the compiler.
 //! DxrVisitor walks the AST and processes it.
+//!
+//! With `-Z save-analysis-json` the output is a single JSON object with the
+//! sections `crate`, `external_crates`, `defs`, `refs`, `impls`, `imports` and
+//! `macro_uses`. Each section is a list of rows of the form
+//! `{"kind": "function", "span": {...}, ...}`, where `kind` is the row label of
+//! the CSV output and the remaining keys are its fields. Node ids and crate
+//! numbers are numbers (`null` when there is none), everything else is a
+//! string. Spans have the keys `file_name`, `byte_start`, `byte_end`,
+//! `line_start`, `line_end`, `column_start` and `column_end`; columns are
+//! 1-based.
 
 use driver::driver::CrateAnalysis;
 use driver::session::Session;
@@ -35,6 +45,7 @@ use middle::ty;
 use middle::typeck;
 
 use std::cell::Cell;
+use std::collections::HashSet;
 use std::gc::Gc;
 use std::io;
 use std::io::File;
@@ -54,7 +65,7 @@ use syntax::visit::Visitor;
 use syntax::print::pprust::{path_to_str,ty_to_str};
 
 use middle::save::span_utils::SpanUtils;
-use middle::save::recorder::Recorder;
+use middle::save::recorder::{Recorder, CsvFormat, JsonFormat};
 use middle::save::recorder::FmtStrs;
 
 use util::ppaux;
@@ -79,6 +90,10 @@ struct DxrVisitor<'l> {
     collected_paths: Vec<(NodeId, ast::Path, bool, recorder::Row)>,
     collecting: bool,
 
+    // The (lo, hi) of the call sites of the macro uses already recorded, a
+    // macro expands into many generated nodes but is only recorded once.
+    macro_calls: HashSet<(uint, uint)>,
+
     span: SpanUtils<'l>,
     fmt: FmtStrs<'l>,
 }
@@ -92,7 +107,36 @@ impl <'l> DxrVisitor<'l> {
         self.sess.cstore.iter_crate_data(|n, cmd| {
             self.fmt.external_crate_str(krate.span, cmd.name.as_slice(), n);
         });
-        self.fmt.recorder.record("end_external_crates\n");
+        self.fmt.end_external_crates();
+    }
+
+    // Record the use of a macro if `span` was generated by one. Nested
+    // expansions are attributed to the outermost macro, which is the only one
+    // with a call site in the source.
+    fn process_macro_use(&mut self, span: Span, e: DxrVisitorEnv) {
+        let mut info = match span.expn_info {
+            Some(info) => info,
+            None => return,
+        };
+        loop {
+            match info.call_site.expn_info.clone() {
+                Some(outer) => info = outer,
+                None => break,
+            }
+        }
+
+        let name = match info.callee.format {
+            MacroBang => info.callee.name.clone(),
+            MacroAttribute => return,
+        };
+        let call_site = info.call_site;
+        if call_site == DUMMY_SP ||
+           !self.macro_calls.insert((call_site.lo.to_uint(), call_site.hi.to_uint())) {
+            return;
+        }
+
+        let sub_span = self.span.span_for_first_ident(call_site);
+        self.fmt.macro_use_str(call_site, sub_span, name.as_slice(), e.cur_scope);
     }
 
     // Return all non-empty prefixes of a path.
@@ -955,6 +999,7 @@ impl <'l> DxrVisitor<'l> {
 impl<'l> Visitor<DxrVisitorEnv> for DxrVisitor<'l> {
     fn visit_item(&mut self, item:&ast::Item, e: DxrVisitorEnv) {
         if generated_code(item.span) {
+            self.process_macro_use(item.span, e);
             return
         }
 
@@ -1185,6 +1230,7 @@ impl<'l> Visitor<DxrVisitorEnv> for DxrVisitor<'l> {
 
     fn visit_expr(&mut self, ex: &ast::Expr, e: DxrVisitorEnv) {
         if generated_code(ex.span) {
+            self.process_macro_use(ex.span, e);
             return
         }
 
@@ -1308,6 +1354,7 @@ impl<'l> Visitor<DxrVisitorEnv> for DxrVisitor<'l> {
 
     fn visit_stmt(&mut self, s:&ast::Stmt, e:DxrVisitorEnv) {
         if generated_code(s.span) {
+            self.process_macro_use(s.span, e);
             return
         }
 
@@ -1384,11 +1431,14 @@ pub fn process_crate(sess: &Session,
     info!("Dumping crate {}", cratename);
 
     // find a path to dump our data to
-    let mut root_path = match os::getenv("DXR_RUST_TEMP_FOLDER") {
-        Some(val) => Path::new(val),
-        None => match *odir {
-            Some(ref val) => val.join("dxr"),
-            None => Path::new("dxr-temp"),
+    let mut root_path = match sess.opts.save_analysis_dir {
+        Some(ref dir) => dir.clone(),
+        None => match os::getenv("DXR_RUST_TEMP_FOLDER") {
+            Some(val) => Path::new(val),
+            None => match *odir {
+                Some(ref val) => val.join("dxr"),
+                None => Path::new("dxr-temp"),
+            },
         },
     };
 
@@ -1403,9 +1453,14 @@ pub fn process_crate(sess: &Session,
         info!("Writing output to {}", disp);
     }
 
+    let format = if sess.save_analysis_json() { JsonFormat } else { CsvFormat };
+
     // Create output file.
     let mut out_name = cratename.clone();
-    out_name.push_str(".csv");
+    out_name.push_str(match format {
+        JsonFormat => ".json",
+        CsvFormat => ".csv",
+    });
     root_path.push(out_name);
     let output_file = match File::create(&root_path) {
         Ok(f) => box f,
@@ -1420,10 +1475,9 @@ pub fn process_crate(sess: &Session,
                                   analysis: analysis,
                                   collected_paths: vec!(),
                                   collecting: false,
-                                  fmt: FmtStrs::new(box Recorder {
-                                                        out: output_file as Box<Writer>,
-                                                        dump_spans: false,
-                                                    },
+                                  macro_calls: HashSet::new(),
+                                  fmt: FmtStrs::new(box Recorder::new(output_file as Box<Writer>,
+                                                                      format),
                                                     SpanUtils {
                                                         sess: sess,
                                                         err_count: Cell::new(0)
@@ -1437,4 +1491,6 @@ pub fn process_crate(sess: &Session,
     visitor.dump_crate_info(cratename.as_slice(), krate);
 
     visit::walk_crate(&mut visitor, krate, DxrVisitorEnv::new());
+
+    visitor.fmt.recorder.finish();
 }
@@ -11,19 +11,65 @@
 use middle::save::escape;
 use middle::save::span_utils::SpanUtils;
 
+use serialize::json;
+use std::collections::TreeMap;
 use std::vec::Vec;
 
 use syntax::ast;
 use syntax::ast::{NodeId,DefId};
 use syntax::codemap::*;
 
+/// The format the analysis is written in.
+#[deriving(PartialEq)]
+pub enum Format {
+    /// A line of comma separated values per row.
+    CsvFormat,
+    /// A single JSON object, see the module documentation for its schema.
+    JsonFormat,
+}
+
 pub struct Recorder {
     // output file
     pub out: Box<Writer>,
     pub dump_spans: bool,
+    // The rows of the JSON output, by section. They're only written out by
+    // `finish`, once the whole crate has been walked.
+    json: Option<TreeMap<String, Vec<json::Json>>>,
 }
 
 impl Recorder {
+    pub fn new(out: Box<Writer>, format: Format) -> Recorder {
+        Recorder {
+            out: out,
+            dump_spans: false,
+            json: if format == JsonFormat { Some(TreeMap::new()) } else { None },
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json.is_some()
+    }
+
+    pub fn record_json(&mut self, section: &str, row: json::Object) {
+        let sections = self.json.get_mut_ref();
+        sections.find_or_insert(section.to_string(), Vec::new()).push(json::Object(row));
+    }
+
+    /// Writes out the JSON output, there's nothing left to do for CSV.
+    pub fn finish(&mut self) {
+        let sections = match self.json.take() {
+            Some(sections) => sections,
+            None => return,
+        };
+        let obj: json::Object = sections.move_iter().map(|(section, rows)| {
+            (section, json::List(rows))
+        }).collect();
+        match json::Object(obj).to_writer(&mut *self.out) {
+            Err(_) => error!("Error writing JSON output"),
+            _ => (),
+        }
+    }
+
     pub fn record(&mut self, info: &str) {
         match write!(self.out, "{}", info) {
             Err(_) => error!("Error writing output '{}'", info),
@@ -83,6 +129,7 @@ pub enum Row {
     TypeRef,
     StructRef,
     FnRef,
+    MacroUse,
 }
 
 impl<'a> FmtStrs<'a> {
@@ -139,20 +186,82 @@ impl<'a> FmtStrs<'a> {
             StructRef => ("struct_ref",
                           vec!("refid","refidcrate","qualname","scopeid"),
                            true, true),
-            FnRef => ("fn_ref", vec!("refid","refidcrate","qualname","scopeid"), true, true)
+            FnRef => ("fn_ref", vec!("refid","refidcrate","qualname","scopeid"), true, true),
+            MacroUse => ("macro_use", vec!("name","scopeid"), true, true)
         }
     }
 
-    pub fn make_values_str(&self,
-                           kind: &'static str,
-                           fields: &Vec<&'static str>,
-                           values: Vec<String>,
-                           span: Span) -> Option<String> {
+    // The section of the JSON output a kind of row belongs in.
+    fn json_section(r: Row) -> &'static str {
+        match r {
+            Variable | Enum | Variant | VariantStruct | Function | MethodDecl |
+            Struct | Trait | Module | Typedef => "defs",
+            MethodCall | FnCall | ModRef | VarRef | TypeRef | StructRef |
+            FnRef => "refs",
+            Impl | Inheritance => "impls",
+            UseAlias | ExternCrate => "imports",
+            MacroUse => "macro_uses",
+            ExternalCrate => "external_crates",
+            Crate => "crate",
+        }
+    }
+
+    // Whether the field holds a node id or crate number, which are written as
+    // numbers (or null when missing) in the JSON output.
+    fn is_id_field(field: &str) -> bool {
+        match field {
+            "id" | "ctor_id" | "scopeid" | "refid" | "refidcrate" | "declid" |
+            "declidcrate" | "base" | "basecrate" | "derived" | "derivedcrate" |
+            "crate" => true,
+            _ => false,
+        }
+    }
+
+    fn make_json_row(&self,
+                     kind: &'static str,
+                     fields: &Vec<&'static str>,
+                     values: Vec<String>,
+                     span: Option<Span>) -> json::Object {
+        let mut row = TreeMap::new();
+        row.insert("kind".to_string(), json::String(kind.to_string()));
+        match span {
+            Some(span) => { row.insert("span".to_string(), self.span.extent_json(span)); }
+            None => {}
+        }
+        for (f, v) in fields.iter().zip(values.move_iter()) {
+            let value = if *f == "qualname" {
+                json::String(self.krate.clone().append("::").append(v.as_slice()))
+            } else if FmtStrs::is_id_field(*f) {
+                match from_str::<u64>(v.as_slice()) {
+                    Some(n) => json::Number(n as f64),
+                    None => json::Null,
+                }
+            } else {
+                json::String(v)
+            };
+            row.insert(f.to_string(), value);
+        }
+        row
+    }
+
+    fn check_values(&self,
+                    kind: &'static str,
+                    fields: &Vec<&'static str>,
+                    values: &Vec<String>,
+                    span: Span) {
         if values.len() != fields.len() {
             self.span.sess.span_bug(span, format!(
                 "Mismatch between length of fields for '{}', expected '{}', found '{}'",
                 kind, fields.len(), values.len()).as_slice());
         }
+    }
+
+    pub fn make_values_str(&self,
+                           kind: &'static str,
+                           fields: &Vec<&'static str>,
+                           values: Vec<String>,
+                           span: Span) -> Option<String> {
+        self.check_values(kind, fields, &values, span);
 
         let values = values.iter().map(|s| {
             if s.len() > 1020 {
@@ -190,6 +299,13 @@ impl<'a> FmtStrs<'a> {
             return;
         }
 
+        if self.recorder.is_json() {
+            self.check_values(label, fields, &values, span);
+            let row = self.make_json_row(label, fields, values, None);
+            self.recorder.record_json(FmtStrs::json_section(kind), row);
+            return;
+        }
+
         let values_str = match self.make_values_str(label, fields, values, span) {
             Some(vs) => vs,
             None => return,
@@ -219,6 +335,13 @@ impl<'a> FmtStrs<'a> {
                                              which does not require a span", label).as_slice());
         }
 
+        if self.recorder.is_json() {
+            self.check_values(label, fields, &values, span);
+            let row = self.make_json_row(label, fields, values, Some(sub_span));
+            self.recorder.record_json(FmtStrs::json_section(kind), row);
+            return;
+        }
+
         let values_str = match self.make_values_str(label, fields, values, span) {
             Some(vs) => vs,
             None => return,
@@ -543,6 +666,12 @@ impl<'a> FmtStrs<'a> {
                               svec!(name));
     }
 
+    pub fn end_external_crates(&mut self) {
+        if !self.recorder.is_json() {
+            self.recorder.record("end_external_crates\n");
+        }
+    }
+
     pub fn external_crate_str(&mut self,
                               span: Span,
                               name: &str,
@@ -563,6 +692,17 @@ impl<'a> FmtStrs<'a> {
                               svec!(0u, 0u, qualname, 0u));
     }
 
+    pub fn macro_use_str(&mut self,
+                         span: Span,
+                         sub_span: Option<Span>,
+                         name: &str,
+                         scope_id: NodeId) {
+        self.check_and_record(MacroUse,
+                              span,
+                              sub_span,
+                              svec!(name, scope_id));
+    }
+
     // A slightly generic function for a reference to an item of any kind.
     pub fn ref_str(&mut self,
                    kind: Row,
@@ -12,7 +12,9 @@ use driver::session::Session;
 
 use middle::save::generated_code;
 
+use serialize::json;
 use std::cell::Cell;
+use std::collections::TreeMap;
 
 use syntax::ast;
 use syntax::codemap::*;
@@ -40,6 +42,25 @@ impl<'a> SpanUtils<'a> {
                 hi_loc.line, hi_loc.col.to_uint(), hi_pos.to_uint())
     }
 
+    // The same extent as an object for the JSON output. Unlike the CSV output,
+    // columns are 1-based, to match the JSON diagnostics.
+    pub fn extent_json(&self, span: Span) -> json::Json {
+        let lo_loc = self.sess.codemap().lookup_char_pos(span.lo);
+        let hi_loc = self.sess.codemap().lookup_char_pos(span.hi);
+        let lo_pos = self.sess.codemap().lookup_byte_offset(span.lo).pos;
+        let hi_pos = self.sess.codemap().lookup_byte_offset(span.hi).pos;
+
+        let mut obj = TreeMap::new();
+        obj.insert("file_name".to_string(), json::String(lo_loc.file.name.clone()));
+        obj.insert("byte_start".to_string(), json::Number(lo_pos.to_uint() as f64));
+        obj.insert("byte_end".to_string(), json::Number(hi_pos.to_uint() as f64));
+        obj.insert("line_start".to_string(), json::Number(lo_loc.line as f64));
+        obj.insert("line_end".to_string(), json::Number(hi_loc.line as f64));
+        obj.insert("column_start".to_string(), json::Number((lo_loc.col.to_uint() + 1) as f64));
+        obj.insert("column_end".to_string(), json::Number((hi_loc.col.to_uint() + 1) as f64));
+        json::Object(obj)
+    }
+
     // sub_span starts at span.lo, so we need to adjust the positions etc.
     // If sub_span is None, we don't need to adjust.
     pub fn make_sub_span(&self, span: Span, sub_span: Option<Span>) -> Option<Span> {
@@ -31,7 +31,7 @@ use driver::config;
 use driver::config::{NoDebugInfo, FullDebugInfo};
 use driver::session::Session;
 use driver::driver::{CrateAnalysis, CrateTranslation};
-use lib::llvm::{ModuleRef, ValueRef, BasicBlockRef};
+use lib::llvm::{ContextRef, ModuleRef, ValueRef, BasicBlockRef};
 use lib::llvm::{llvm, Vector};
 use lib;
 use metadata::{csearch, encoder, loader};
@@ -77,8 +77,9 @@ use util::nodemap::NodeMap;
 
 use arena::TypedArena;
 use libc::{c_uint, uint64_t};
-use std::c_str::ToCStr;
+use std::c_str::{CString, ToCStr};
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::{i8, i16, i32, i64};
 use std::gc::Gc;
@@ -1700,10 +1701,17 @@ pub fn trans_struct_def(ccx: &CrateContext, struct_def: Gc<ast::StructDef>) {
 // separate modules in the compiled program.  That's because modules exist
 // only as a convenience for humans working with the code, to organize names
 // and control visibility.
+//
+// When the crate is split into several codegen units, every unit walks all of
+// the modules but only translates its own share of the items in them.
 pub fn trans_mod(ccx: &CrateContext, m: &ast::Mod) {
     let _icx = push_ctxt("trans_mod");
     for item in m.items.iter() {
-        trans_item(ccx, &**item);
+        match item.node {
+            ast::ItemMod(..) => trans_item(ccx, &**item),
+            _ if ccx.is_in_current_unit(item.id) => trans_item(ccx, &**item),
+            _ => {}
+        }
     }
 }
 
@@ -2243,7 +2251,9 @@ pub fn write_metadata(cx: &CrateContext, krate: &ast::Crate) -> Vec<u8> {
     let any_library = cx.sess().crate_types.borrow().iter().any(|ty| {
         *ty != config::CrateTypeExecutable
     });
-    if !any_library {
+    let emit_metadata = cx.sess().opts.output_types
+                          .contains(&link::OutputTypeMetadata);
+    if !any_library && !emit_metadata {
         return Vec::new()
     }
 
@@ -2279,6 +2289,140 @@ pub fn write_metadata(cx: &CrateContext, krate: &ast::Crate) -> Vec<u8> {
     return metadata;
 }
 
+// Registers the symbol of every item the metadata refers to by symbol, the
+// same way translating the item would.
+struct SymbolCollector<'a> {
+    ccx: &'a CrateContext,
+}
+
+impl<'a> Visitor<()> for SymbolCollector<'a> {
+    fn visit_item(&mut self, item: &ast::Item, _: ()) {
+        let ccx = self.ccx;
+        match item.node {
+            ast::ItemFn(_, _, _, ref generics, _) |
+            ast::ItemStruct(_, ref generics) |
+            ast::ItemEnum(_, ref generics) |
+            ast::ItemImpl(ref generics, _, _, _)
+                if generics.is_type_parameterized() => {}
+            ast::ItemFn(..) | ast::ItemStatic(..) => {
+                get_item_val(ccx, item.id);
+            }
+            ast::ItemImpl(_, _, _, ref methods) => {
+                for method in methods.iter() {
+                    if !method.generics.is_type_parameterized() {
+                        get_item_val(ccx, method.id);
+                    }
+                }
+            }
+            ast::ItemEnum(ref enum_definition, _) => {
+                for variant in enum_definition.variants.iter() {
+                    match variant.node.kind {
+                        ast::TupleVariantKind(ref args) if args.len() > 0 => {
+                            get_item_val(ccx, variant.node.id);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ast::ItemStruct(struct_def, _) => {
+                match struct_def.ctor_id {
+                    Some(ctor_id) if struct_def.fields.len() > 0 => {
+                        get_item_val(ccx, ctor_id);
+                    }
+                    Some(_) | None => {}
+                }
+            }
+            ast::ItemForeignMod(ref foreign_mod) => {
+                for foreign_item in foreign_mod.items.iter() {
+                    let lname = foreign::link_name(&**foreign_item);
+                    ccx.item_symbols.borrow_mut().insert(foreign_item.id,
+                                                         lname.get().to_string());
+                }
+            }
+            _ => {}
+        }
+        visit::walk_item(self, item, ());
+    }
+}
+
+// Calls `f` on every function and global variable of `llmod`.
+unsafe fn iter_globals(llmod: ModuleRef, f: |ValueRef|) {
+    let mut v = llvm::LLVMGetFirstFunction(llmod);
+    while v.is_not_null() {
+        f(v);
+        v = llvm::LLVMGetNextFunction(v);
+    }
+    let mut v = llvm::LLVMGetFirstGlobal(llmod);
+    while v.is_not_null() {
+        f(v);
+        v = llvm::LLVMGetNextGlobal(v);
+    }
+}
+
+// Items which aren't reachable are given internal linkage when they're
+// declared, which only works as long as they're declared in the module which
+// defines them. When the crate is split into codegen units, a unit can refer
+// to an item translated by another unit. Those items, and only those, are
+// given external linkage, with hidden visibility so that they still aren't
+// exported from the final product.
+unsafe fn promote_shared_symbols(modules: &[(ContextRef, ModuleRef)]) {
+    unsafe fn is_internal(v: ValueRef) -> bool {
+        llvm::LLVMGetLinkage(v) == lib::llvm::InternalLinkage as c_uint
+    }
+    unsafe fn name_of(v: ValueRef) -> String {
+        let name = CString::new(llvm::LLVMGetValueName(v), false);
+        name.as_str().unwrap().to_string()
+    }
+
+    let mut shared = HashSet::new();
+    for &(_, llmod) in modules.iter() {
+        iter_globals(llmod, |v| {
+            if llvm::LLVMIsDeclaration(v) == lib::llvm::True && is_internal(v) {
+                lib::llvm::SetLinkage(v, lib::llvm::ExternalLinkage);
+                shared.insert(name_of(v));
+            }
+        });
+    }
+
+    for &(_, llmod) in modules.iter() {
+        iter_globals(llmod, |v| {
+            if llvm::LLVMIsDeclaration(v) == lib::llvm::False && is_internal(v) &&
+               shared.contains(&name_of(v)) {
+                lib::llvm::SetLinkage(v, lib::llvm::ExternalLinkage);
+                llvm::LLVMSetVisibility(v, lib::llvm::HiddenVisibility as c_uint);
+            }
+        });
+    }
+}
+
+/// Encodes the metadata of the crate without translating it, for
+/// `--emit=metadata`. Items are only declared, to find out their symbols, so
+/// the metadata is the same as that of a full build.
+pub fn trans_metadata(krate: &ast::Crate,
+                      analysis: CrateAnalysis) -> (ty::ctxt, Vec<u8>) {
+    let CrateAnalysis { ty_cx: tcx, exp_map2, reachable, name, .. } = analysis;
+
+    let link_meta = link::build_link_meta(&tcx.sess, krate, name);
+    let mut llmod_id = link_meta.crate_name.clone();
+    llmod_id.push_str(".rs");
+
+    let ccx = CrateContext::new(llmod_id.as_slice(), tcx, exp_map2,
+                                Sha256::new(), link_meta, reachable);
+    {
+        let _icx = push_ctxt("symbols");
+        let mut v = SymbolCollector { ccx: &ccx };
+        visit::walk_crate(&mut v, krate, ());
+    }
+    let metadata = write_metadata(&ccx, krate);
+
+    unsafe {
+        llvm::LLVMDisposeModule(ccx.metadata_llmod);
+        llvm::LLVMDisposeModule(ccx.llmod);
+        llvm::LLVMContextDispose(ccx.llcx);
+    }
+    (ccx.tcx, metadata)
+}
+
 pub fn trans_crate(krate: ast::Crate,
                    analysis: CrateAnalysis) -> (ty::ctxt, CrateTranslation) {
     let CrateAnalysis { ty_cx: tcx, exp_map2, reachable, name, .. } = analysis;
@@ -2314,21 +2458,33 @@ pub fn trans_crate(krate: ast::Crate,
     let mut llmod_id = link_meta.crate_name.clone();
     llmod_id.push_str(".rs");
 
-    let ccx = CrateContext::new(llmod_id.as_slice(), tcx, exp_map2,
-                                Sha256::new(), link_meta, reachable);
+    let mut ccx = CrateContext::new(llmod_id.as_slice(), tcx, exp_map2,
+                                    Sha256::new(), link_meta, reachable);
 
     // First, verify intrinsics.
     intrinsic::check_intrinsics(&ccx);
 
-    // Next, translate the module.
-    {
-        let _icx = push_ctxt("text");
-        trans_mod(&ccx, &krate.module);
-    }
+    // Next, translate the module, into one LLVM module per codegen unit.
+    let mut modules = Vec::new();
+    for unit in range(0, ccx.sess().opts.cg.codegen_units) {
+        if unit > 0 {
+            let name = format!("{}.{}.rs", ccx.link_meta.crate_name, unit);
+            modules.push(ccx.start_next_unit(name.as_slice()));
+        }
+
+        {
+            let _icx = push_ctxt("text");
+            trans_mod(&ccx, &krate.module);
+        }
 
-    glue::emit_tydescs(&ccx);
-    if ccx.sess().opts.debuginfo != NoDebugInfo {
-        debuginfo::finalize(&ccx);
+        glue::emit_tydescs(&ccx);
+        if ccx.sess().opts.debuginfo != NoDebugInfo {
+            debuginfo::finalize(&ccx);
+        }
+    }
+    modules.push((ccx.llcx, ccx.llmod));
+    if modules.len() > 1 {
+        unsafe { promote_shared_symbols(modules.as_slice()) }
     }
 
     // Translate the metadata.
@@ -2361,10 +2517,11 @@ pub fn trans_crate(krate: ast::Crate,
             println!("{:7u} {}", *v, *k);
         }
     }
+    if ccx.sess().print_mono_items() {
+        monomorphize::print_mono_items(&ccx);
+    }
 
-    let llcx = ccx.llcx;
     let link_meta = ccx.link_meta.clone();
-    let llmod = ccx.llmod;
 
     let mut reachable: Vec<String> = ccx.reachable.iter().filter_map(|id| {
         ccx.item_symbols.borrow().find(id).map(|s| s.to_string())
@@ -2399,8 +2556,7 @@ pub fn trans_crate(krate: ast::Crate,
     let no_builtins = attr::contains_name(krate.attrs.as_slice(), "no_builtins");
 
     (ccx.tcx, CrateTranslation {
-        context: llcx,
-        module: llmod,
+        modules: modules,
         link: link_meta,
         metadata_module: metadata_module,
         metadata: metadata,
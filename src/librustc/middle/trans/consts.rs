@@ -171,11 +171,19 @@ pub fn get_const_val(cx: &CrateContext,
                      mut def_id: ast::DefId) -> (ValueRef, bool) {
     let contains_key = cx.const_values.borrow().contains_key(&def_id.node);
     if !ast_util::is_local(def_id) || !contains_key {
-        if !ast_util::is_local(def_id) {
+        let is_local = ast_util::is_local(def_id);
+        if !is_local {
             def_id = inline::maybe_instantiate_inline(cx, def_id);
         }
 
         match cx.tcx.map.expect_item(def_id.node).node {
+            // Only the codegen unit which the static belongs to defines it.
+            // Other units just declare it, which still records its value in
+            // `const_values` for it to be inlined.
+            ast::ItemStatic(_, ast::MutImmutable, _)
+                    if is_local && !cx.is_in_current_unit(def_id.node) => {
+                base::get_item_val(cx, def_id.node);
+            }
             ast::ItemStatic(_, ast::MutImmutable, _) => {
                 trans_const(cx, ast::MutImmutable, def_id.node);
             }
@@ -20,7 +20,7 @@ use middle::trans::base;
 use middle::trans::builder::Builder;
 use middle::trans::common::{ExternMap,tydesc_info,BuilderRef_res};
 use middle::trans::debuginfo;
-use middle::trans::monomorphize::MonoId;
+use middle::trans::monomorphize::{MonoId, MonoItem};
 use middle::trans::type_::Type;
 use middle::ty;
 use util::sha2::Sha256;
@@ -48,11 +48,16 @@ pub struct Stats {
     pub llvm_insns: RefCell<HashMap<String, uint>>,
     // (ident, time-in-ms, llvm-instructions)
     pub fn_stats: RefCell<Vec<(String, uint, uint)> >,
+    /// Only recorded with `-Z print-mono-items`.
+    pub mono_items: RefCell<Vec<MonoItem>>,
 }
 
 pub struct CrateContext {
     pub llmod: ModuleRef,
     pub llcx: ContextRef,
+    /// The codegen unit being translated into `llmod`, see
+    /// `start_next_unit`.
+    pub codegen_unit: uint,
     pub metadata_llmod: ModuleRef,
     pub td: TargetData,
     pub tn: TypeNames,
@@ -137,30 +142,10 @@ impl CrateContext {
                -> CrateContext {
         unsafe {
             let llcx = llvm::LLVMContextCreate();
-            let llmod = name.with_c_str(|buf| {
-                llvm::LLVMModuleCreateWithNameInContext(buf, llcx)
-            });
-            let metadata_llmod = format!("{}_metadata", name).with_c_str(|buf| {
-                llvm::LLVMModuleCreateWithNameInContext(buf, llcx)
-            });
-            tcx.sess
-               .targ_cfg
-               .target_strs
-               .data_layout
-               .as_slice()
-               .with_c_str(|buf| {
-                llvm::LLVMSetDataLayout(llmod, buf);
-                llvm::LLVMSetDataLayout(metadata_llmod, buf);
-            });
-            tcx.sess
-               .targ_cfg
-               .target_strs
-               .target_triple
-               .as_slice()
-               .with_c_str(|buf| {
-                llvm::LLVMRustSetNormalizedTarget(llmod, buf);
-                llvm::LLVMRustSetNormalizedTarget(metadata_llmod, buf);
-            });
+            let llmod = create_module(&tcx.sess, llcx, name);
+            let metadata_llmod = create_module(&tcx.sess, llcx,
+                                               format!("{}_metadata",
+                                                       name).as_slice());
 
             let td = mk_target_data(tcx.sess
                                        .targ_cfg
@@ -177,6 +162,7 @@ impl CrateContext {
             let mut ccx = CrateContext {
                 llmod: llmod,
                 llcx: llcx,
+                codegen_unit: 0,
                 metadata_llmod: metadata_llmod,
                 td: td,
                 tn: TypeNames::new(),
@@ -220,6 +206,7 @@ impl CrateContext {
                     n_llvm_insns: Cell::new(0u),
                     llvm_insns: RefCell::new(HashMap::new()),
                     fn_stats: RefCell::new(Vec::new()),
+                    mono_items: RefCell::new(Vec::new()),
                 },
                 int_type: Type::from_ref(ptr::mut_null()),
                 opaque_vec_type: Type::from_ref(ptr::mut_null()),
@@ -230,14 +217,7 @@ impl CrateContext {
                 intrinsics: RefCell::new(HashMap::new()),
             };
 
-            ccx.int_type = Type::int(&ccx);
-            ccx.opaque_vec_type = Type::opaque_vec(&ccx);
-
-            let mut str_slice_ty = Type::named_struct(&ccx, "str_slice");
-            str_slice_ty.set_struct_body([Type::i8p(&ccx), ccx.int_type], false);
-            ccx.tn.associate_type("str_slice", &str_slice_ty);
-
-            ccx.tn.associate_type("tydesc", &Type::tydesc(&ccx, str_slice_ty));
+            ccx.init_types();
 
             if ccx.sess().count_llvm_insns() {
                 base::init_insn_ctxt()
@@ -247,6 +227,70 @@ impl CrateContext {
         }
     }
 
+    fn init_types(&mut self) {
+        self.int_type = Type::int(self);
+        self.opaque_vec_type = Type::opaque_vec(self);
+
+        let mut str_slice_ty = Type::named_struct(self, "str_slice");
+        str_slice_ty.set_struct_body([Type::i8p(self), self.int_type], false);
+        self.tn.associate_type("str_slice", &str_slice_ty);
+
+        self.tn.associate_type("tydesc", &Type::tydesc(self, str_slice_ty));
+    }
+
+    /// Moves on to the next codegen unit, which is translated into a module
+    /// of its own, in an LLVM context of its own so that the units can be
+    /// optimized in parallel. Everything referring to LLVM values of the
+    /// previous unit is reset, the context and module of which are returned.
+    /// The (still empty) metadata module moves to the new context as well, as
+    /// the metadata is written once the last unit has been translated.
+    pub fn start_next_unit(&mut self, name: &str) -> (ContextRef, ModuleRef) {
+        let finished = (self.llcx, self.llmod);
+        unsafe {
+            llvm::LLVMDisposeModule(self.metadata_llmod);
+
+            self.llcx = llvm::LLVMContextCreate();
+            self.llmod = create_module(self.sess(), self.llcx, name);
+            self.metadata_llmod = create_module(self.sess(), self.llcx,
+                                                format!("{}_metadata",
+                                                        name).as_slice());
+            self.builder = BuilderRef_res(llvm::LLVMCreateBuilderInContext(self.llcx));
+        }
+        self.codegen_unit += 1;
+        self.tn = TypeNames::new();
+        self.externs = RefCell::new(HashMap::new());
+        self.item_vals = RefCell::new(NodeMap::new());
+        self.drop_glues = RefCell::new(HashMap::new());
+        self.tydescs = RefCell::new(HashMap::new());
+        self.finished_tydescs = Cell::new(false);
+        self.external = RefCell::new(DefIdMap::new());
+        self.monomorphized = RefCell::new(HashMap::new());
+        self.vtables = RefCell::new(HashMap::new());
+        self.const_cstr_cache = RefCell::new(HashMap::new());
+        self.const_globals = RefCell::new(HashMap::new());
+        self.const_values = RefCell::new(NodeMap::new());
+        self.extern_const_values = RefCell::new(DefIdMap::new());
+        self.closure_bare_wrapper_cache = RefCell::new(HashMap::new());
+        self.lltypes = RefCell::new(HashMap::new());
+        self.llsizingtypes = RefCell::new(HashMap::new());
+        self.all_llvm_symbols = RefCell::new(HashSet::new());
+        self.dbg_cx = if self.sess().opts.debuginfo != NoDebugInfo {
+            Some(debuginfo::CrateDebugContext::new(self.llmod))
+        } else {
+            None
+        };
+        self.eh_personality = RefCell::new(None);
+        self.intrinsics = RefCell::new(HashMap::new());
+        self.init_types();
+        finished
+    }
+
+    /// Whether the top-level item `id` is translated as part of the current
+    /// codegen unit. Other units only declare it.
+    pub fn is_in_current_unit(&self, id: ast::NodeId) -> bool {
+        id as uint % self.sess().opts.cg.codegen_units == self.codegen_unit
+    }
+
     pub fn tcx<'a>(&'a self) -> &'a ty::ctxt {
         &self.tcx
     }
@@ -283,10 +327,23 @@ impl CrateContext {
     // but it could be enabled (with patched LLVM)
     pub fn is_split_stack_supported(&self) -> bool {
         let ref cfg = self.sess().targ_cfg;
-        cfg.os != abi::OsiOS || cfg.arch != abi::Arm
+        cfg.target_opts.morestack && (cfg.os != abi::OsiOS || cfg.arch != abi::Arm)
     }
 }
 
+unsafe fn create_module(sess: &Session, llcx: ContextRef, name: &str) -> ModuleRef {
+    let llmod = name.with_c_str(|buf| {
+        llvm::LLVMModuleCreateWithNameInContext(buf, llcx)
+    });
+    sess.targ_cfg.target_strs.data_layout.as_slice().with_c_str(|buf| {
+        llvm::LLVMSetDataLayout(llmod, buf);
+    });
+    sess.targ_cfg.target_strs.target_triple.as_slice().with_c_str(|buf| {
+        llvm::LLVMRustSetNormalizedTarget(llmod, buf);
+    });
+    llmod
+}
+
 fn declare_intrinsic(ccx: &CrateContext, key: & &'static str) -> Option<ValueRef> {
     macro_rules! ifn (
         ($name:expr fn() -> $ret:expr) => (
@@ -13,6 +13,7 @@ use metadata::csearch;
 use middle::astencode;
 use middle::trans::base::{push_ctxt, trans_item, get_item_val, trans_fn};
 use middle::trans::common::*;
+use middle::trans::monomorphize;
 use middle::ty;
 
 use syntax::ast;
@@ -53,6 +54,18 @@ pub fn maybe_instantiate_inline(ccx: &CrateContext, fn_id: ast::DefId)
             ccx.stats.n_inlines.set(ccx.stats.n_inlines.get() + 1);
             trans_item(ccx, &*item);
 
+            // Generic functions are recorded once they're monomorphized.
+            match item.node {
+                ast::ItemFn(_, _, _, ref generics, _)
+                        if ccx.sess().print_mono_items() &&
+                           generics.ty_params.is_empty() => {
+                    let llfn = get_item_val(ccx, item.id);
+                    monomorphize::record_mono_item(ccx, monomorphize::Inlined,
+                                                   local_def(item.id), [], llfn);
+                }
+                _ => {}
+            }
+
             // We're bringing an external global into this crate, but we don't
             // want to create two copies of the global. If we do this, then if
             // you take the address of the global in two separate crates you get
@@ -134,6 +147,10 @@ pub fn maybe_instantiate_inline(ccx: &CrateContext, fn_id: ast::DefId)
               let llfn = get_item_val(ccx, mth.id);
               trans_fn(ccx, &*mth.decl, &*mth.body, llfn,
                        &param_substs::empty(), mth.id, []);
+              if ccx.sess().print_mono_items() {
+                  monomorphize::record_mono_item(ccx, monomorphize::Inlined,
+                                                 local_def(mth.id), [], llfn);
+              }
           }
           local_def(mth.id)
         }
@@ -10,7 +10,7 @@
 
 use back::link::exported_name;
 use driver::session;
-use lib::llvm::ValueRef;
+use lib::llvm::{ValueRef, llvm};
 use middle::subst;
 use middle::subst::Subst;
 use middle::trans::base::{set_llvm_fn_attrs, set_inline_hint};
@@ -21,12 +21,13 @@ use middle::trans::common::*;
 use middle::trans::intrinsic;
 use middle::ty;
 use middle::typeck;
-use util::ppaux::Repr;
+use util::ppaux::{Repr, ty_to_str};
 
 use syntax::abi;
 use syntax::ast;
 use syntax::ast_map;
 use syntax::ast_util::local_def;
+use std::collections::HashMap;
 use std::hash::{sip, Hash};
 
 pub fn monomorphic_fn(ccx: &CrateContext,
@@ -237,10 +238,133 @@ pub fn monomorphic_fn(ccx: &CrateContext,
 
     ccx.monomorphizing.borrow_mut().insert(fn_id, depth);
 
+    if ccx.sess().print_mono_items() {
+        let params: Vec<ty::t> = real_substs.types.iter().map(|t| *t).collect();
+        record_mono_item(ccx, Monomorphized, fn_id, params.as_slice(), lldecl);
+    }
+
     debug!("leaving monomorphic fn {}", ty::item_path_str(ccx.tcx(), fn_id));
     (lldecl, false)
 }
 
+#[deriving(PartialEq)]
+pub enum MonoItemKind {
+    Monomorphized,
+    Inlined,
+}
+
+/// A function translated into this crate for `-Z print-mono-items`: either an
+/// instantiation of a generic function, or a non-generic function inlined
+/// from another crate.
+pub struct MonoItem {
+    pub kind: MonoItemKind,
+    /// The crate the function is defined in.
+    pub krate: String,
+    pub path: String,
+    /// The type parameters of the instantiation, empty for inlined functions.
+    pub params: Vec<String>,
+    /// The number of LLVM instructions of the function, before optimization
+    /// and not counting any closures it contains.
+    pub llvm_insns: uint,
+}
+
+/// Records the translation of `fn_id` as `llfn` for `-Z print-mono-items`.
+/// `fn_id` may be the local copy of an inlined function, in which case it's
+/// attributed to the crate it was inlined from.
+pub fn record_mono_item(ccx: &CrateContext,
+                        kind: MonoItemKind,
+                        fn_id: ast::DefId,
+                        params: &[ty::t],
+                        llfn: ValueRef) {
+    let llvm_insns = match count_llvm_insns(llfn) {
+        // Intrinsics are declarations which are never given a body.
+        None => return,
+        Some(n) => n,
+    };
+    let source = match ccx.external_srcs.borrow().find(&fn_id.node) {
+        Some(&did) if fn_id.krate == ast::LOCAL_CRATE => did,
+        _ => fn_id,
+    };
+    let krate = if source.krate == ast::LOCAL_CRATE {
+        ccx.link_meta.crate_name.clone()
+    } else {
+        ccx.sess().cstore.get_crate_data(source.krate).name.clone()
+    };
+    ccx.stats.mono_items.borrow_mut().push(MonoItem {
+        kind: kind,
+        krate: krate,
+        path: ty::item_path_str(ccx.tcx(), source),
+        params: params.iter().map(|&t| ty_to_str(ccx.tcx(), t)).collect(),
+        llvm_insns: llvm_insns,
+    });
+}
+
+// Returns the number of instructions in the body of `llfn`, or `None` if it's
+// only a declaration.
+fn count_llvm_insns(llfn: ValueRef) -> Option<uint> {
+    unsafe {
+        let mut bb = llvm::LLVMGetFirstBasicBlock(llfn);
+        if bb.is_null() {
+            return None
+        }
+        let mut n = 0u;
+        while bb.is_not_null() {
+            let mut insn = llvm::LLVMGetFirstInstruction(bb);
+            while insn.is_not_null() {
+                n += 1;
+                insn = llvm::LLVMGetNextInstruction(insn);
+            }
+            bb = llvm::LLVMGetNextBasicBlock(bb);
+        }
+        Some(n)
+    }
+}
+
+/// Prints the functions recorded by `record_mono_item`, largest first,
+/// followed by the total size of all the instantiations of each function.
+pub fn print_mono_items(ccx: &CrateContext) {
+    let mut items = ccx.stats.mono_items.borrow_mut();
+    items.sort_by(|a, b| {
+        match b.llvm_insns.cmp(&a.llvm_insns) {
+            Equal => (&a.path, &a.params).cmp(&(&b.path, &b.params)),
+            order => order,
+        }
+    });
+
+    println!("--- mono items ---");
+    let mut totals = HashMap::new();
+    for item in items.iter() {
+        let kind = match item.kind {
+            Monomorphized => "mono",
+            Inlined => "inline",
+        };
+        let params = if item.params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", item.params.connect(", "))
+        };
+        println!("{:8u} {:6s} {} {}{}", item.llvm_insns, kind, item.krate,
+                 item.path, params);
+
+        let total = totals.find_or_insert((item.krate.as_slice(),
+                                           item.path.as_slice()), (0u, 0u));
+        let (insns, count) = *total;
+        *total = (insns + item.llvm_insns, count + 1);
+    }
+
+    println!("--- mono item totals ---");
+    let mut totals: Vec<_> = totals.move_iter().collect();
+    totals.sort_by(|&(a, (a_insns, _)), &(b, (b_insns, _))| {
+        match b_insns.cmp(&a_insns) {
+            Equal => a.cmp(&b),
+            order => order,
+        }
+    });
+    for &((krate, path), (insns, count)) in totals.iter() {
+        println!("{:8u} {:6u} {} {}", insns, count, krate, path);
+    }
+}
+
 // Used to identify cached monomorphized functions and vtables
 #[deriving(PartialEq, Eq, Hash)]
 pub struct MonoParamId {
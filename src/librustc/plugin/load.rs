@@ -42,8 +42,9 @@ pub type PluginRegistrarFun =
 pub struct Plugins {
     /// Source code of exported macros.
     pub macros: Vec<ExportedMacros>,
-    /// Registrars, as function pointers.
-    pub registrars: Vec<PluginRegistrarFun>,
+    /// Registrars, as function pointers, along with the name of the crate
+    /// providing each.
+    pub registrars: Vec<(ast::Ident, PluginRegistrarFun)>,
 }
 
 struct PluginLoader<'a> {
@@ -102,7 +103,7 @@ impl<'a> Visitor<()> for PluginLoader<'a> {
 
                 match (lib, registrar_symbol) {
                     (Some(lib), Some(symbol))
-                        => self.dylink_registrar(vi, lib, symbol),
+                        => self.dylink_registrar(vi, name, lib, symbol),
                     _ => (),
                 }
             }
@@ -113,7 +114,8 @@ impl<'a> Visitor<()> for PluginLoader<'a> {
 
 impl<'a> PluginLoader<'a> {
     // Dynamically link a registrar function into the compiler process.
-    fn dylink_registrar(&mut self, vi: &ast::ViewItem, path: Path, symbol: String) {
+    fn dylink_registrar(&mut self, vi: &ast::ViewItem, name: ast::Ident,
+                        path: Path, symbol: String) {
         // Make sure the path contains a / or the linker will search for it.
         let path = os::make_absolute(&path);
 
@@ -135,7 +137,7 @@ impl<'a> PluginLoader<'a> {
                     Err(err) => self.sess.span_fatal(vi.span, err.as_slice())
                 };
 
-            self.plugins.registrars.push(registrar);
+            self.plugins.registrars.push((name, registrar));
 
             // Intentionally leak the dynamic library. We can't ever unload it
             // since the library can make things that will live arbitrarily long
@@ -462,8 +462,11 @@ fn write_shared(cx: &Context,
     // Write out the shared files. Note that these are shared among all rustdoc
     // docs placed in the output directory, so this needs to be a synchronized
     // operation with respect to all other rustdocs running around.
+    // The lock is released when the file is closed on return.
     try!(mkdir(&cx.dst));
-    let _lock = ::flock::Lock::new(&cx.dst.join(".lock"));
+    let mut lock = try!(File::open_mode(&cx.dst.join(".lock"), io::Open,
+                                        io::Write));
+    try!(lock.lock_exclusive());
 
     // Add all the static files. These may already exist, but we just
     // overwrite them anyway to make sure that they're fresh and up-to-date.
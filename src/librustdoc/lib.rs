@@ -60,7 +60,6 @@ pub mod passes;
 pub mod plugins;
 pub mod visit_ast;
 pub mod test;
-mod flock;
 
 type Pass = (&'static str,                                      // name
              fn(clean::Crate) -> plugins::PluginResult,         // fn
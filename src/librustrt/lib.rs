@@ -60,10 +60,12 @@ pub mod local;
 pub mod local_data;
 pub mod local_heap;
 pub mod mutex;
+pub mod pty;
 pub mod rtio;
 pub mod stack;
 pub mod task;
 pub mod thread;
+pub mod time;
 pub mod unwind;
 
 /// The interface to the current runtime.
@@ -80,6 +82,16 @@ pub trait Runtime {
     fn maybe_yield(~self, cur_task: Box<Task>);
     fn deschedule(~self, times: uint, cur_task: Box<Task>,
                   f: |BlockedTask| -> Result<(), BlockedTask>);
+    /// As `deschedule`, except that the task is also woken once `timeout_ms`
+    /// milliseconds have passed. The handles given to `f` are always
+    /// selectable, so whichever of them or the timeout is first to wake the
+    /// task takes ownership of it. If the timeout can't be set up, the task is
+    /// put back in place without blocking or calling `f`, and an error is
+    /// returned.
+    fn deschedule_timeout(~self, times: uint, timeout_ms: u64,
+                          cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>)
+                          -> rtio::IoResult<()>;
     fn reawaken(~self, to_wake: Box<Task>);
 
     // Miscellaneous calls which are very different depending on what context
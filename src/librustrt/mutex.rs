@@ -195,6 +195,19 @@ impl StaticNativeMutex {
     /// is unsafe because the mutex may not be currently locked.
     pub unsafe fn wait_noguard(&self) { self.inner.wait() }
 
+    /// Block on the internal condition variable for at most `ms` milliseconds.
+    ///
+    /// As with `wait_noguard`, this may wake up spuriously, and it may also
+    /// return early when waiting for very long periods. It's up to the caller
+    /// to check how long it has waited.
+    ///
+    /// # Unsafety
+    ///
+    /// This method is unsafe for the same reasons as `wait_noguard`.
+    pub unsafe fn wait_timeout_noguard(&self, ms: u64) {
+        self.inner.wait_timeout(ms)
+    }
+
     /// Signals a thread in `wait` to wake up
     ///
     /// # Unsafety
@@ -302,6 +315,16 @@ impl NativeMutex {
     /// `StaticNativeMutex::wait_noguard`.
     pub unsafe fn wait_noguard(&self) { self.inner.wait_noguard() }
 
+    /// Block on the internal condition variable for at most `ms` milliseconds.
+    ///
+    /// # Unsafety
+    ///
+    /// This method is unsafe due to the same reasons as
+    /// `StaticNativeMutex::wait_timeout_noguard`.
+    pub unsafe fn wait_timeout_noguard(&self, ms: u64) {
+        self.inner.wait_timeout_noguard(ms)
+    }
+
     /// Signals a thread in `wait` to wake up
     ///
     /// # Unsafety
@@ -323,6 +346,12 @@ impl<'a> LockGuard<'a> {
         self.lock.wait_noguard()
     }
 
+    /// Block on the internal condition variable for at most `ms` milliseconds.
+    /// See `StaticNativeMutex::wait_timeout_noguard`.
+    pub unsafe fn wait_timeout(&self, ms: u64) {
+        self.lock.wait_timeout_noguard(ms)
+    }
+
     /// Signals a thread in `wait` to wake up.
     pub unsafe fn signal(&self) {
         self.lock.signal_noguard()
@@ -336,13 +365,22 @@ impl<'a> Drop for LockGuard<'a> {
     }
 }
 
+// The longest a single timed wait lasts, which is short enough not to overflow
+// anywhere and long enough that callers waiting longer rarely need to wait
+// again.
+static MAX_WAIT_MS: u64 = 0x7fffffff;
+
 #[cfg(unix)]
 mod imp {
     use libc;
     use self::os::{PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER,
                    pthread_mutex_t, pthread_cond_t};
+    use core::cmp;
+    use core::mem;
+    use core::ptr;
     use core::ty::Unsafe;
     use core::kinds::marker;
+    use super::MAX_WAIT_MS;
 
     type pthread_mutexattr_t = libc::c_void;
     type pthread_condattr_t = libc::c_void;
@@ -500,6 +538,20 @@ mod imp {
         pub unsafe fn wait(&self) {
             pthread_cond_wait(self.cond.get(), self.lock.get());
         }
+        pub unsafe fn wait_timeout(&self, ms: u64) {
+            // pthread_cond_timedwait() takes an absolute time on the system
+            // clock, which is capped to keep it from overflowing
+            let ms = cmp::min(ms, MAX_WAIT_MS);
+            let mut now: libc::timeval = mem::zeroed();
+            gettimeofday(&mut now, ptr::mut_null());
+            let nsec = (now.tv_usec as u64) * 1000 + (ms % 1000) * 1000000;
+            let timeout = libc::timespec {
+                tv_sec: now.tv_sec +
+                        (ms / 1000 + nsec / 1000000000) as libc::time_t,
+                tv_nsec: (nsec % 1000000000) as libc::c_long,
+            };
+            pthread_cond_timedwait(self.cond.get(), self.lock.get(), &timeout);
+        }
         pub unsafe fn trylock(&self) -> bool {
             pthread_mutex_trylock(self.lock.get()) == 0
         }
@@ -518,6 +570,11 @@ mod imp {
 
         fn pthread_cond_wait(cond: *mut pthread_cond_t,
                              lock: *mut pthread_mutex_t) -> libc::c_int;
+        fn pthread_cond_timedwait(cond: *mut pthread_cond_t,
+                                  lock: *mut pthread_mutex_t,
+                                  abstime: *const libc::timespec) -> libc::c_int;
+        fn gettimeofday(timeval: *mut libc::timeval,
+                        tzp: *mut libc::c_void) -> libc::c_int;
         fn pthread_cond_signal(cond: *mut pthread_cond_t) -> libc::c_int;
     }
 }
@@ -526,9 +583,11 @@ mod imp {
 mod imp {
     use alloc::libc_heap::malloc_raw;
     use core::atomics;
+    use core::cmp;
     use core::ptr;
     use libc::{HANDLE, BOOL, LPSECURITY_ATTRIBUTES, c_void, DWORD, LPCSTR};
     use libc;
+    use super::MAX_WAIT_MS;
 
     type LPCRITICAL_SECTION = *mut c_void;
     static SPIN_COUNT: DWORD = 4000;
@@ -571,6 +630,13 @@ mod imp {
             self.lock();
         }
 
+        pub unsafe fn wait_timeout(&self, ms: u64) {
+            let ms = cmp::min(ms, MAX_WAIT_MS);
+            self.unlock();
+            WaitForSingleObject(self.getcond() as HANDLE, ms as DWORD);
+            self.lock();
+        }
+
         pub unsafe fn signal(&self) {
             assert!(SetEvent(self.getcond() as HANDLE) != 0);
         }
@@ -685,6 +751,15 @@ mod test {
         }
     }
 
+    #[test]
+    fn smoke_cond_timeout() {
+        static mut lock: StaticNativeMutex = NATIVE_MUTEX_INIT;
+        unsafe {
+            let guard = lock.lock();
+            guard.wait_timeout(10);
+        }
+    }
+
     #[test]
     fn smoke_lock_noguard() {
         static mut lock: StaticNativeMutex = NATIVE_MUTEX_INIT;
@@ -0,0 +1,93 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Pseudo-terminals, shared by the runtimes' implementations of `RtioPty`.
+//!
+//! Errors are reported through the `last_error` function each runtime passes
+//! in, which is called right after the failing call so that it can still
+//! inspect `errno`.
+
+use core::prelude::*;
+use libc::c_int;
+use libc;
+
+/// Opens a new pseudo-terminal, returning the descriptors of its master and
+/// slave ends. Neither is inherited by spawned processes unless it's passed
+/// as one of their stdio streams.
+#[cfg(unix)]
+pub fn open<E>(last_error: || -> E) -> Result<(c_int, c_int), E> {
+    use mutex::{StaticNativeMutex, NATIVE_MUTEX_INIT};
+
+    // ptsname() returns a static buffer, so it's only called under this lock,
+    // which every runtime shares.
+    static mut LOCK: StaticNativeMutex = NATIVE_MUTEX_INIT;
+
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if master == -1 { return Err(last_error()) }
+        let slave = if libc::grantpt(master) != 0 || libc::unlockpt(master) != 0 {
+            -1
+        } else {
+            let _guard = LOCK.lock();
+            match libc::ptsname(master) {
+                name if name.is_null() => -1,
+                name => libc::open(name as *const libc::c_char,
+                                   libc::O_RDWR | libc::O_NOCTTY, 0),
+            }
+        };
+        if slave == -1 {
+            let err = last_error();
+            libc::close(master);
+            return Err(err)
+        }
+        libc::fcntl(master, libc::F_SETFD, libc::FD_CLOEXEC);
+        libc::fcntl(slave, libc::F_SETFD, libc::FD_CLOEXEC);
+        Ok((master, slave))
+    }
+}
+
+/// Sets the window size of the terminal `fd` is one end of, in characters.
+#[cfg(unix)]
+pub fn set_winsize<E>(fd: c_int, width: int, height: int,
+                      last_error: || -> E) -> Result<(), E> {
+    let size = libc::winsize {
+        ws_row: height as libc::c_ushort,
+        ws_col: width as libc::c_ushort,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    match unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &size) } {
+        0 => Ok(()),
+        _ => Err(last_error()),
+    }
+}
+
+/// Returns the window size of the terminal `fd` is one end of, as a
+/// `(width, height)` pair in characters.
+#[cfg(unix)]
+pub fn get_winsize<E>(fd: c_int, last_error: || -> E) -> Result<(int, int), E> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    match unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } {
+        0 => Ok((size.ws_col as int, size.ws_row as int)),
+        _ => Err(last_error()),
+    }
+}
+
+/// Whether `code`, the error of a failed read from the master end, only means
+/// that every copy of the slave end has been closed. Linux reports this with
+/// EIO rather than as the end of the stream.
+pub fn is_hangup(code: uint) -> bool {
+    code == libc::EIO as uint
+}
@@ -117,6 +117,30 @@ pub struct ProcessConfig<'a> {
     /// If true, the child process is spawned in a detached state. On unix, this
     /// means that the child is the leader of a new process group.
     pub detach: bool,
+
+    /// Moves the child into a process group with a `setpgid` call in the child
+    /// process. An id of 0 makes the child the leader of a new group, any other
+    /// id is an existing group to join. Setting this value on windows will
+    /// cause the spawn to fail.
+    pub process_group: Option<libc::pid_t>,
+
+    /// Resource limits to apply to the child process with `setrlimit`, as
+    /// `(resource, soft limit, hard limit)`. A limit of `u64::MAX` stands for
+    /// no limit. The limits are applied before the user and group ids are
+    /// changed, and setting any on windows will cause the spawn to fail.
+    pub rlimits: &'a [(RlimitResource, u64, u64)],
+}
+
+/// A resource whose consumption by a child process can be limited.
+pub enum RlimitResource {
+    RlimitCpuTime,
+    RlimitFileSize,
+    RlimitDataSize,
+    RlimitStackSize,
+    RlimitCoreSize,
+    RlimitProcesses,
+    RlimitOpenFiles,
+    RlimitAddressSpace,
 }
 
 pub struct LocalIo<'a> {
@@ -198,6 +222,20 @@ pub trait IoFactory {
                  -> IoResult<Box<RtioUnixListener + Send>>;
     fn unix_connect(&mut self, path: &CString,
                     timeout: Option<u64>) -> IoResult<Box<RtioPipe + Send>>;
+    /// Creates a datagram socket bound to `path`, or an unbound one if `path`
+    /// is `None`.
+    fn unix_datagram_bind(&mut self, path: Option<&CString>)
+                          -> IoResult<Box<RtioUnixDatagram + Send>>;
+    // adopting sockets created elsewhere, which are closed when dropped
+    fn tcp_from_raw_fd(&mut self, fd: c_int)
+                       -> IoResult<Box<RtioTcpStream + Send>>;
+    fn tcp_listener_from_raw_fd(&mut self, fd: c_int)
+                                -> IoResult<Box<RtioTcpListener + Send>>;
+    fn udp_from_raw_fd(&mut self, fd: c_int)
+                       -> IoResult<Box<RtioUdpSocket + Send>>;
+    fn unix_from_raw_fd(&mut self, fd: c_int) -> IoResult<Box<RtioPipe + Send>>;
+    fn unix_listener_from_raw_fd(&mut self, fd: c_int)
+                                 -> IoResult<Box<RtioUnixListener + Send>>;
     fn get_host_addresses(&mut self, host: Option<&str>, servname: Option<&str>,
                           hint: Option<AddrinfoHint>)
                           -> IoResult<Vec<AddrinfoInfo>>;
@@ -228,13 +266,20 @@ pub trait IoFactory {
     fn timer_init(&mut self) -> IoResult<Box<RtioTimer + Send>>;
     fn spawn(&mut self, cfg: ProcessConfig)
             -> IoResult<(Box<RtioProcess + Send>,
-                         Vec<Option<Box<RtioPipe + Send>>>)>;
+                         Vec<Option<Box<RtioPipe + Send>>>,
+                         Option<Box<RtioPty + Send>>)>;
     fn kill(&mut self, pid: libc::pid_t, signal: int) -> IoResult<()>;
     fn pipe_open(&mut self, fd: c_int) -> IoResult<Box<RtioPipe + Send>>;
     fn tty_open(&mut self, fd: c_int, readable: bool)
             -> IoResult<Box<RtioTTY + Send>>;
+    fn pty_open(&mut self)
+            -> IoResult<(Box<RtioPty + Send>, Box<RtioPty + Send>)>;
     fn signal(&mut self, signal: int, cb: Box<Callback + Send>)
         -> IoResult<Box<RtioSignal + Send>>;
+    fn poller_init(&mut self) -> IoResult<Box<RtioPoller + Send>>;
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<FsEventCallback + Send>)
+        -> IoResult<Box<RtioFsWatcher + Send>>;
 }
 
 pub trait RtioTcpListener : RtioSocket {
@@ -251,6 +296,25 @@ pub trait RtioTcpAcceptor : RtioSocket {
 pub trait RtioTcpStream : RtioSocket {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+    /// Reads into each of `bufs` in turn with a single call where possible,
+    /// returning the total number of bytes read. By default this only reads
+    /// into the first non-empty buffer.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        readv_first(bufs, |buf| self.read(buf), 0)
+    }
+    /// Writes all of `bufs`, in order, with as few calls as possible. By
+    /// default each buffer is written separately.
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        writev_each(bufs, |buf| self.write(buf))
+    }
+    /// Sends up to `len` bytes of the file `fd`, starting at `offset`, without
+    /// copying them through userspace, returning how many were sent. Fewer
+    /// bytes are only sent at the end of the file. If this can't be done then
+    /// `None` is returned without sending anything, which is the default.
+    fn sendfile(&mut self, _fd: c_int, _offset: u64,
+                _len: u64) -> IoResult<Option<u64>> {
+        Ok(None)
+    }
     fn peer_name(&mut self) -> IoResult<SocketAddr>;
     fn control_congestion(&mut self) -> IoResult<()>;
     fn nodelay(&mut self) -> IoResult<()>;
@@ -266,6 +330,7 @@ pub trait RtioTcpStream : RtioSocket {
 
 pub trait RtioSocket {
     fn socket_name(&mut self) -> IoResult<SocketAddr>;
+    fn raw_fd(&self) -> c_int;
 }
 
 pub trait RtioUdpSocket : RtioSocket {
@@ -301,12 +366,26 @@ pub trait RtioFileStream {
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int>;
     fn pwrite(&mut self, buf: &[u8], offset: u64) -> IoResult<()>;
+    /// As with `RtioTcpStream::readv`, reporting the end of the file in the
+    /// same way as `read`.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        readv_first(bufs, |buf| self.read(buf), 0)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        writev_each(bufs, |buf| self.write(buf))
+    }
     fn seek(&mut self, pos: i64, whence: SeekStyle) -> IoResult<u64>;
     fn tell(&self) -> IoResult<u64>;
     fn fsync(&mut self) -> IoResult<()>;
     fn datasync(&mut self) -> IoResult<()>;
     fn truncate(&mut self, offset: i64) -> IoResult<()>;
     fn fstat(&mut self) -> IoResult<FileStat>;
+    /// Takes an advisory lock on the whole file, shared or exclusive. When
+    /// `wait` is false this returns `Ok(false)` instead of blocking if the
+    /// lock is held elsewhere.
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool>;
+    fn unlock(&mut self) -> IoResult<()>;
+    fn raw_fd(&self) -> c_int;
 }
 
 pub trait RtioProcess {
@@ -319,7 +398,38 @@ pub trait RtioProcess {
 pub trait RtioPipe {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+    /// See `RtioTcpStream::readv` and `RtioTcpStream::writev`.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        readv_first(bufs, |buf| self.read(buf), 0)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        writev_each(bufs, |buf| self.write(buf))
+    }
+    /// Writes all of `buf`, passing copies of the file descriptors `fds` to
+    /// the peer along with it. This is only possible over unix domain sockets,
+    /// and by default it's unsupported.
+    fn send_with_fds(&mut self, _buf: &[u8], _fds: &[c_int]) -> IoResult<()> {
+        Err(unsupported())
+    }
+    /// Reads into `buf`, storing any file descriptors passed along with the
+    /// data at the front of `fds` and returning the number of bytes and of
+    /// descriptors received. The descriptors are then owned by the caller.
+    /// By default this is unsupported.
+    fn recv_with_fds(&mut self, _buf: &mut [u8],
+                     _fds: &mut [c_int]) -> IoResult<(uint, uint)> {
+        Err(unsupported())
+    }
+    /// Returns the credentials of the process on the other end of a unix
+    /// domain socket, as of when the connection was made. By default this is
+    /// unsupported.
+    fn peer_credentials(&mut self) -> IoResult<PeerCredentials> {
+        Err(unsupported())
+    }
     fn clone(&self) -> Box<RtioPipe + Send>;
+    /// Pipes on windows may be named pipes, whose `HANDLE` can't be handed out
+    /// as a `c_int`, so this isn't available there.
+    #[cfg(unix)]
+    fn raw_fd(&self) -> c_int;
 
     fn close_write(&mut self) -> IoResult<()>;
     fn close_read(&mut self) -> IoResult<()>;
@@ -328,13 +438,39 @@ pub trait RtioPipe {
     fn set_write_timeout(&mut self, timeout_ms: Option<u64>);
 }
 
+/// The identity of a process connected over a unix domain socket. The process
+/// id isn't available on all platforms.
+pub struct PeerCredentials {
+    pub pid: Option<libc::pid_t>,
+    pub uid: u64,
+    pub gid: u64,
+}
+
+/// A unix domain socket in datagram mode. Sockets which sent a datagram
+/// without being bound to a path have no address, and are given as `None`.
+pub trait RtioUnixDatagram {
+    fn recv_from(&mut self, buf: &mut [u8])
+                 -> IoResult<(uint, Option<CString>)>;
+    fn send_to(&mut self, buf: &[u8], dst: &CString) -> IoResult<()>;
+
+    fn clone(&self) -> Box<RtioUnixDatagram + Send>;
+    fn raw_fd(&self) -> c_int;
+    fn set_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_read_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>);
+}
+
 pub trait RtioUnixListener {
     fn listen(~self) -> IoResult<Box<RtioUnixAcceptor + Send>>;
+    #[cfg(unix)]
+    fn raw_fd(&self) -> c_int;
 }
 
 pub trait RtioUnixAcceptor {
     fn accept(&mut self) -> IoResult<Box<RtioPipe + Send>>;
     fn set_timeout(&mut self, timeout: Option<u64>);
+    #[cfg(unix)]
+    fn raw_fd(&self) -> c_int;
 }
 
 pub trait RtioTTY {
@@ -345,6 +481,17 @@ pub trait RtioTTY {
     fn isatty(&self) -> bool;
 }
 
+/// One end of a pseudo-terminal. Window sizes are `(width, height)` pairs, in
+/// the same order as `RtioTTY::get_winsize`.
+pub trait RtioPty {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
+    fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+    fn clone(&self) -> Box<RtioPty + Send>;
+    fn raw_fd(&self) -> c_int;
+    fn set_winsize(&mut self, width: int, height: int) -> IoResult<()>;
+    fn get_winsize(&mut self) -> IoResult<(int, int)>;
+}
+
 pub trait PausableIdleCallback {
     fn pause(&mut self);
     fn resume(&mut self);
@@ -352,12 +499,83 @@ pub trait PausableIdleCallback {
 
 pub trait RtioSignal {}
 
+/// A watch on a file or directory tree, which delivers events to its callback
+/// until it is dropped.
+pub trait RtioFsWatcher {}
+
+pub trait FsEventCallback {
+    fn call(&mut self, event: FsEvent);
+}
+
+/// A change to a watched path.
+pub struct FsEvent {
+    pub path: CString,
+    pub kind: FsEventKind,
+}
+
+pub enum FsEventKind {
+    FsCreated,
+    FsModified,
+    FsRemoved,
+    FsRenamed,
+}
+
+/// Watches file descriptors for readiness. Each descriptor is registered with
+/// a token which is handed back in the events it produces.
+pub trait RtioPoller {
+    fn register(&mut self, fd: c_int, token: uint,
+                readable: bool, writable: bool) -> IoResult<()>;
+    fn reregister(&mut self, fd: c_int, token: uint,
+                  readable: bool, writable: bool) -> IoResult<()>;
+    fn deregister(&mut self, fd: c_int) -> IoResult<()>;
+    /// Blocks until at least one registered descriptor is ready or the
+    /// timeout expires, appending the ready descriptors to `events`. A
+    /// timeout of `None` blocks forever.
+    fn poll(&mut self, events: &mut Vec<PollEvent>,
+            timeout_ms: Option<u64>) -> IoResult<()>;
+}
+
+/// The readiness of a descriptor registered with an `RtioPoller`.
+pub struct PollEvent {
+    pub token: uint,
+    pub readable: bool,
+    pub writable: bool,
+    /// The peer hung up or an error is pending on the descriptor.
+    pub hangup: bool,
+}
+
 pub struct IoError {
     pub code: uint,
     pub extra: uint,
     pub detail: Option<String>,
 }
 
+// The default implementations of `readv` and `writev` shared by the streams
+// above, which read into the first non-empty buffer (returning `empty` if there
+// are none) and write out each buffer in turn.
+fn readv_first<T>(bufs: &mut [&mut [u8]], read: |&mut [u8]| -> IoResult<T>,
+                  empty: T) -> IoResult<T> {
+    match bufs.mut_iter().find(|buf| buf.len() > 0) {
+        Some(buf) => read(&mut **buf),
+        None => Ok(empty),
+    }
+}
+
+fn writev_each(bufs: &[&[u8]], write: |&[u8]| -> IoResult<()>) -> IoResult<()> {
+    for buf in bufs.iter() {
+        try!(write(*buf));
+    }
+    Ok(())
+}
+
+// The error returned by the default implementations of operations which a
+// runtime doesn't support.
+fn unsupported() -> IoError {
+    #[cfg(unix)] use ERROR = libc::ENOSYS;
+    #[cfg(windows)] use ERROR = libc::ERROR_CALL_NOT_IMPLEMENTED;
+    IoError { code: ERROR as uint, extra: 0, detail: None }
+}
+
 pub type IoResult<T> = Result<T, IoError>;
 
 #[deriving(PartialEq, Eq)]
@@ -389,6 +607,7 @@ pub enum StdioContainer {
     Ignored,
     InheritFd(i32),
     CreatePipe(bool, bool),
+    CreatePty,
 }
 
 pub enum ProcessExit {
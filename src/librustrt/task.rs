@@ -28,7 +28,7 @@ use local_data;
 use Runtime;
 use local::Local;
 use local_heap::LocalHeap;
-use rtio::LocalIo;
+use rtio::{LocalIo, IoResult};
 use unwind;
 use unwind::Unwinder;
 use collections::str::SendStr;
@@ -408,6 +408,25 @@ impl Task {
         ops.deschedule(amt, self, f)
     }
 
+    /// Deschedules the current task as `deschedule` does, but only for at most
+    /// `timeout_ms` milliseconds, after which the task is woken up if nothing
+    /// else has woken it yet.
+    ///
+    /// The blocked tasks given to `f` are always selectable handles, so once
+    /// this returns it's up to the caller to take back any it left behind, as
+    /// is done after selecting over many channels.
+    ///
+    /// An error is returned, without blocking, if the runtime is unable to
+    /// time out, as is the case for a green task whose scheduler has no event
+    /// loop to provide timers.
+    pub fn deschedule_timeout(mut ~self, amt: uint, timeout_ms: u64,
+                              f: |BlockedTask|
+                                  -> ::core::result::Result<(), BlockedTask>)
+                              -> IoResult<()> {
+        let ops = self.imp.take_unwrap();
+        ops.deschedule_timeout(amt, timeout_ms, self, f)
+    }
+
     /// Wakes up a previously blocked task, optionally specifying whether the
     /// current task can accept a change in scheduling. This function can only
     /// be called on tasks that were previously blocked in `deschedule`.
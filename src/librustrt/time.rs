@@ -0,0 +1,47 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A clock for measuring timeouts
+//!
+//! Primitives which block with a timeout, such as `Receiver::recv_timeout`,
+//! measure how long they have left to wait against this clock.
+
+use libc;
+
+/// Returns the current time in milliseconds. Only the difference between two
+/// values returned by this function is meaningful.
+#[cfg(unix)]
+pub fn precise_time_ms() -> u64 {
+    use core::mem;
+    use core::ptr;
+
+    extern {
+        fn gettimeofday(timeval: *mut libc::timeval,
+                        tzp: *mut libc::c_void) -> libc::c_int;
+    }
+
+    unsafe {
+        let mut now: libc::timeval = mem::zeroed();
+        rtassert!(gettimeofday(&mut now, ptr::mut_null()) == 0);
+        (now.tv_sec as u64) * 1000 + (now.tv_usec as u64) / 1000
+    }
+}
+
+/// Returns the current time in milliseconds. Only the difference between two
+/// values returned by this function is meaningful.
+#[cfg(windows)]
+pub fn precise_time_ms() -> u64 {
+    let mut ticks_per_s = 0;
+    rtassert!(unsafe { libc::QueryPerformanceFrequency(&mut ticks_per_s) } == 1);
+    let ticks_per_s = if ticks_per_s == 0 {1} else {ticks_per_s};
+    let mut ticks = 0;
+    rtassert!(unsafe { libc::QueryPerformanceCounter(&mut ticks) } == 1);
+    (ticks as u64 * 1000) / (ticks_per_s as u64)
+}
@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use libc::{c_int, c_char, c_void, ssize_t};
+use libc::{c_int, c_uint, c_char, c_void, ssize_t};
 use libc;
 use std::c_str::CString;
 use std::c_str;
@@ -20,6 +20,7 @@ use std::rt::task::BlockedTask;
 
 use homing::{HomingIO, HomeHandle};
 use super::{Loop, UvError, uv_error_to_io_error, wait_until_woken_after, wakeup};
+use super::slice_to_uv_buf;
 use uvio::UvIoFactory;
 use uvll;
 
@@ -80,28 +81,51 @@ impl FsRequest {
 
     pub fn write(loop_: &Loop, fd: c_int, buf: &[u8], offset: i64)
         -> Result<(), UvError>
+    {
+        FsRequest::writev(loop_, fd, [buf], offset)
+    }
+
+    pub fn writev(loop_: &Loop, fd: c_int, bufs: &[&[u8]], offset: i64)
+        -> Result<(), UvError>
     {
         // In libuv, uv_fs_write is basically just shelling out to a write()
         // syscall at some point, with very little fluff around it. This means
         // that write() could actually be a short write, so we need to be sure
         // to call it continuously if we get a short write back. This method is
         // expected to write the full data if it returns success.
+        let mut uvbufs: Vec<uvll::uv_buf_t> = bufs.iter().filter(|buf| {
+            buf.len() > 0
+        }).map(|buf| slice_to_uv_buf(*buf)).collect();
+        let mut start = 0;
         let mut written = 0;
-        while written < buf.len() {
+        while start < uvbufs.len() {
             let offset = if offset == -1 {
                 offset
             } else {
                 offset + written as i64
             };
-            let uvbuf = uvll::uv_buf_t {
-                base: buf.slice_from(written as uint).as_ptr() as *mut _,
-                len: (buf.len() - written) as uvll::uv_buf_len_t,
+            let n = {
+                let pending = uvbufs.slice_from(start);
+                try!(execute(|req, cb| unsafe {
+                    uvll::uv_fs_write(loop_.handle, req, fd, pending.as_ptr(),
+                                      pending.len() as c_uint, offset, cb)
+                }).map(|req| req.get_result() as uint))
             };
-            match execute(|req, cb| unsafe {
-                uvll::uv_fs_write(loop_.handle, req, fd, &uvbuf, 1, offset, cb)
-            }).map(|req| req.get_result()) {
-                Err(e) => return Err(e),
-                Ok(n) => { written += n as uint; }
+            written += n;
+
+            // Skip the buffers which were written entirely, and the written
+            // part of the one which wasn't.
+            let mut n = n;
+            while n > 0 {
+                let uvbuf = uvbufs.get_mut(start);
+                let len = uvbuf.len as uint;
+                if n < len {
+                    uvbuf.base = unsafe { uvbuf.base.offset(n as int) };
+                    uvbuf.len = (len - n) as uvll::uv_buf_len_t;
+                    break
+                }
+                n -= len;
+                start += 1;
             }
         }
         Ok(())
@@ -110,12 +134,21 @@ impl FsRequest {
     pub fn read(loop_: &Loop, fd: c_int, buf: &mut [u8], offset: i64)
         -> Result<int, UvError>
     {
-        execute(|req, cb| unsafe {
-            let mut uvbuf = uvll::uv_buf_t {
-                base: buf.as_mut_ptr(),
+        FsRequest::readv(loop_, fd, &mut [buf], offset)
+    }
+
+    pub fn readv(loop_: &Loop, fd: c_int, bufs: &mut [&mut [u8]], offset: i64)
+        -> Result<int, UvError>
+    {
+        let mut uvbufs: Vec<uvll::uv_buf_t> = bufs.mut_iter().map(|buf| {
+            uvll::uv_buf_t {
+                base: buf.as_ptr() as *mut u8,
                 len: buf.len() as uvll::uv_buf_len_t,
-            };
-            uvll::uv_fs_read(loop_.handle, req, fd, &mut uvbuf, 1, offset, cb)
+            }
+        }).collect();
+        execute(|req, cb| unsafe {
+            uvll::uv_fs_read(loop_.handle, req, fd, uvbufs.as_mut_ptr(),
+                             uvbufs.len() as c_uint, offset, cb)
         }).map(|req| {
             req.get_result() as int
         })
@@ -366,6 +399,17 @@ impl FileWatcher {
         let r = FsRequest::read(&self.loop_, self.fd, buf, offset);
         r.map_err(uv_error_to_io_error)
     }
+    fn base_readv(&mut self, bufs: &mut [&mut [u8]],
+                  offset: i64) -> IoResult<int> {
+        let _m = self.fire_homing_missile();
+        let r = FsRequest::readv(&self.loop_, self.fd, bufs, offset);
+        r.map_err(uv_error_to_io_error)
+    }
+    fn base_writev(&mut self, bufs: &[&[u8]], offset: i64) -> IoResult<()> {
+        let _m = self.fire_homing_missile();
+        let r = FsRequest::writev(&self.loop_, self.fd, bufs, offset);
+        r.map_err(uv_error_to_io_error)
+    }
     fn base_write(&mut self, buf: &[u8], offset: i64) -> IoResult<()> {
         let _m = self.fire_homing_missile();
         let r = FsRequest::write(&self.loop_, self.fd, buf, offset);
@@ -383,6 +427,125 @@ impl FileWatcher {
             n => Ok(n as u64)
         }
     }
+
+    // libuv has no support for file locks. Taking a lock without waiting
+    // never blocks so it's done directly, but a lock which may have to wait is
+    // taken on the loop's threadpool to keep the event loop running in the
+    // meantime.
+    fn lock_common(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        struct Ctx {
+            slot: Option<BlockedTask>,
+            fd: c_int,
+            exclusive: bool,
+            result: Result<bool, uint>,
+        }
+
+        if !wait {
+            return lock(self.fd, exclusive, false).map_err(lock_error)
+        }
+
+        let _m = self.fire_homing_missile();
+        let mut cx = Ctx {
+            slot: None,
+            fd: self.fd,
+            exclusive: exclusive,
+            result: Ok(false),
+        };
+        // The work may be picked up before we block, so the context has to be
+        // in place before it's queued.
+        let mut req = Request::new(uvll::UV_WORK);
+        req.set_data(&mut cx);
+
+        return match unsafe {
+            uvll::uv_queue_work(self.loop_.handle, req.handle, lock_cb,
+                                after_lock_cb)
+        } {
+            0 => {
+                req.defuse(); // uv callback now owns this request
+                wait_until_woken_after(&mut cx.slot, &self.loop_, || {});
+                cx.result.map_err(lock_error)
+            }
+            n => Err(uv_error_to_io_error(UvError(n))),
+        };
+
+        // Run on one of libuv's threads, where there's no task to speak of, so
+        // this does nothing but make the call.
+        extern fn lock_cb(req: *mut uvll::uv_work_t) {
+            let cx: &mut Ctx = unsafe {
+                mem::transmute(uvll::get_data_for_req(req))
+            };
+            cx.result = lock(cx.fd, cx.exclusive, true);
+        }
+
+        extern fn after_lock_cb(req: *mut uvll::uv_work_t, status: c_int) {
+            let req = Request::wrap(req);
+            assert!(status != uvll::ECANCELED);
+            let cx: &mut Ctx = unsafe { req.get_data() };
+            wakeup(&mut cx.slot);
+        }
+    }
+}
+
+fn lock_error(errno: uint) -> IoError {
+    IoError { code: errno, extra: 0, detail: None }
+}
+
+// Takes a lock on the whole file, returning the error code if it fails. When
+// `wait` is false this returns `Ok(false)` instead of blocking if the lock is
+// held elsewhere.
+#[cfg(unix)]
+fn lock(fd: c_int, exclusive: bool, wait: bool) -> Result<bool, uint> {
+    let mut op = if exclusive {libc::LOCK_EX} else {libc::LOCK_SH};
+    if !wait { op |= libc::LOCK_NB }
+    loop {
+        match unsafe { libc::flock(fd, op) } {
+            0 => return Ok(true),
+            _ => match os::errno() {
+                n if n == libc::EINTR as int => {}
+                n if !wait && n == libc::EWOULDBLOCK as int => return Ok(false),
+                n => return Err(n as uint),
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn lock(fd: c_int, exclusive: bool, wait: bool) -> Result<bool, uint> {
+    let mut flags = 0;
+    if exclusive { flags |= libc::LOCKFILE_EXCLUSIVE_LOCK }
+    if !wait { flags |= libc::LOCKFILE_FAIL_IMMEDIATELY }
+    let mut overlap: libc::OVERLAPPED = unsafe { mem::zeroed() };
+    let ret = unsafe {
+        let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+        libc::LockFileEx(handle, flags, 0, !0, !0, &mut overlap)
+    };
+    if ret != 0 {
+        Ok(true)
+    } else if !wait && os::errno() == libc::ERROR_LOCK_VIOLATION as int {
+        Ok(false)
+    } else {
+        Err(os::errno() as uint)
+    }
+}
+
+#[cfg(unix)]
+fn unlock(fd: c_int) -> IoResult<()> {
+    match unsafe { libc::flock(fd, libc::LOCK_UN) } {
+        0 => Ok(()),
+        _ => Err(lock_error(os::errno() as uint)),
+    }
+}
+
+#[cfg(windows)]
+fn unlock(fd: c_int) -> IoResult<()> {
+    let mut overlap: libc::OVERLAPPED = unsafe { mem::zeroed() };
+    match unsafe {
+        let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+        libc::UnlockFileEx(handle, 0, !0, !0, &mut overlap)
+    } {
+        0 => Err(lock_error(os::errno() as uint)),
+        _ => Ok(()),
+    }
 }
 
 impl Drop for FileWatcher {
@@ -426,6 +589,12 @@ impl rtio::RtioFileStream for FileWatcher {
     fn pwrite(&mut self, buf: &[u8], offset: u64) -> IoResult<()> {
         self.base_write(buf, offset as i64)
     }
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        self.base_readv(bufs, -1)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.base_writev(bufs, -1)
+    }
     fn seek(&mut self, pos: i64, whence: rtio::SeekStyle) -> IoResult<u64> {
         use libc::{SEEK_SET, SEEK_CUR, SEEK_END};
         let whence = match whence {
@@ -458,6 +627,14 @@ impl rtio::RtioFileStream for FileWatcher {
         let _m = self.fire_homing_missile();
         FsRequest::fstat(&self.loop_, self.fd).map_err(uv_error_to_io_error)
     }
+
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        self.lock_common(exclusive, wait)
+    }
+    fn unlock(&mut self) -> IoResult<()> {
+        unlock(self.fd)
+    }
+    fn raw_fd(&self) -> c_int { self.fd }
 }
 
 #[cfg(test)]
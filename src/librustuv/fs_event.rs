@@ -0,0 +1,202 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use libc::{c_int, c_char};
+use libc;
+use std::c_str::CString;
+use std::mem;
+use std::rt::rtio;
+
+use homing::{HomingIO, HomeHandle};
+use super::{Loop, UvError, UvHandle};
+use file::FsRequest;
+use uvio::UvIoFactory;
+use uvll;
+
+// libuv only watches whole directory trees by itself on OSX and windows,
+// elsewhere every directory in the tree gets a handle of its own.
+#[cfg(target_os = "macos")]
+#[cfg(windows)]
+static NATIVE_RECURSION: bool = true;
+#[cfg(not(target_os = "macos"), not(windows))]
+static NATIVE_RECURSION: bool = false;
+
+pub struct FsEventWatcher {
+    home: HomeHandle,
+    loop_: Loop,
+    recursive: bool,
+    watches: Vec<Box<Watch>>,
+    cb: Box<rtio::FsEventCallback + Send>,
+}
+
+// A uv fs event handle watching one path.
+struct Watch {
+    handle: *mut uvll::uv_fs_event_t,
+    path: Path,
+    dir: bool,
+    watcher: *mut FsEventWatcher,
+}
+
+impl FsEventWatcher {
+    pub fn new(io: &mut UvIoFactory, path: &CString, recursive: bool,
+               cb: Box<rtio::FsEventCallback + Send>)
+               -> Result<Box<FsEventWatcher>, UvError> {
+        let mut watcher = box FsEventWatcher {
+            home: io.make_handle(),
+            loop_: Loop::wrap(io.uv_loop()),
+            recursive: recursive,
+            watches: Vec::new(),
+            cb: cb,
+        };
+        let path = Path::new(path.as_bytes_no_nul());
+        let stat = try!(FsRequest::stat(&watcher.loop_, &path.to_c_str()));
+        let dir = is_dir(stat.kind);
+        try!(watcher.start(path.clone(), dir));
+        if recursive && dir && !NATIVE_RECURSION {
+            watcher.start_children(&path);
+        }
+        Ok(watcher)
+    }
+
+    fn start(&mut self, path: Path, dir: bool) -> Result<(), UvError> {
+        let handle = UvHandle::alloc(None::<Watch>, uvll::UV_FS_EVENT);
+        assert_eq!(unsafe {
+            uvll::uv_fs_event_init(self.loop_.handle, handle)
+        }, 0);
+        let mut watch = box Watch {
+            handle: handle,
+            path: path,
+            dir: dir,
+            watcher: self as *mut FsEventWatcher,
+        }.install();
+
+        let flags = if self.recursive && NATIVE_RECURSION {
+            uvll::UV_FS_EVENT_RECURSIVE
+        } else {
+            0
+        };
+        match watch.path.with_c_str(|p| unsafe {
+            uvll::uv_fs_event_start(handle, fs_event_cb, p, flags)
+        }) {
+            0 => {
+                self.watches.push(watch);
+                Ok(())
+            }
+            n => {
+                // This may run inside of a callback, so the handle can't be
+                // waited on to close.
+                watch.close_async_();
+                Err(UvError(n))
+            }
+        }
+    }
+
+    // Watches every directory below `dir`. This runs on the watcher's task,
+    // so the directory can be read with a blocking request. Errors are
+    // ignored, as entries may disappear while they're being walked.
+    fn start_children(&mut self, dir: &Path) {
+        let children = match FsRequest::readdir(&self.loop_, &dir.to_c_str(), 0) {
+            Ok(children) => children,
+            Err(..) => return,
+        };
+        for child in children.move_iter() {
+            match FsRequest::lstat(&self.loop_, &child) {
+                Ok(ref stat) if is_dir(stat.kind) => {}
+                _ => continue,
+            }
+            let child = Path::new(child.as_bytes_no_nul());
+            if self.start(child.clone(), true).is_ok() {
+                self.start_children(&child);
+            }
+        }
+    }
+
+    fn notify(&mut self, path: &Path, kind: rtio::FsEventKind) {
+        self.cb.call(rtio::FsEvent { path: path.to_c_str(), kind: kind });
+    }
+}
+
+fn is_dir(kind: u64) -> bool {
+    kind & libc::S_IFMT as u64 == libc::S_IFDIR as u64
+}
+
+// Callbacks can't block, so they check paths with a plain stat() instead of
+// a uv request. Returns whether `path` exists and is a directory.
+fn stat_now(path: &Path) -> Option<bool> {
+    let mut stat: libc::stat = unsafe { mem::zeroed() };
+    match path.with_c_str(|p| unsafe { libc::stat(p, &mut stat) }) {
+        0 => Some(is_dir(stat.st_mode as u64)),
+        _ => None,
+    }
+}
+
+extern fn fs_event_cb(handle: *mut uvll::uv_fs_event_t, filename: *const c_char,
+                      events: c_int, status: c_int) {
+    if status < 0 { return }
+    let watch: &mut Watch = unsafe { UvHandle::from_uv_handle(&handle) };
+    let watcher: &mut FsEventWatcher = unsafe { &mut *watch.watcher };
+
+    // Events about a watched file come with the file's own name.
+    let path = if filename.is_null() || !watch.dir {
+        watch.path.clone()
+    } else {
+        watch.path.join(unsafe { CString::new(filename, false) })
+    };
+
+    if events & uvll::UV_CHANGE != 0 {
+        watcher.notify(&path, rtio::FsModified);
+    }
+    if events & uvll::UV_RENAME == 0 { return }
+
+    // libuv reports creations, removals and both sides of a rename alike, so
+    // they're told apart by whether the path is still there.
+    match stat_now(&path) {
+        Some(dir) => {
+            let emulated = watcher.recursive && !NATIVE_RECURSION;
+            if emulated && dir && !watcher.watches.iter().any(|w| w.path == path) {
+                let _ = watcher.start(path.clone(), true);
+            }
+            watcher.notify(&path, rtio::FsCreated);
+        }
+        None => {
+            // `watch` may be one of the handles closed here, so it can't be
+            // used past this point.
+            let mut i = 0;
+            while i < watcher.watches.len() {
+                let gone = path.is_ancestor_of(&watcher.watches.get(i).path);
+                if gone {
+                    watcher.watches.remove(i).unwrap().close_async_();
+                } else {
+                    i += 1;
+                }
+            }
+            watcher.notify(&path, rtio::FsRemoved);
+        }
+    }
+}
+
+impl HomingIO for FsEventWatcher {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl UvHandle<uvll::uv_fs_event_t> for Watch {
+    fn uv_handle(&self) -> *mut uvll::uv_fs_event_t { self.handle }
+}
+
+impl rtio::RtioFsWatcher for FsEventWatcher {}
+
+impl Drop for FsEventWatcher {
+    fn drop(&mut self) {
+        let _m = self.fire_homing_missile();
+        for watch in self.watches.mut_iter() {
+            watch.close();
+        }
+    }
+}
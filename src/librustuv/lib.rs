@@ -69,10 +69,11 @@ use std::task;
 
 pub use self::async::AsyncWatcher;
 pub use self::file::{FsRequest, FileWatcher};
+pub use self::fs_event::FsEventWatcher;
 pub use self::idle::IdleWatcher;
 pub use self::net::{TcpWatcher, TcpListener, TcpAcceptor, UdpWatcher};
 pub use self::pipe::{PipeWatcher, PipeListener, PipeAcceptor};
-pub use self::process::Process;
+pub use self::process::{Process, PtyWatcher};
 pub use self::signal::SignalWatcher;
 pub use self::timer::TimerWatcher;
 pub use self::tty::TtyWatcher;
@@ -99,6 +100,7 @@ pub mod uvio;
 pub mod uvll;
 
 pub mod file;
+pub mod fs_event;
 pub mod net;
 pub mod idle;
 pub mod timer;
@@ -108,6 +110,7 @@ pub mod process;
 pub mod pipe;
 pub mod tty;
 pub mod signal;
+pub mod poll;
 pub mod stream;
 
 /// Creates a new event loop which is powered by libuv
@@ -143,6 +146,17 @@ pub trait UvHandle<T> {
         Loop::wrap(unsafe { uvll::get_loop_for_uv_handle(self.uv_handle()) })
     }
 
+    /// The file descriptor of the handle, or -1 if it doesn't have one.
+    fn fileno(&self) -> c_int {
+        let mut fd: uvll::uv_os_fd_t = unsafe { mem::zeroed() };
+        match unsafe {
+            uvll::uv_fileno(self.uv_handle() as *const uvll::uv_handle_t, &mut fd)
+        } {
+            0 => fd as c_int,
+            _ => -1,
+        }
+    }
+
     // FIXME(#8888) dummy self
     fn alloc(_: Option<Self>, ty: uvll::uv_handle_type) -> *mut T {
         unsafe {
@@ -422,6 +436,7 @@ pub fn uv_error_to_io_error(uverr: UvError) -> IoError {
             uvll::EADDRNOTAVAIL => libc::WSAEADDRNOTAVAIL,
             uvll::ECANCELED => libc::ERROR_OPERATION_ABORTED,
             uvll::EADDRINUSE => libc::WSAEADDRINUSE,
+            uvll::ENOSYS => libc::ERROR_CALL_NOT_IMPLEMENTED,
             err => {
                 uvdebug!("uverr.code {}", err as int);
                 // FIXME: Need to map remaining uv error types
@@ -220,6 +220,7 @@ impl rtio::RtioSocket for TcpWatcher {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.handle)
     }
+    fn raw_fd(&self) -> c_int { self.fileno() }
 }
 
 impl rtio::RtioTcpStream for TcpWatcher {
@@ -241,6 +242,12 @@ impl rtio::RtioTcpStream for TcpWatcher {
         self.stream.write(buf, guard.can_timeout).map_err(uv_error_to_io_error)
     }
 
+    fn writev(&mut self, bufs: &[&[u8]]) -> Result<(), IoError> {
+        let m = self.fire_homing_missile();
+        let guard = try!(self.write_access.grant(m));
+        self.stream.writev(bufs, guard.can_timeout).map_err(uv_error_to_io_error)
+    }
+
     fn peer_name(&mut self) -> Result<rtio::SocketAddr, IoError> {
         let _m = self.fire_homing_missile();
         socket_name(TcpPeer, self.handle)
@@ -386,6 +393,7 @@ impl rtio::RtioSocket for TcpListener {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.handle)
     }
+    fn raw_fd(&self) -> c_int { self.fileno() }
 }
 
 impl rtio::RtioTcpListener for TcpListener {
@@ -440,6 +448,7 @@ impl rtio::RtioSocket for TcpAcceptor {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.listener.handle)
     }
+    fn raw_fd(&self) -> c_int { self.listener.fileno() }
 }
 
 impl rtio::RtioTcpAcceptor for TcpAcceptor {
@@ -537,6 +546,7 @@ impl rtio::RtioSocket for UdpWatcher {
         let _m = self.fire_homing_missile();
         socket_name(Udp, self.handle)
     }
+    fn raw_fd(&self) -> c_int { self.fileno() }
 }
 
 impl rtio::RtioUdpSocket for UdpWatcher {
@@ -129,6 +129,12 @@ impl rtio::RtioPipe for PipeWatcher {
         self.stream.write(buf, guard.can_timeout).map_err(uv_error_to_io_error)
     }
 
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let m = self.fire_homing_missile();
+        let guard = try!(self.write_access.grant(m));
+        self.stream.writev(bufs, guard.can_timeout).map_err(uv_error_to_io_error)
+    }
+
     fn clone(&self) -> Box<rtio::RtioPipe + Send> {
         box PipeWatcher {
             stream: StreamWatcher::new(self.stream.handle, false),
@@ -140,6 +146,9 @@ impl rtio::RtioPipe for PipeWatcher {
         } as Box<rtio::RtioPipe + Send>
     }
 
+    #[cfg(unix)]
+    fn raw_fd(&self) -> libc::c_int { self.fileno() }
+
     fn close_read(&mut self) -> IoResult<()> {
         // The current uv_shutdown method only shuts the writing half of the
         // connection, and no method is provided to shut down the reading half
@@ -229,22 +238,34 @@ impl PipeListener {
         match unsafe {
             uvll::uv_pipe_bind(pipe.handle(), name.as_ptr())
         } {
-            0 => {
-                // If successful, unwrap the PipeWatcher because we control how
-                // we close the pipe differently. We can't rely on
-                // StreamWatcher's default close method.
-                let (tx, rx) = channel();
-                let p = box PipeListener {
-                    home: io.make_handle(),
-                    pipe: pipe.unwrap(),
-                    incoming: rx,
-                    outgoing: tx,
-                };
-                Ok(p.install())
-            }
+            0 => Ok(PipeListener::wrap(io, pipe)),
             n => Err(UvError(n))
         }
     }
+
+    // Adopts a socket which is already bound, and possibly listening.
+    pub fn open(io: &mut UvIoFactory, file: libc::c_int)
+        -> Result<Box<PipeListener>, UvError>
+    {
+        let pipe = PipeWatcher::new(io, false);
+        match unsafe { uvll::uv_pipe_open(pipe.handle(), file) } {
+            0 => Ok(PipeListener::wrap(io, pipe)),
+            n => Err(UvError(n))
+        }
+    }
+
+    fn wrap(io: &mut UvIoFactory, pipe: PipeWatcher) -> Box<PipeListener> {
+        // Unwrap the PipeWatcher because we control how we close the pipe
+        // differently. We can't rely on StreamWatcher's default close method.
+        let (tx, rx) = channel();
+        let p = box PipeListener {
+            home: io.make_handle(),
+            pipe: pipe.unwrap(),
+            incoming: rx,
+            outgoing: tx,
+        };
+        p.install()
+    }
 }
 
 impl rtio::RtioUnixListener for PipeListener {
@@ -262,6 +283,9 @@ impl rtio::RtioUnixListener for PipeListener {
             n => Err(uv_error_to_io_error(UvError(n))),
         }
     }
+
+    #[cfg(unix)]
+    fn raw_fd(&self) -> libc::c_int { self.fileno() }
 }
 
 impl HomingIO for PipeListener {
@@ -310,6 +334,9 @@ impl rtio::RtioUnixAcceptor for PipeAcceptor {
             Some(ms) => self.timeout.set_timeout(ms, &mut *self.listener),
         }
     }
+
+    #[cfg(unix)]
+    fn raw_fd(&self) -> libc::c_int { self.listener.fileno() }
 }
 
 impl HomingIO for PipeAcceptor {
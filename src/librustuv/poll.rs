@@ -0,0 +1,266 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use libc::c_int;
+use libc;
+use std::collections::HashMap;
+use std::mem;
+use std::rt::rtio;
+use std::rt::rtio::IoResult;
+use std::rt::task::BlockedTask;
+
+use homing::{HomingIO, HomeHandle};
+use super::{UvError, UvHandle, uv_error_to_io_error, wait_until_woken_after,
+            wakeup};
+use timer::TimerWatcher;
+use uvio::UvIoFactory;
+use uvll;
+
+pub struct Poller {
+    home: HomeHandle,
+    timer: TimerWatcher,
+    watches: HashMap<c_int, Box<Watch>>,
+    // The descriptors with events which haven't been handed out by `poll` yet.
+    ready: Vec<c_int>,
+    blocker: Option<BlockedTask>,
+}
+
+// A uv poll handle watching one registered descriptor.
+struct Watch {
+    handle: *mut uvll::uv_poll_t,
+    // The descriptor the watch was registered with, and the duplicate of it
+    // which the poll handle actually watches, see `dup`.
+    fd: c_int,
+    polled: c_int,
+    token: uint,
+    interest: c_int,
+    // The events seen since they were last handed out. libuv calls back on
+    // every turn of the loop while a descriptor stays ready, so the handle is
+    // stopped while there are any and restarted once they're handed out.
+    events: c_int,
+    hangup: bool,
+    poller: *mut Poller,
+}
+
+impl Poller {
+    pub fn new(io: &mut UvIoFactory) -> Box<Poller> {
+        let timer = TimerWatcher::new_home(&io.loop_, io.make_handle());
+        let mut poller = box Poller {
+            home: io.make_handle(),
+            timer: timer,
+            watches: HashMap::new(),
+            ready: Vec::new(),
+            blocker: None,
+        };
+        let ptr: *mut Poller = &mut *poller;
+        unsafe { poller.timer.set_data(ptr) }
+        poller
+    }
+}
+
+impl Watch {
+    fn start(&mut self) -> Result<(), UvError> {
+        match unsafe { uvll::uv_poll_start(self.handle, self.interest, poll_cb) } {
+            0 => Ok(()),
+            n => Err(UvError(n)),
+        }
+    }
+
+    fn stop(&mut self) {
+        assert_eq!(unsafe { uvll::uv_poll_stop(self.handle) }, 0)
+    }
+}
+
+// Every descriptor which can be registered is already owned by a uv tcp, udp
+// or pipe handle, and libuv doesn't allow a poll handle to watch a descriptor
+// which another handle watches too (the two would fight over the loop's
+// interest in it). The poll handle watches a duplicate of the descriptor
+// instead, which the loop tracks separately but which shares the readiness of
+// the original.
+#[cfg(unix)]
+fn dup(fd: c_int) -> Result<c_int, UvError> {
+    use std::os;
+    match unsafe { libc::dup(fd) } {
+        -1 => Err(UvError(-(os::errno() as c_int))),
+        fd => Ok(fd),
+    }
+}
+
+// The descriptors handed out on windows are sockets which belong to the
+// loop's completion port, and they can't be duplicated as descriptors, so
+// they can't be registered.
+#[cfg(windows)]
+fn dup(_fd: c_int) -> Result<c_int, UvError> {
+    Err(UvError(uvll::ENOSYS))
+}
+
+fn uv_interest(readable: bool, writable: bool) -> c_int {
+    let mut interest = 0;
+    if readable { interest |= uvll::UV_READABLE }
+    if writable { interest |= uvll::UV_WRITABLE }
+    interest
+}
+
+impl HomingIO for Poller {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl UvHandle<uvll::uv_poll_t> for Watch {
+    fn uv_handle(&self) -> *mut uvll::uv_poll_t { self.handle }
+}
+
+impl rtio::RtioPoller for Poller {
+    fn register(&mut self, fd: c_int, token: uint,
+                readable: bool, writable: bool) -> IoResult<()> {
+        let _m = self.fire_homing_missile();
+        if self.watches.contains_key(&fd) {
+            return Err(uv_error_to_io_error(UvError(uvll::EEXIST)))
+        }
+
+        let polled = try!(dup(fd).map_err(uv_error_to_io_error));
+        let handle = UvHandle::alloc(None::<Watch>, uvll::UV_POLL);
+        match unsafe { uvll::uv_poll_init(self.timer.uv_loop().handle, handle, polled) } {
+            0 => {}
+            n => {
+                unsafe {
+                    uvll::free_handle(handle);
+                    libc::close(polled);
+                }
+                return Err(uv_error_to_io_error(UvError(n)))
+            }
+        }
+        let mut watch = box Watch {
+            handle: handle,
+            fd: fd,
+            polled: polled,
+            token: token,
+            interest: uv_interest(readable, writable),
+            events: 0,
+            hangup: false,
+            poller: self as *mut Poller,
+        }.install();
+        match watch.start() {
+            Ok(()) => {
+                self.watches.insert(fd, watch);
+                Ok(())
+            }
+            Err(e) => Err(uv_error_to_io_error(e)),
+        }
+    }
+
+    fn reregister(&mut self, fd: c_int, token: uint,
+                  readable: bool, writable: bool) -> IoResult<()> {
+        let _m = self.fire_homing_missile();
+        let watch = match self.watches.find_mut(&fd) {
+            Some(watch) => watch,
+            None => return Err(uv_error_to_io_error(UvError(uvll::ENOENT))),
+        };
+        watch.token = token;
+        watch.interest = uv_interest(readable, writable);
+        watch.events &= watch.interest;
+        if watch.events == 0 && !watch.hangup {
+            watch.start().map_err(uv_error_to_io_error)
+        } else {
+            // Still stopped until the pending events are handed out.
+            Ok(())
+        }
+    }
+
+    fn deregister(&mut self, fd: c_int) -> IoResult<()> {
+        let _m = self.fire_homing_missile();
+        match self.watches.pop(&fd) {
+            Some(_) => {
+                self.ready.retain(|&ready| ready != fd);
+                Ok(())
+            }
+            None => Err(uv_error_to_io_error(UvError(uvll::ENOENT))),
+        }
+    }
+
+    fn poll(&mut self, events: &mut Vec<rtio::PollEvent>,
+            timeout_ms: Option<u64>) -> IoResult<()> {
+        let _m = self.fire_homing_missile();
+
+        if self.ready.len() == 0 && timeout_ms != Some(0) {
+            let loop_ = self.timer.uv_loop();
+            wait_until_woken_after(&mut self.blocker, &loop_, || {
+                match timeout_ms {
+                    Some(ms) => self.timer.start(timer_cb, ms, 0),
+                    None => {}
+                }
+            });
+            self.timer.stop();
+        }
+
+        for fd in mem::replace(&mut self.ready, Vec::new()).move_iter() {
+            let watch = match self.watches.find_mut(&fd) {
+                Some(watch) => watch,
+                None => continue,
+            };
+            if watch.events == 0 && !watch.hangup {
+                // The events were dropped by `reregister`.
+                continue
+            }
+            events.push(rtio::PollEvent {
+                token: watch.token,
+                readable: watch.events & uvll::UV_READABLE != 0,
+                writable: watch.events & uvll::UV_WRITABLE != 0,
+                hangup: watch.hangup,
+            });
+            watch.events = 0;
+            watch.hangup = false;
+            try!(watch.start().map_err(uv_error_to_io_error));
+        }
+        Ok(())
+    }
+}
+
+extern fn poll_cb(handle: *mut uvll::uv_poll_t, status: c_int, events: c_int) {
+    let watch: &mut Watch = unsafe { UvHandle::from_uv_handle(&handle) };
+    let poller: &mut Poller = unsafe { &mut *watch.poller };
+
+    watch.stop();
+    if watch.events == 0 && !watch.hangup {
+        poller.ready.push(watch.fd);
+    }
+    if status < 0 {
+        watch.hangup = true;
+    } else {
+        watch.events |= events;
+    }
+
+    if poller.blocker.is_some() {
+        wakeup(&mut poller.blocker);
+    }
+}
+
+extern fn timer_cb(handle: *mut uvll::uv_timer_t) {
+    let poller: &mut Poller = unsafe {
+        &mut *(uvll::get_data_for_uv_handle(handle) as *mut Poller)
+    };
+    if poller.blocker.is_some() {
+        wakeup(&mut poller.blocker);
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        let _m = self.fire_homing_missile();
+        self.watches.clear();
+    }
+}
+
+// Only dropped by the poller, which is already at home.
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.close();
+        unsafe { libc::close(self.polled); }
+    }
+}
@@ -12,9 +12,11 @@ use libc::c_int;
 use libc;
 use std::ptr;
 use std::c_str::CString;
+use std::rt::pty;
 use std::rt::rtio;
-use std::rt::rtio::IoResult;
+use std::rt::rtio::{IoResult, RtioPipe, RtioProcess};
 use std::rt::task::BlockedTask;
+#[cfg(unix)] use std::os;
 
 use homing::{HomingIO, HomeHandle};
 use pipe::PipeWatcher;
@@ -50,24 +52,42 @@ impl Process {
     ///
     /// Returns either the corresponding process object or an error which
     /// occurred.
+    ///
+    /// All of the stdio streams configured as `CreatePty` share a single
+    /// pseudo-terminal, the master end of which is returned alongside the
+    /// pipes. libuv can't make the terminal the controlling terminal of the
+    /// child, but the child does get a session of its own.
     pub fn spawn(io_loop: &mut UvIoFactory, cfg: rtio::ProcessConfig)
-                -> Result<(Box<Process>, Vec<Option<PipeWatcher>>), UvError> {
+                -> Result<(Box<Process>, Vec<Option<PipeWatcher>>,
+                           Option<PtyWatcher>), UvError> {
+        // libuv doesn't run any code of ours in the child between fork and
+        // exec, which is where these would have to be set up.
+        if cfg.process_group.is_some() || cfg.rlimits.len() > 0 {
+            return Err(UvError(uvll::ENOSYS))
+        }
+
         let mut io = vec![cfg.stdin, cfg.stdout, cfg.stderr];
         for slot in cfg.extra_io.iter() {
             io.push(*slot);
         }
+        let wants_pty = io.iter().any(|io| {
+            match *io { rtio::CreatePty => true, _ => false }
+        });
+        let pty = if wants_pty { Some(try!(open_pty())) } else { None };
+        let slave = pty.map(|(_, slave)| slave);
+
         let mut stdio = Vec::<uvll::uv_stdio_container_t>::with_capacity(io.len());
         let mut ret_io = Vec::with_capacity(io.len());
         unsafe {
             stdio.set_len(io.len());
             for (slot, other) in stdio.mut_iter().zip(io.iter()) {
                 let io = set_stdio(slot as *mut uvll::uv_stdio_container_t, other,
-                                   io_loop);
+                                   io_loop, slave);
                 ret_io.push(io);
             }
         }
 
-        let ret = with_argv(cfg.program, cfg.args, |argv| {
+        let mut ret = with_argv(cfg.program, cfg.args, |argv| {
             with_env(cfg.env, |envp| {
                 let mut flags = 0;
                 if cfg.uid.is_some() {
@@ -76,7 +96,7 @@ impl Process {
                 if cfg.gid.is_some() {
                     flags |= uvll::PROCESS_SETGID;
                 }
-                if cfg.detach {
+                if cfg.detach || pty.is_some() {
                     flags |= uvll::PROCESS_DETACHED;
                 }
                 let mut options = uvll::uv_process_options_t {
@@ -113,8 +133,38 @@ impl Process {
             })
         });
 
+        // The child has its own copies of the slave, which have to be the only
+        // ones left for the master to see it hang up.
+        let pty = match pty {
+            Some((master, slave)) => {
+                unsafe { libc::close(slave); }
+                match ret {
+                    Ok(ref mut p) => match PipeWatcher::open(io_loop, master) {
+                        Ok(master) => {
+                            let pipe = box master as Box<rtio::RtioPipe + Send>;
+                            Some(PtyWatcher { pipe: pipe })
+                        }
+                        Err(e) => {
+                            // Nobody could ever talk to the child over its
+                            // terminal, so it's killed (with SIGKILL) and
+                            // reaped rather than left running.
+                            unsafe { libc::close(master); }
+                            let _ = p.kill(9);
+                            let _ = p.wait();
+                            return Err(e)
+                        }
+                    },
+                    Err(..) => {
+                        unsafe { libc::close(master); }
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
         match ret {
-            Ok(p) => Ok((p, ret_io)),
+            Ok(p) => Ok((p, ret_io, pty)),
             Err(e) => Err(e),
         }
     }
@@ -146,7 +196,8 @@ extern fn on_exit(handle: *mut uvll::uv_process_t,
 
 unsafe fn set_stdio(dst: *mut uvll::uv_stdio_container_t,
                     io: &rtio::StdioContainer,
-                    io_loop: &mut UvIoFactory) -> Option<PipeWatcher> {
+                    io_loop: &mut UvIoFactory,
+                    pty_slave: Option<c_int>) -> Option<PipeWatcher> {
     match *io {
         rtio::Ignored => {
             uvll::set_stdio_container_flags(dst, uvll::STDIO_IGNORE);
@@ -170,6 +221,11 @@ unsafe fn set_stdio(dst: *mut uvll::uv_stdio_container_t,
             uvll::set_stdio_container_stream(dst, pipe.handle());
             Some(pipe)
         }
+        rtio::CreatePty => {
+            uvll::set_stdio_container_flags(dst, uvll::STDIO_INHERIT_FD);
+            uvll::set_stdio_container_fd(dst, pty_slave.unwrap());
+            None
+        }
     }
 }
 
@@ -322,3 +378,80 @@ impl Drop for Process {
         self.close();
     }
 }
+
+/// One end of a pseudo-terminal. The terminal is read and written through a
+/// pipe handle, while its window size is managed with ioctl() directly.
+pub struct PtyWatcher {
+    pipe: Box<rtio::RtioPipe + Send>,
+}
+
+impl PtyWatcher {
+    /// Opens a new pseudo-terminal, returning its master and slave ends.
+    pub fn open(io: &mut UvIoFactory)
+                -> Result<(PtyWatcher, PtyWatcher), UvError> {
+        let (master, slave) = try!(open_pty());
+        let master = try!(PipeWatcher::open(io, master));
+        let slave = try!(PipeWatcher::open(io, slave));
+        Ok((PtyWatcher { pipe: box master as Box<rtio::RtioPipe + Send> },
+            PtyWatcher { pipe: box slave as Box<rtio::RtioPipe + Send> }))
+    }
+}
+
+impl rtio::RtioPty for PtyWatcher {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        match self.pipe.read(buf) {
+            Err(ref e) if pty::is_hangup(e.code) => {
+                Err(uv_error_to_io_error(UvError(uvll::EOF)))
+            }
+            ret => ret,
+        }
+    }
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.pipe.write(buf)
+    }
+    fn clone(&self) -> Box<rtio::RtioPty + Send> {
+        box PtyWatcher { pipe: self.pipe.clone() } as Box<rtio::RtioPty + Send>
+    }
+    #[cfg(unix)]
+    fn raw_fd(&self) -> c_int { self.pipe.raw_fd() }
+    // Ptys are never opened on windows, see `open_pty`.
+    #[cfg(windows)]
+    fn raw_fd(&self) -> c_int { unreachable!() }
+
+    #[cfg(unix)]
+    fn set_winsize(&mut self, width: int, height: int) -> IoResult<()> {
+        pty::set_winsize(self.raw_fd(), width, height, || {
+            uv_error_to_io_error(last_error())
+        })
+    }
+    #[cfg(windows)]
+    fn set_winsize(&mut self, _width: int, _height: int) -> IoResult<()> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    #[cfg(unix)]
+    fn get_winsize(&mut self) -> IoResult<(int, int)> {
+        pty::get_winsize(self.raw_fd(), || uv_error_to_io_error(last_error()))
+    }
+    #[cfg(windows)]
+    fn get_winsize(&mut self) -> IoResult<(int, int)> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+}
+
+#[cfg(unix)]
+fn last_error() -> UvError {
+    UvError(-(os::errno() as c_int))
+}
+
+/// Opens a new pseudo-terminal, returning the descriptors of its master and
+/// slave ends.
+#[cfg(unix)]
+fn open_pty() -> Result<(c_int, c_int), UvError> {
+    pty::open(last_error)
+}
+
+#[cfg(windows)]
+fn open_pty() -> Result<(c_int, c_int), UvError> {
+    Err(UvError(uvll::ENOSYS))
+}
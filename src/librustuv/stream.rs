@@ -126,6 +126,12 @@ impl StreamWatcher {
     }
 
     pub fn write(&mut self, buf: &[u8], may_timeout: bool) -> Result<(), UvError> {
+        self.writev([buf], may_timeout)
+    }
+
+    // Writes all of `bufs` with a single write request.
+    pub fn writev(&mut self, bufs: &[&[u8]],
+                  may_timeout: bool) -> Result<(), UvError> {
         // The ownership of the write request is dubious if this function
         // unwinds. I believe that if the write_cb fails to re-schedule the task
         // then the write request will be leaked.
@@ -158,19 +164,19 @@ impl StreamWatcher {
         // return, there's no guarantee that `buf` is a valid buffer any more.
         //
         // To do this, the write context has an optionally owned vector of
-        // bytes.
-        let data = if may_timeout {Some(Vec::from_slice(buf))} else {None};
-        let uv_buf = if may_timeout {
-            slice_to_uv_buf(data.get_ref().as_slice())
-        } else {
-            slice_to_uv_buf(buf)
+        // bytes, into which all of the buffers are copied.
+        let data = if may_timeout {Some(bufs.concat_vec())} else {None};
+        let uv_bufs: Vec<Buf> = match data {
+            Some(ref data) => vec![slice_to_uv_buf(data.as_slice())],
+            None => bufs.iter().map(|buf| slice_to_uv_buf(*buf)).collect(),
         };
 
         // Send off the request, but be careful to not block until we're sure
         // that the write request is queued. If the request couldn't be queued,
         // then we should return immediately with an error.
         match unsafe {
-            uvll::uv_write(req.handle, self.handle, [uv_buf], write_cb)
+            uvll::uv_write(req.handle, self.handle, uv_bufs.as_slice(),
+                           write_cb)
         } {
             0 => {
                 let mut wcx = WriteContext {
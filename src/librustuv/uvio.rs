@@ -21,17 +21,19 @@ use std::rt::rtio::{ProcessConfig, IoFactory, EventLoop, IoResult};
 
 #[cfg(test)] use std::rt::thread::Thread;
 
-use super::{uv_error_to_io_error, Loop};
+use super::{uv_error_to_io_error, Loop, UvError};
 
 use addrinfo::GetAddrInfoRequest;
 use async::AsyncWatcher;
 use file::{FsRequest, FileWatcher};
+use fs_event::FsEventWatcher;
 use queue::QueuePool;
 use homing::HomeHandle;
 use idle::IdleWatcher;
 use net::{TcpWatcher, TcpListener, UdpWatcher};
 use pipe::{PipeWatcher, PipeListener};
-use process::Process;
+use poll::Poller;
+use process::{Process, PtyWatcher};
 use signal::SignalWatcher;
 use timer::TimerWatcher;
 use tty::TtyWatcher;
@@ -265,14 +267,16 @@ impl IoFactory for UvIoFactory {
 
     fn spawn(&mut self, cfg: ProcessConfig)
             -> IoResult<(Box<rtio::RtioProcess + Send>,
-                         Vec<Option<Box<rtio::RtioPipe + Send>>>)>
+                         Vec<Option<Box<rtio::RtioPipe + Send>>>,
+                         Option<Box<rtio::RtioPty + Send>>)>
     {
         match Process::spawn(self, cfg) {
-            Ok((p, io)) => {
+            Ok((p, io, pty)) => {
                 Ok((p as Box<rtio::RtioProcess + Send>,
                     io.move_iter().map(|i| i.map(|p| {
                         box p as Box<rtio::RtioPipe + Send>
-                    })).collect()))
+                    })).collect(),
+                    pty.map(|p| box p as Box<rtio::RtioPty + Send>)))
             }
             Err(e) => Err(uv_error_to_io_error(e)),
         }
@@ -298,6 +302,43 @@ impl IoFactory for UvIoFactory {
         }
     }
 
+    // libuv has no support for unix domain sockets in datagram mode
+    fn unix_datagram_bind(&mut self, _path: Option<&CString>)
+                          -> IoResult<Box<rtio::RtioUnixDatagram + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    fn tcp_from_raw_fd(&mut self, _fd: c_int)
+                       -> IoResult<Box<rtio::RtioTcpStream + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    fn tcp_listener_from_raw_fd(&mut self, _fd: c_int)
+                                -> IoResult<Box<rtio::RtioTcpListener + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    fn udp_from_raw_fd(&mut self, _fd: c_int)
+                       -> IoResult<Box<rtio::RtioUdpSocket + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    fn unix_from_raw_fd(&mut self, fd: c_int)
+                        -> IoResult<Box<rtio::RtioPipe + Send>> {
+        match PipeWatcher::open(self, fd) {
+            Ok(p) => Ok(box p as Box<rtio::RtioPipe + Send>),
+            Err(e) => Err(uv_error_to_io_error(e)),
+        }
+    }
+
+    fn unix_listener_from_raw_fd(&mut self, fd: c_int)
+                                 -> IoResult<Box<rtio::RtioUnixListener + Send>> {
+        match PipeListener::open(self, fd) {
+            Ok(p) => Ok(p as Box<rtio::RtioUnixListener + Send>),
+            Err(e) => Err(uv_error_to_io_error(e)),
+        }
+    }
+
     fn tty_open(&mut self, fd: c_int, readable: bool)
             -> IoResult<Box<rtio::RtioTTY + Send>> {
         match TtyWatcher::new(self, fd, readable) {
@@ -315,6 +356,18 @@ impl IoFactory for UvIoFactory {
         }
     }
 
+    fn pty_open(&mut self)
+        -> IoResult<(Box<rtio::RtioPty + Send>, Box<rtio::RtioPty + Send>)>
+    {
+        match PtyWatcher::open(self) {
+            Ok((master, slave)) => {
+                Ok((box master as Box<rtio::RtioPty + Send>,
+                    box slave as Box<rtio::RtioPty + Send>))
+            }
+            Err(e) => Err(uv_error_to_io_error(e))
+        }
+    }
+
     fn signal(&mut self, signum: int, cb: Box<rtio::Callback + Send>)
         -> IoResult<Box<rtio::RtioSignal + Send>>
     {
@@ -323,4 +376,18 @@ impl IoFactory for UvIoFactory {
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
+
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        Ok(Poller::new(self) as Box<rtio::RtioPoller + Send>)
+    }
+
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<rtio::FsEventCallback + Send>)
+        -> IoResult<Box<rtio::RtioFsWatcher + Send>>
+    {
+        match FsEventWatcher::new(self, path, recursive, cb) {
+            Ok(w) => Ok(w as Box<rtio::RtioFsWatcher + Send>),
+            Err(e) => Err(uv_error_to_io_error(e)),
+        }
+    }
 }
@@ -39,7 +39,7 @@ use libc::uintptr_t;
 
 pub use self::errors::{EACCES, ECONNREFUSED, ECONNRESET, EPIPE, ECONNABORTED,
                        ECANCELED, EBADF, ENOTCONN, ENOENT, EADDRNOTAVAIL,
-                       EADDRINUSE};
+                       EADDRINUSE, EEXIST, ENOSYS};
 
 pub static OK: c_int = 0;
 pub static EOF: c_int = -4095;
@@ -63,6 +63,8 @@ pub mod errors {
     pub static EBADF: c_int = -4083;
     pub static EADDRNOTAVAIL: c_int = -4090;
     pub static EADDRINUSE: c_int = -4091;
+    pub static EEXIST: c_int = -4075;
+    pub static ENOSYS: c_int = -4054;
 }
 #[cfg(not(windows))]
 pub mod errors {
@@ -80,6 +82,8 @@ pub mod errors {
     pub static EBADF : c_int = -libc::EBADF;
     pub static EADDRNOTAVAIL : c_int = -libc::EADDRNOTAVAIL;
     pub static EADDRINUSE : c_int = -libc::EADDRINUSE;
+    pub static EEXIST : c_int = -libc::EEXIST;
+    pub static ENOSYS : c_int = -libc::ENOSYS;
 }
 
 pub static PROCESS_SETUID: c_int = 1 << 0;
@@ -162,6 +166,9 @@ pub type uv_pipe_t = c_void;
 pub type uv_tty_t = c_void;
 pub type uv_signal_t = c_void;
 pub type uv_shutdown_t = c_void;
+pub type uv_work_t = c_void;
+pub type uv_poll_t = c_void;
+pub type uv_fs_event_t = c_void;
 
 pub struct uv_timespec_t {
     pub tv_sec: libc::c_long,
@@ -251,12 +258,31 @@ pub type uv_signal_cb = extern "C" fn(handle: *mut uv_signal_t,
                                       signum: c_int);
 pub type uv_fs_cb = extern "C" fn(req: *mut uv_fs_t);
 pub type uv_shutdown_cb = extern "C" fn(req: *mut uv_shutdown_t, status: c_int);
+pub type uv_work_cb = extern "C" fn(req: *mut uv_work_t);
+pub type uv_after_work_cb = extern "C" fn(req: *mut uv_work_t, status: c_int);
+pub type uv_poll_cb = extern "C" fn(handle: *mut uv_poll_t,
+                                    status: c_int,
+                                    events: c_int);
+pub type uv_fs_event_cb = extern "C" fn(handle: *mut uv_fs_event_t,
+                                        filename: *const c_char,
+                                        events: c_int,
+                                        status: c_int);
 
 #[cfg(unix)] pub type uv_uid_t = libc::types::os::arch::posix88::uid_t;
 #[cfg(unix)] pub type uv_gid_t = libc::types::os::arch::posix88::gid_t;
 #[cfg(windows)] pub type uv_uid_t = libc::c_uchar;
 #[cfg(windows)] pub type uv_gid_t = libc::c_uchar;
 
+#[cfg(unix)] pub type uv_os_fd_t = c_int;
+#[cfg(windows)] pub type uv_os_fd_t = libc::HANDLE;
+
+pub static UV_READABLE: c_int = 1;
+pub static UV_WRITABLE: c_int = 2;
+
+pub static UV_RENAME: c_int = 1;
+pub static UV_CHANGE: c_int = 2;
+pub static UV_FS_EVENT_RECURSIVE: c_uint = 4;
+
 #[repr(C)]
 #[deriving(PartialEq)]
 pub enum uv_handle_type {
@@ -532,6 +558,7 @@ extern {
     pub fn uv_req_size(ty: uv_req_type) -> size_t;
     pub fn uv_run(l: *mut uv_loop_t, mode: uv_run_mode) -> c_int;
     pub fn uv_close(h: *mut uv_handle_t, cb: uv_close_cb);
+    pub fn uv_fileno(h: *const uv_handle_t, fd: *mut uv_os_fd_t) -> c_int;
     pub fn uv_walk(l: *mut uv_loop_t, cb: uv_walk_cb, arg: *mut c_void);
     pub fn uv_buf_init(base: *mut c_char, len: c_uint) -> uv_buf_t;
     pub fn uv_strerror(err: c_int) -> *const c_char;
@@ -589,6 +616,19 @@ extern {
     pub fn uv_udp_getsockname(h: *mut uv_udp_t, name: *mut sockaddr,
                               len: *mut c_int) -> c_int;
 
+    // poll bindings
+    pub fn uv_poll_init(l: *mut uv_loop_t, h: *mut uv_poll_t,
+                        fd: c_int) -> c_int;
+    pub fn uv_poll_start(h: *mut uv_poll_t, events: c_int,
+                         cb: uv_poll_cb) -> c_int;
+    pub fn uv_poll_stop(h: *mut uv_poll_t) -> c_int;
+
+    // fs event bindings
+    pub fn uv_fs_event_init(l: *mut uv_loop_t, h: *mut uv_fs_event_t) -> c_int;
+    pub fn uv_fs_event_start(h: *mut uv_fs_event_t, cb: uv_fs_event_cb,
+                             filename: *const c_char, flags: c_uint) -> c_int;
+    pub fn uv_fs_event_stop(h: *mut uv_fs_event_t) -> c_int;
+
     // timer bindings
     pub fn uv_timer_init(l: *mut uv_loop_t, t: *mut uv_timer_t) -> c_int;
     pub fn uv_timer_start(t: *mut uv_timer_t, cb: uv_timer_cb,
@@ -596,6 +636,11 @@ extern {
                           repeat: libc::uint64_t) -> c_int;
     pub fn uv_timer_stop(handle: *mut uv_timer_t) -> c_int;
 
+    // threadpool operations
+    pub fn uv_queue_work(l: *mut uv_loop_t, req: *mut uv_work_t,
+                         work_cb: uv_work_cb,
+                         after_work_cb: uv_after_work_cb) -> c_int;
+
     // fs operations
     pub fn uv_fs_open(loop_ptr: *mut uv_loop_t, req: *mut uv_fs_t,
                       path: *const c_char, flags: c_int, mode: c_int,
@@ -52,24 +52,29 @@ fs::unlink(&path);
 use c_str::ToCStr;
 use clone::Clone;
 use collections::Collection;
+use comm::{Sender, Receiver, channel};
 use io::standard_error;
 use io::{FilePermission, Write, UnstableFileStat, Open, FileAccess, FileMode};
 use io::{IoResult, IoError, FileStat, SeekStyle, Seek, Writer, Reader};
 use io::{Read, Truncate, SeekCur, SeekSet, ReadWrite, SeekEnd, Append};
-use io::UpdateIoError;
+use io::{UpdateIoError, AsRawFd};
 use io;
 use iter::Iterator;
 use kinds::Send;
 use libc;
+use mem;
 use option::{Some, None, Option};
+use os;
 use owned::Box;
 use path::{Path, GenericPath};
 use path;
+use raw;
 use result::{Err, Ok};
 use rt::rtio::LocalIo;
 use rt::rtio;
-use slice::ImmutableVector;
+use slice::{ImmutableVector, Vector};
 use string::String;
+use uint;
 use vec::Vec;
 
 /// Unconstrained file access type that exposes read and write operations
@@ -156,6 +161,21 @@ impl File {
         })
     }
 
+    /// Creates a `File` which takes ownership of the file descriptor `fd`.
+    ///
+    /// The descriptor will be closed when the returned `File` is dropped. As
+    /// there is no path available for the descriptor, errors will refer to
+    /// the file as `/dev/fd/<fd>`.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<File> {
+        LocalIo::maybe_raise(|io| {
+            Ok(File {
+                path: Path::new(format!("/dev/fd/{}", fd)),
+                fd: io.fs_from_raw_fd(fd, rtio::CloseSynchronously),
+                last_nread: -1
+            })
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Attempts to open a file in read-only mode. This function is equivalent to
     /// `File::open_mode(path, Open, Read)`, and will raise all of the same
     /// errors that `File::open_mode` does.
@@ -251,6 +271,90 @@ impl File {
         err.update_err("couldn't fstat file",
                        |e| format!("{}; path={}", e, self.path.display()))
     }
+
+    /// Reads data from this file starting at `offset` bytes from its start,
+    /// returning the number of bytes read. The position used by `read`,
+    /// `write` and `seek` is left untouched.
+    ///
+    /// As with `read`, an error of kind `EndOfFile` is returned if `offset` is
+    /// at or past the end of the file.
+    pub fn read_at(&mut self, buf: &mut [u8], offset: u64) -> IoResult<uint> {
+        let result = match self.fd.pread(buf, offset) {
+            Ok(0) => Err(standard_error(io::EndOfFile)),
+            Ok(read) => Ok(read as uint),
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        };
+        result.update_err("couldn't read file", |e| {
+            format!("{}; path={}; offset={}", e, self.path.display(), offset)
+        })
+    }
+
+    /// Writes all of `buf` to this file starting at `offset` bytes from its
+    /// start, leaving the position used by `read`, `write` and `seek`
+    /// untouched.
+    ///
+    /// Note that on some platforms, notably linux, data written through a file
+    /// opened in `Append` mode is always added to the end of the file, whatever
+    /// the offset given.
+    pub fn write_at(&mut self, buf: &[u8], offset: u64) -> IoResult<()> {
+        let err = self.fd.pwrite(buf, offset).map_err(IoError::from_rtio_error);
+        err.update_err("couldn't write to file", |e| {
+            format!("{}; path={}; offset={}", e, self.path.display(), offset)
+        })
+    }
+
+    /// Takes a shared advisory lock on this file, blocking until any exclusive
+    /// lock held elsewhere is released.
+    ///
+    /// Any number of shared locks can be held on a file at once. Advisory
+    /// locks are only honored by other processes which also lock the file, and
+    /// they are released by `unlock` or when the file is closed. Taking a lock
+    /// on a file which is already locked through this handle converts the
+    /// existing lock to the requested kind.
+    ///
+    /// On unix these are `flock` locks rather than POSIX record locks taken
+    /// with `fcntl`. They belong to the open file rather than to the process,
+    /// so two `File`s opened on the same path exclude each other even within
+    /// one process, and closing some other descriptor for the file doesn't
+    /// release them. They may not be seen by other machines for files on
+    /// network filesystems such as NFS. On windows they are taken with
+    /// `LockFileEx`.
+    pub fn lock_shared(&mut self) -> IoResult<()> {
+        self.lock(false, true).map(|_| ())
+    }
+
+    /// Takes an exclusive advisory lock on this file, blocking until every
+    /// other lock on it is released.
+    ///
+    /// See `lock_shared` for more details about advisory locks.
+    pub fn lock_exclusive(&mut self) -> IoResult<()> {
+        self.lock(true, true).map(|_| ())
+    }
+
+    /// Attempts to take a shared advisory lock on this file without blocking,
+    /// returning whether the lock was acquired.
+    pub fn try_lock_shared(&mut self) -> IoResult<bool> {
+        self.lock(false, false)
+    }
+
+    /// Attempts to take an exclusive advisory lock on this file without
+    /// blocking, returning whether the lock was acquired.
+    pub fn try_lock_exclusive(&mut self) -> IoResult<bool> {
+        self.lock(true, false)
+    }
+
+    /// Releases the advisory lock held on this file through this handle.
+    pub fn unlock(&mut self) -> IoResult<()> {
+        let err = self.fd.unlock().map_err(IoError::from_rtio_error);
+        err.update_err("couldn't unlock file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
+
+    fn lock(&mut self, exclusive: bool, wait: bool) -> IoResult<bool> {
+        let err = self.fd.lock(exclusive, wait).map_err(IoError::from_rtio_error);
+        err.update_err("couldn't lock file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
 }
 
 /// Unlink a file from the underlying filesystem.
@@ -770,6 +874,10 @@ pub fn change_file_times(path: &Path, atime: u64, mtime: u64) -> IoResult<()> {
                    |e| format!("{}; path={}", e, path.display()))
 }
 
+impl AsRawFd for File {
+    fn as_raw_fd(&self) -> libc::c_int { self.fd.raw_fd() }
+}
+
 impl Reader for File {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         fn update_err<T>(result: IoResult<T>, file: &File) -> IoResult<T> {
@@ -792,6 +900,21 @@ impl Reader for File {
             Err(e) => Err(e)
         }
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let result = match self.fd.readv(bufs) {
+            Ok(read) => {
+                self.last_nread = read;
+                match read {
+                    0 => Err(standard_error(io::EndOfFile)),
+                    _ => Ok(read as uint)
+                }
+            }
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        };
+        result.update_err("couldn't read file",
+                          |e| format!("{}; path={}", e, self.path.display()))
+    }
 }
 
 impl Writer for File {
@@ -800,6 +923,12 @@ impl Writer for File {
         err.update_err("couldn't write to file",
                        |e| format!("{}; path={}", e, self.path.display()))
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let err = self.fd.writev(bufs).map_err(IoError::from_rtio_error);
+        err.update_err("couldn't write to file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
 }
 
 impl Seek for File {
@@ -828,6 +957,380 @@ impl Seek for File {
     }
 }
 
+/// A hint to the operating system about how the contents of a `MappedFile`
+/// will be accessed, given to `MappedFile::advise`.
+pub enum MapAdvice {
+    /// No particular access pattern, the default.
+    AdviseNormal,
+    /// The contents will be accessed in order, so pages can be read ahead
+    /// aggressively and freed soon after they are accessed.
+    AdviseSequential,
+    /// The contents will be accessed in no particular order, so reading ahead
+    /// is of little use.
+    AdviseRandom,
+    /// The contents will be needed soon, so they should be paged in now.
+    AdviseWillNeed,
+    /// The contents will not be needed soon, so they may be paged out.
+    AdviseDontNeed,
+}
+
+/// A file whose contents are mapped into memory and accessed as a slice of
+/// bytes, rather than through `read` and `write` calls.
+///
+/// The mapping covers the whole file and is shared with the file itself:
+/// changes made through `as_mut_slice` are visible to other processes reading
+/// or mapping the file, although they only reach the disk once the operating
+/// system writes them back or `flush` is called. The mapping does not follow
+/// changes to the length of the file made through other handles until `remap`
+/// is called, and accessing the mapping of a file which was shrunk elsewhere
+/// may crash the process.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::io::fs::MappedFile;
+///
+/// let map = MappedFile::open(&Path::new("index.bin")).unwrap();
+/// let header = map.as_slice().slice_to(16);
+/// ```
+pub struct MappedFile {
+    file: File,
+    map: Option<os::MemoryMap>,
+    len: uint,
+    writable: bool,
+}
+
+impl MappedFile {
+    /// Opens the file at `path` and maps it read-only.
+    pub fn open(path: &Path) -> IoResult<MappedFile> {
+        File::open(path).and_then(|f| MappedFile::new(f, false))
+    }
+
+    /// Opens the file at `path` for both reading and writing, creating it if
+    /// it does not exist, and maps it.
+    pub fn open_rw(path: &Path) -> IoResult<MappedFile> {
+        File::open_mode(path, Open, ReadWrite).and_then(|f| {
+            MappedFile::new(f, true)
+        })
+    }
+
+    /// Maps an already opened file, taking ownership of it. A `writable`
+    /// mapping requires the file to be open for both reading and writing.
+    pub fn new(file: File, writable: bool) -> IoResult<MappedFile> {
+        let mut ret = MappedFile {
+            file: file,
+            map: None,
+            len: 0,
+            writable: writable,
+        };
+        try!(ret.remap());
+        Ok(ret)
+    }
+
+    /// Views the contents of the file.
+    pub fn as_slice<'a>(&'a self) -> &'a [u8] {
+        match self.map {
+            Some(ref map) => unsafe {
+                mem::transmute(raw::Slice {
+                    data: map.data as *const u8,
+                    len: self.len,
+                })
+            },
+            None => &[],
+        }
+    }
+
+    /// Views the contents of the file mutably.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the file was mapped read-only.
+    pub fn as_mut_slice<'a>(&'a mut self) -> &'a mut [u8] {
+        assert!(self.writable, "file was mapped read-only");
+        match self.map {
+            Some(ref map) => unsafe {
+                mem::transmute(raw::Slice {
+                    data: map.data as *const u8,
+                    len: self.len,
+                })
+            },
+            None => &mut [],
+        }
+    }
+
+    /// Writes any changes made through the mapping back to the disk, blocking
+    /// until they have been written.
+    pub fn flush(&mut self) -> IoResult<()> {
+        let err = match self.map {
+            Some(ref map) => sync_map(map, &mut self.file),
+            None => Ok(()),
+        };
+        err.update_err("couldn't flush mapped file",
+                       |e| format!("{}; path={}", e, self.file.path().display()))
+    }
+
+    /// Tells the operating system how the mapping is going to be accessed, so
+    /// that it can page the file in and out accordingly. This is only a hint,
+    /// and does nothing on platforms which don't support it.
+    pub fn advise(&self, advice: MapAdvice) -> IoResult<()> {
+        let err = match self.map {
+            Some(ref map) => advise_map(map, advice),
+            None => Ok(()),
+        };
+        err.update_err("couldn't advise mapped file",
+                       |e| format!("{}; path={}", e, self.file.path().display()))
+    }
+
+    /// Changes the length of the file to `size` and remaps it. If the file
+    /// grows, the new bytes are filled with 0s.
+    ///
+    /// Slices taken from the old mapping can not outlive this call.
+    pub fn truncate(&mut self, size: u64) -> IoResult<()> {
+        self.unmap();
+        try!(self.file.truncate(size as i64));
+        self.remap()
+    }
+
+    /// Maps the file again, picking up any change to its length which was
+    /// made through another handle.
+    pub fn remap(&mut self) -> IoResult<()> {
+        // The old mapping goes first, windows doesn't allow a file to be
+        // mapped more than once.
+        self.unmap();
+        let size = try!(self.file.stat()).size;
+        if size > uint::MAX as u64 {
+            return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "file is too large to be mapped",
+                detail: Some(format!("path={}; size={}",
+                                     self.file.path().display(), size)),
+            })
+        }
+        // Empty mappings are invalid, so empty files are simply left unmapped.
+        if size == 0 { return Ok(()) }
+
+        let mut options = vec![os::MapReadable,
+                               os::MapFd(self.file.fd.raw_fd()),
+                               shared_map_flags()];
+        if self.writable {
+            options.push(os::MapWritable);
+        }
+        match os::MemoryMap::new(size as uint, options.as_slice()) {
+            Ok(map) => {
+                self.map = Some(map);
+                self.len = size as uint;
+                Ok(())
+            }
+            Err(e) => Err(IoError {
+                kind: io::OtherIoError,
+                desc: "couldn't map file",
+                detail: Some(format!("{}; path={}", e,
+                                     self.file.path().display())),
+            }),
+        }
+    }
+
+    /// Returns a reference to the mapped file.
+    pub fn file<'a>(&'a self) -> &'a File { &self.file }
+
+    fn unmap(&mut self) {
+        self.map = None;
+        self.len = 0;
+    }
+}
+
+impl Collection for MappedFile {
+    fn len(&self) -> uint { self.len }
+}
+
+#[cfg(unix)]
+fn shared_map_flags() -> os::MapOption {
+    os::MapNonStandardFlags(libc::MAP_SHARED | libc::MAP_FILE)
+}
+
+// Views of files are always shared on windows, which ignores these flags.
+#[cfg(windows)]
+fn shared_map_flags() -> os::MapOption {
+    os::MapNonStandardFlags(0)
+}
+
+#[cfg(unix)]
+fn sync_map(map: &os::MemoryMap, _file: &mut File) -> IoResult<()> {
+    match unsafe {
+        libc::msync(map.data as *mut libc::c_void, map.len as libc::size_t,
+                    libc::MS_SYNC)
+    } {
+        0 => Ok(()),
+        _ => Err(IoError::last_error()),
+    }
+}
+
+#[cfg(windows)]
+fn sync_map(map: &os::MemoryMap, file: &mut File) -> IoResult<()> {
+    use libc::types::os::arch::extra::{LPCVOID, SIZE_T, BOOL};
+    extern "system" {
+        fn FlushViewOfFile(lpBaseAddress: LPCVOID,
+                           dwNumberOfBytesToFlush: SIZE_T) -> BOOL;
+    }
+
+    // Flushing the view only starts writing it back, the file itself has to
+    // be flushed to wait for the data to reach the disk.
+    match unsafe { FlushViewOfFile(map.data as LPCVOID, 0) } {
+        0 => Err(IoError::last_error()),
+        _ => file.fsync(),
+    }
+}
+
+#[cfg(unix)]
+fn advise_map(map: &os::MemoryMap, advice: MapAdvice) -> IoResult<()> {
+    use libc::consts::os::bsd44::{MADV_NORMAL, MADV_SEQUENTIAL, MADV_RANDOM};
+    use libc::consts::os::bsd44::{MADV_WILLNEED, MADV_DONTNEED};
+    use libc::funcs::bsd44::madvise;
+
+    let advice = match advice {
+        AdviseNormal => MADV_NORMAL,
+        AdviseSequential => MADV_SEQUENTIAL,
+        AdviseRandom => MADV_RANDOM,
+        AdviseWillNeed => MADV_WILLNEED,
+        AdviseDontNeed => MADV_DONTNEED,
+    };
+    match unsafe {
+        madvise(map.data as *mut libc::c_void, map.len as libc::size_t, advice)
+    } {
+        0 => Ok(()),
+        _ => Err(IoError::last_error()),
+    }
+}
+
+#[cfg(windows)]
+fn advise_map(_map: &os::MemoryMap, _advice: MapAdvice) -> IoResult<()> {
+    Ok(())
+}
+
+/// A change to a watched file or directory, as reported by a `Watcher`.
+#[deriving(PartialEq, Clone)]
+pub struct FileEvent {
+    /// The path which changed. For a watched directory, this is the path of
+    /// the entry within it which changed.
+    pub path: Path,
+    /// What happened to the path.
+    pub kind: FileEventKind,
+}
+
+/// The kinds of changes reported by a `Watcher`.
+#[deriving(PartialEq, Clone, Show)]
+pub enum FileEventKind {
+    /// The path was created.
+    Created,
+    /// The contents or metadata of the path were modified.
+    Modified,
+    /// The path was removed.
+    Removed,
+    /// The path was renamed, either from or to this name.
+    Renamed,
+}
+
+/// Watches files and directories for changes, delivering events about them to
+/// a `Receiver`.
+///
+/// Watching a directory reports changes to the entries directly inside of it,
+/// while watching it recursively also covers the whole tree below it,
+/// including directories which are created after the watch started.
+///
+/// Some platforms can't tell renames apart from other changes, so a rename may
+/// also be reported as the removal of the old path and the creation of the
+/// new one. Events are only a hint that a path changed, they should be
+/// followed by a `stat` or a read of the path rather than trusted blindly.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![allow(unused_must_use)]
+/// use std::io::fs::Watcher;
+///
+/// let mut watcher = Watcher::new();
+/// watcher.watch_recursive(&Path::new("src"));
+/// loop {
+///     let event = watcher.rx.recv();
+///     println!("{} was {}", event.path.display(), event.kind);
+/// }
+/// ```
+pub struct Watcher {
+    handles: Vec<(Path, Box<rtio::RtioFsWatcher + Send>)>,
+    tx: Sender<FileEvent>,
+
+    /// Events about all of the watched paths are received on this receiver.
+    /// It is exposed to allow selecting over it as well as manipulating it
+    /// directly.
+    pub rx: Receiver<FileEvent>,
+}
+
+impl Watcher {
+    /// Creates a new watcher which isn't watching anything yet.
+    pub fn new() -> Watcher {
+        let (tx, rx) = channel();
+        Watcher {
+            handles: Vec::new(),
+            tx: tx,
+            rx: rx,
+        }
+    }
+
+    /// Starts watching the file or directory at `path`. Watching a path which
+    /// is already being watched does nothing.
+    pub fn watch(&mut self, path: &Path) -> IoResult<()> {
+        self.add(path, false)
+    }
+
+    /// Starts watching the directory tree rooted at `path`.
+    pub fn watch_recursive(&mut self, path: &Path) -> IoResult<()> {
+        self.add(path, true)
+    }
+
+    /// Stops watching `path`. Events about it which were already delivered
+    /// may still be received.
+    pub fn unwatch(&mut self, path: &Path) {
+        match self.handles.iter().position(|&(ref p, _)| p == path) {
+            Some(i) => mem::drop(self.handles.remove(i)),
+            None => {}
+        }
+    }
+
+    fn add(&mut self, path: &Path, recursive: bool) -> IoResult<()> {
+        struct EventCallback {
+            tx: Sender<FileEvent>,
+        }
+        impl rtio::FsEventCallback for EventCallback {
+            fn call(&mut self, event: rtio::FsEvent) {
+                let kind = match event.kind {
+                    rtio::FsCreated => Created,
+                    rtio::FsModified => Modified,
+                    rtio::FsRemoved => Removed,
+                    rtio::FsRenamed => Renamed,
+                };
+                let _ = self.tx.send_opt(FileEvent {
+                    path: Path::new(event.path),
+                    kind: kind,
+                });
+            }
+        }
+
+        if self.handles.iter().any(|&(ref p, _)| p == path) {
+            return Ok(())
+        }
+        let err = LocalIo::maybe_raise(|io| {
+            io.fs_watch(&path.to_c_str(), recursive, box EventCallback {
+                tx: self.tx.clone(),
+            })
+        }).map_err(IoError::from_rtio_error);
+        let handle = try!(err.update_err("couldn't watch path", |e| {
+            format!("{}; path={}", e, path.display())
+        }));
+        self.handles.push((path.clone(), handle));
+        Ok(())
+    }
+}
+
 impl path::Path {
     /// Get information on the file, directory, etc at this path.
     ///
@@ -899,7 +1402,7 @@ mod test {
     use str;
     use io::fs::{File, rmdir, mkdir, readdir, rmdir_recursive,
                  mkdir_recursive, copy, unlink, stat, symlink, link,
-                 readlink, chmod, lstat, change_file_times};
+                 readlink, chmod, lstat, change_file_times, MappedFile};
     use path::Path;
     use io;
     use ops::Drop;
@@ -1586,4 +2089,199 @@ mod test {
         let actual = check!(File::open(&tmpdir.join("test")).read_to_end());
         assert!(actual.as_slice() == bytes);
     })
+
+    iotest!(fn mapped_file_read() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("map");
+        check!(File::create(&path).write(b"hello mapped"));
+
+        let map = check!(MappedFile::open(&path));
+        assert_eq!(map.len(), 12);
+        assert_eq!(map.as_slice(), b"hello mapped");
+        check!(map.advise(AdviseSequential));
+
+        check!(File::create(&tmpdir.join("empty")));
+        let map = check!(MappedFile::open(&tmpdir.join("empty")));
+        assert!(map.is_empty());
+    })
+
+    iotest!(fn mapped_file_write() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("map");
+        check!(File::create(&path).write(b"hello"));
+
+        {
+            let mut map = check!(MappedFile::open_rw(&path));
+            map.as_mut_slice()[0] = 'j' as u8;
+            check!(map.flush());
+            check!(map.truncate(8));
+            assert_eq!(map.as_slice(), b"jello\0\0\0");
+            map.as_mut_slice()[7] = '!' as u8;
+            check!(map.flush());
+        }
+        assert_eq!(check!(File::open(&path).read_to_end()).as_slice(),
+                   b"jello\0\0!");
+    })
+
+    #[test]
+    #[should_fail]
+    fn mapped_file_read_only() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("map");
+        check!(File::create(&path).write(b"hello"));
+        let mut map = check!(MappedFile::open(&path));
+        map.as_mut_slice();
+    }
+
+    iotest!(fn watcher_reports_changes() {
+        fn expect(watcher: &mut Watcher, path: &Path, kind: FileEventKind) {
+            loop {
+                let event = watcher.rx.recv();
+                if event.path == *path && event.kind == kind { break }
+            }
+        }
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("a");
+        let mut watcher = Watcher::new();
+        check!(watcher.watch(tmpdir.path()));
+
+        check!(File::create(&path));
+        expect(&mut watcher, &path, Created);
+        check!(File::open_mode(&path, Append, Write).write(b"foo"));
+        expect(&mut watcher, &path, Modified);
+        check!(unlink(&path));
+        expect(&mut watcher, &path, Removed);
+
+        watcher.unwatch(tmpdir.path());
+        while watcher.rx.try_recv().is_ok() {}
+        check!(File::create(&path));
+        sleep(10);
+        assert!(watcher.rx.try_recv().is_err());
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+
+    iotest!(fn watcher_recursive() {
+        let tmpdir = tmpdir();
+        check!(mkdir(&tmpdir.join("sub"), io::UserRWX));
+        let mut watcher = Watcher::new();
+        check!(watcher.watch_recursive(tmpdir.path()));
+
+        let path = tmpdir.join("sub").join("a");
+        check!(File::create(&path));
+        loop {
+            let event = watcher.rx.recv();
+            if event.path == path && event.kind == Created { break }
+        }
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+
+    iotest!(fn watcher_missing_path() {
+        let tmpdir = tmpdir();
+        let mut watcher = Watcher::new();
+        assert!(watcher.watch(&tmpdir.join("missing")).is_err());
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+
+    iotest!(fn lock_works() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = check!(File::create(&path));
+        let mut b = check!(File::open(&path));
+
+        check!(a.lock_exclusive());
+        assert!(!check!(b.try_lock_shared()));
+        assert!(!check!(b.try_lock_exclusive()));
+        check!(a.unlock());
+
+        assert!(check!(b.try_lock_shared()));
+        assert!(check!(a.try_lock_shared()));
+        assert!(!check!(a.try_lock_exclusive()));
+        check!(b.unlock());
+        assert!(check!(a.try_lock_exclusive()));
+        check!(a.unlock());
+    })
+
+    iotest!(fn lock_blocks() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = check!(File::create(&path));
+        check!(a.lock_exclusive());
+
+        let (tx, rx) = channel();
+        let p = path.clone();
+        spawn(proc() {
+            let mut b = check!(File::open(&p));
+            tx.send(());
+            check!(b.lock_shared());
+            tx.send(());
+            check!(b.unlock());
+        });
+
+        rx.recv();
+        sleep(10);
+        assert!(rx.try_recv().is_err());
+        check!(a.unlock());
+        rx.recv();
+    })
+
+    iotest!(fn vectored_io() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("vectored");
+        let mut file = check!(File::open_mode(&path, io::Truncate,
+                                              io::ReadWrite));
+        check!(file.write_vectored([b"hello", b"", b" world"]));
+        check!(file.seek(0, SeekSet));
+
+        let mut a = [0u8, ..5];
+        let mut b = [0u8, ..16];
+        let n = check!(file.read_vectored([a.as_mut_slice(),
+                                           b.as_mut_slice()]));
+        assert_eq!(n, 11);
+        assert_eq!(a.as_slice(), b"hello");
+        assert_eq!(b.slice_to(6), b" world");
+
+        match file.read_vectored([a.as_mut_slice()]) {
+            Err(ref e) if e.kind == io::EndOfFile => {}
+            r => fail!("expected end of file, got {}", r),
+        }
+    })
+
+    iotest!(fn read_at_write_at() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("positional");
+        let mut file = check!(File::open_mode(&path, io::Truncate,
+                                              io::ReadWrite));
+        check!(file.write(b"hello world"));
+        check!(file.write_at(b"W", 6));
+        assert_eq!(check!(file.tell()), 11);
+
+        let mut buf = [0u8, ..5];
+        assert_eq!(check!(file.read_at(buf, 6)), 5);
+        assert_eq!(buf.as_slice(), b"World");
+        assert_eq!(check!(file.tell()), 11);
+
+        match file.read_at(buf, 11) {
+            Err(ref e) if e.kind == io::EndOfFile => {}
+            r => fail!("expected end of file, got {}", r),
+        }
+        check!(file.seek(0, SeekSet));
+        assert_eq!(check!(file.read_to_end()), b"hello World".to_vec());
+    })
+
+    iotest!(fn from_raw_fd() {
+        use io::AsRawFd;
+        use libc;
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("adopted");
+        let file = check!(File::create(&path));
+        let fd = unsafe { libc::dup(file.as_raw_fd()) };
+        assert!(fd >= 0);
+        drop(file);
+
+        let mut file = check!(File::from_raw_fd(fd));
+        assert_eq!(file.as_raw_fd(), fd);
+        check!(file.write(b"adopted"));
+        drop(file);
+        assert_eq!(check!(File::open(&path).read_to_end()),
+                   b"adopted".to_vec());
+    })
 }
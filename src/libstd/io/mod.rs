@@ -273,7 +273,9 @@ pub mod extensions;
 pub mod fs;
 pub mod net;
 pub mod pipe;
+pub mod poll;
 pub mod process;
+pub mod pty;
 pub mod signal;
 pub mod stdio;
 pub mod timer;
@@ -550,6 +552,20 @@ pub trait Reader {
     /// not to return 0 if you can avoid it.
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
 
+    /// Reads into each of `bufs` in turn, returning the total number of bytes
+    /// read. As with `read`, this may read fewer bytes than the buffers can
+    /// hold, and returns `Err` on EOF.
+    ///
+    /// Readers backed by a file or socket do this with a single `readv`
+    /// system call where the platform supports it. By default only the first
+    /// non-empty buffer is read into.
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        match bufs.mut_iter().find(|buf| buf.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+
     // Convenient helper methods based on the above methods
 
     /// Reads at least `min` bytes and places them in `buf`.
@@ -943,10 +959,16 @@ pub trait Reader {
 
 impl Reader for Box<Reader> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> { self.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.read_vectored(bufs)
+    }
 }
 
 impl<'a> Reader for &'a mut Reader {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> { self.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.read_vectored(bufs)
+    }
 }
 
 /// Returns a slice of `v` between `start` and `end`.
@@ -1036,6 +1058,24 @@ pub trait Writer {
     /// data (if any) was actually written.
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
 
+    /// Write the entirety of each of `bufs`, in order.
+    ///
+    /// Writers backed by a file or socket do this with as few `writev` system
+    /// calls as possible, so that the buffers need neither be copied together
+    /// nor be written separately. By default each buffer is passed to `write`
+    /// in turn.
+    ///
+    /// # Errors
+    ///
+    /// As with `write`, if an error is returned then it is unknown how much
+    /// data (if any) was actually written.
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.write(*buf));
+        }
+        Ok(())
+    }
+
     /// Flush this output stream, ensuring that all intermediately buffered
     /// contents reach their destination.
     ///
@@ -1278,6 +1318,11 @@ impl Writer for Box<Writer> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> IoResult<()> { self.write(buf) }
 
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.write_vectored(bufs)
+    }
+
     #[inline]
     fn flush(&mut self) -> IoResult<()> { self.flush() }
 }
@@ -1286,6 +1331,11 @@ impl<'a> Writer for &'a mut Writer {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> IoResult<()> { self.write(buf) }
 
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.write_vectored(bufs)
+    }
+
     #[inline]
     fn flush(&mut self) -> IoResult<()> { self.flush() }
 }
@@ -1576,6 +1626,19 @@ pub trait Seek {
     fn seek(&mut self, pos: i64, style: SeekStyle) -> IoResult<()>;
 }
 
+/// An object which is backed by an OS-level file descriptor.
+///
+/// On windows the returned value is the underlying socket cast to a `c_int`,
+/// or the C runtime's descriptor for a file. Pipes don't implement this there,
+/// as their `HANDLE`s can't be represented as a `c_int`.
+pub trait AsRawFd {
+    /// Returns the underlying file descriptor of this object.
+    ///
+    /// The descriptor is still owned by this object, and it will be closed
+    /// when this object is dropped.
+    fn as_raw_fd(&self) -> libc::c_int;
+}
+
 /// A listener is a value that can consume itself to start listening for
 /// connections.
 ///
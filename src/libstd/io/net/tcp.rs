@@ -18,16 +18,21 @@
 //! listener (socket server) implements the `Listener` and `Acceptor` traits.
 
 use clone::Clone;
+use cmp;
 use io::IoResult;
+use io::fs::File;
+use io;
 use iter::Iterator;
-use slice::ImmutableVector;
+use slice::{ImmutableVector, MutableVector};
 use result::{Ok,Err};
 use io::net::addrinfo::get_host_addresses;
 use io::net::ip::SocketAddr;
 use io::{IoError, ConnectionFailed, InvalidInput};
-use io::{Reader, Writer, Listener, Acceptor};
+use io::{Reader, Writer, Listener, Acceptor, AsRawFd};
+use io::poll::Pollable;
 use from_str::FromStr;
 use kinds::Send;
+use libc;
 use option::{None, Some, Option};
 use owned::Box;
 use rt::rtio::{IoFactory, LocalIo, RtioSocket, RtioTcpListener};
@@ -90,6 +95,18 @@ impl TcpStream {
         Err(err)
     }
 
+    /// Creates a `TcpStream` from an already connected socket, taking
+    /// ownership of the file descriptor.
+    ///
+    /// The descriptor is closed when the returned stream is dropped, and also
+    /// if an error is returned. An error is returned if `fd` is not a stream
+    /// socket. This is not yet supported by the green runtime.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<TcpStream> {
+        LocalIo::maybe_raise(|io| {
+            io.tcp_from_raw_fd(fd).map(TcpStream::new)
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Creates a TCP connection to a remote socket address, timing out after
     /// the specified number of milliseconds.
     ///
@@ -196,6 +213,56 @@ impl TcpStream {
         self.obj.close_write().map_err(IoError::from_rtio_error)
     }
 
+    /// Sends `len` bytes of `file`, starting `offset` bytes from its start, to
+    /// the other end of this stream, returning the number of bytes sent. Fewer
+    /// bytes are only sent if the end of the file is reached first. The
+    /// position of `file` is left untouched.
+    ///
+    /// Where the platform supports it, the data is handed from the file to
+    /// the socket by the `sendfile` system call without being copied through
+    /// this process. Otherwise, including under the green runtime and while a
+    /// write timeout is set, the file is read and written out in chunks in
+    /// the same way as `io::util::copy`.
+    ///
+    /// # Errors
+    ///
+    /// As with `write`, if an error is returned then it is unknown how much of
+    /// the data (if any) was sent.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #![allow(unused_must_use)]
+    /// use std::io::{File, TcpStream};
+    ///
+    /// let mut file = File::open(&Path::new("index.html")).unwrap();
+    /// let size = file.stat().unwrap().size;
+    /// let mut stream = TcpStream::connect("127.0.0.1", 34254).unwrap();
+    /// stream.send_file(&mut file, 0, size);
+    /// ```
+    pub fn send_file(&mut self, file: &mut File, offset: u64,
+                     len: u64) -> IoResult<u64> {
+        match self.obj.sendfile(file.as_raw_fd(), offset, len) {
+            Ok(Some(sent)) => return Ok(sent),
+            Ok(None) => {}
+            Err(e) => return Err(IoError::from_rtio_error(e)),
+        }
+
+        let mut buf = [0, ..io::DEFAULT_BUF_SIZE];
+        let mut sent = 0;
+        while sent < len {
+            let amt = cmp::min(len - sent, io::DEFAULT_BUF_SIZE as u64) as uint;
+            let n = match file.read_at(buf.mut_slice_to(amt), offset + sent) {
+                Ok(n) => n,
+                Err(ref e) if e.kind == io::EndOfFile => break,
+                Err(e) => return Err(e),
+            };
+            try!(self.write(buf.slice_to(n)));
+            sent += n as u64;
+        }
+        Ok(sent)
+    }
+
     /// Sets a timeout, in milliseconds, for blocking operations on this stream.
     ///
     /// This function will set a timeout for all blocking operations (including
@@ -263,6 +330,14 @@ impl TcpStream {
     }
 }
 
+impl Pollable for TcpStream {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Clone for TcpStream {
     /// Creates a new handle to this TCP stream, allowing for simultaneous reads
     /// and writes of this connection.
@@ -281,12 +356,20 @@ impl Reader for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         self.obj.read(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.obj.readv(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 impl Writer for TcpStream {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.obj.write(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.obj.writev(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 /// A structure representing a socket server. This listener is used to create a
@@ -358,6 +441,20 @@ impl TcpListener {
         }
     }
 
+    /// Creates a `TcpListener` from a socket which has already been bound,
+    /// taking ownership of the file descriptor.
+    ///
+    /// This is intended for sockets handed down by a supervising process. The
+    /// socket may or may not already be listening, `listen` must be called on
+    /// the returned listener either way. The descriptor is closed when the
+    /// listener is dropped, and also if an error is returned. This is not yet
+    /// supported by the green runtime.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<TcpListener> {
+        LocalIo::maybe_raise(|io| {
+            io.tcp_listener_from_raw_fd(fd).map(|l| TcpListener { obj: l })
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Returns the local socket address of this listener.
     pub fn socket_name(&mut self) -> IoResult<SocketAddr> {
         match self.obj.socket_name() {
@@ -369,6 +466,14 @@ impl TcpListener {
     }
 }
 
+impl Pollable for TcpListener {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Listener<TcpStream, TcpAcceptor> for TcpListener {
     fn listen(self) -> IoResult<TcpAcceptor> {
         match self.obj.listen() {
@@ -433,6 +538,14 @@ impl TcpAcceptor {
     pub fn set_timeout(&mut self, ms: Option<u64>) { self.obj.set_timeout(ms); }
 }
 
+impl Pollable for TcpAcceptor {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl AsRawFd for TcpAcceptor {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Acceptor<TcpStream> for TcpAcceptor {
     fn accept(&mut self) -> IoResult<TcpStream> {
         match self.obj.accept(){
@@ -1400,4 +1513,57 @@ mod test {
         rxdone.recv();
         rxdone.recv();
     })
+
+    iotest!(fn vectored_io() {
+        let addr = next_test_ip4();
+        let ip_str = addr.ip.to_str();
+        let port = addr.port;
+        let mut acceptor = TcpListener::bind(ip_str.as_slice(), port).listen()
+                                       .unwrap();
+        spawn(proc() {
+            let mut s = TcpStream::connect(ip_str.as_slice(), port).unwrap();
+            s.write_vectored([b"hello", b"", b" world"]).unwrap();
+            // with a timeout the buffers may need to outlive the write
+            s.set_write_timeout(Some(1000));
+            s.write_vectored([b"!", b"!"]).unwrap();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut a = [0u8, ..5];
+        let mut b = [0u8, ..16];
+        let n = s.read_vectored([a.as_mut_slice(), b.as_mut_slice()]).unwrap();
+        let mut got = Vec::from_slice(a.as_slice()).append(b.as_slice());
+        got.truncate(n);
+        got.push_all(s.read_to_end().unwrap().as_slice());
+        assert_eq!(got.as_slice(), b"hello world!!");
+    })
+
+    iotest!(fn send_file() {
+        use io::TempDir;
+
+        let tmpdir = TempDir::new("send_file").unwrap();
+        let path = tmpdir.path().join("data");
+        let mut file = File::create(&path).unwrap();
+        file.write(b"hello world").unwrap();
+        let mut file = File::open(&path).unwrap();
+
+        let addr = next_test_ip4();
+        let ip_str = addr.ip.to_str();
+        let port = addr.port;
+        let mut acceptor = TcpListener::bind(ip_str.as_slice(), port).listen()
+                                       .unwrap();
+        spawn(proc() {
+            let mut s = TcpStream::connect(ip_str.as_slice(), port).unwrap();
+            assert_eq!(s.send_file(&mut file, 6, 5).unwrap(), 5);
+            assert_eq!(s.send_file(&mut file, 5, 100).unwrap(), 6);
+            // with a write timeout the file is copied instead
+            s.set_write_timeout(Some(1000));
+            assert_eq!(s.send_file(&mut file, 0, 5).unwrap(), 5);
+            assert_eq!(s.send_file(&mut file, 20, 5).unwrap(), 0);
+            assert_eq!(file.tell().unwrap(), 0);
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        assert_eq!(s.read_to_end().unwrap(), b"world worldhello".to_vec());
+    })
 }
@@ -17,8 +17,10 @@
 
 use clone::Clone;
 use io::net::ip::{SocketAddr, IpAddr};
-use io::{Reader, Writer, IoResult, IoError};
+use io::{Reader, Writer, IoResult, IoError, AsRawFd};
+use io::poll::Pollable;
 use kinds::Send;
+use libc;
 use owned::Box;
 use option::Option;
 use result::{Ok, Err};
@@ -70,6 +72,18 @@ impl UdpSocket {
         }).map_err(IoError::from_rtio_error)
     }
 
+    /// Creates a `UdpSocket` from an already bound datagram socket, taking
+    /// ownership of the file descriptor.
+    ///
+    /// The descriptor is closed when the returned socket is dropped, and also
+    /// if an error is returned. An error is returned if `fd` is not a datagram
+    /// socket. This is not yet supported by the green runtime.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<UdpSocket> {
+        LocalIo::maybe_raise(|io| {
+            io.udp_from_raw_fd(fd).map(|s| UdpSocket { obj: s })
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Receives data from the socket. On success, returns the number of bytes
     /// read and the address from whence the data came.
     pub fn recv_from(&mut self, buf: &mut [u8])
@@ -204,6 +218,14 @@ impl UdpSocket {
     }
 }
 
+impl Pollable for UdpSocket {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl AsRawFd for UdpSocket {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Clone for UdpSocket {
     /// Creates a new handle to this UDP socket, allowing for simultaneous
     /// reads and writes of the socket.
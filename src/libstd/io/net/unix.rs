@@ -28,11 +28,15 @@ use prelude::*;
 
 use c_str::ToCStr;
 use clone::Clone;
-use io::{Listener, Acceptor, Reader, Writer, IoResult, IoError};
+use io::{Listener, Acceptor, Reader, Writer, IoResult, IoError, AsRawFd};
+use io::poll::Pollable;
 use kinds::Send;
+use libc;
 use owned::Box;
+use path::Path;
 use rt::rtio::{IoFactory, LocalIo, RtioUnixListener};
-use rt::rtio::{RtioUnixAcceptor, RtioPipe};
+use rt::rtio::{RtioUnixAcceptor, RtioPipe, RtioUnixDatagram};
+use rt::rtio;
 
 /// A stream which communicates over a named pipe.
 pub struct UnixStream {
@@ -61,6 +65,17 @@ impl UnixStream {
         }).map_err(IoError::from_rtio_error)
     }
 
+    /// Creates a `UnixStream` from an already connected unix domain socket,
+    /// taking ownership of the file descriptor.
+    ///
+    /// The descriptor is closed when the returned stream is dropped, and also
+    /// if an error is returned. This is not supported on windows.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<UnixStream> {
+        LocalIo::maybe_raise(|io| {
+            io.unix_from_raw_fd(fd).map(|p| UnixStream { obj: p })
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Connect to a pipe named by `path`, timing out if the specified number of
     /// milliseconds.
     ///
@@ -76,6 +91,64 @@ impl UnixStream {
     }
 
 
+    /// Writes all of `buf` to the stream, passing the file descriptors `fds`
+    /// to the process at the other end along with it.
+    ///
+    /// The receiver gets its own copies of the descriptors, which stay open
+    /// even once they're closed here, and which it can pick up with
+    /// `recv_with_fds`. At least one byte of data must be sent along with any
+    /// descriptors.
+    ///
+    /// This is only supported by the native runtime, and not on windows.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #![allow(unused_must_use)]
+    /// use std::io::File;
+    /// use std::io::AsRawFd;
+    /// use std::io::net::unix::UnixStream;
+    ///
+    /// let file = File::open(&Path::new("/etc/shadow")).unwrap();
+    /// let server = Path::new("path/to/my/socket");
+    /// let mut stream = UnixStream::connect(&server).unwrap();
+    /// stream.send_with_fds(b"shadow", [file.as_raw_fd()]);
+    /// ```
+    pub fn send_with_fds(&mut self, buf: &[u8],
+                         fds: &[libc::c_int]) -> IoResult<()> {
+        self.obj.send_with_fds(buf, fds).map_err(IoError::from_rtio_error)
+    }
+
+    /// Reads data from the stream into `buf`, as `read` does, storing any file
+    /// descriptors sent along with it at the front of `fds`.
+    ///
+    /// Returns the number of bytes read, and the number of file descriptors
+    /// received. The descriptors are owned by the caller, who is responsible
+    /// for closing them, for example by adopting them with
+    /// `File::from_raw_fd`. Any which don't fit in `fds` are closed. Received
+    /// descriptors are not inherited by child processes.
+    ///
+    /// This is only supported by the native runtime, and not on windows.
+    pub fn recv_with_fds(&mut self, buf: &mut [u8],
+                         fds: &mut [libc::c_int]) -> IoResult<(uint, uint)> {
+        self.obj.recv_with_fds(buf, fds).map_err(IoError::from_rtio_error)
+    }
+
+    /// Returns the credentials of the process at the other end of this
+    /// connection, as they were when the connection was established.
+    ///
+    /// The process id is only available on linux and android.
+    ///
+    /// This is only supported by the native runtime, and not on windows.
+    pub fn peer_credentials(&mut self) -> IoResult<PeerCredentials> {
+        match self.obj.peer_credentials() {
+            Ok(rtio::PeerCredentials { pid, uid, gid }) => {
+                Ok(PeerCredentials { pid: pid, uid: uid, gid: gid })
+            }
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        }
+    }
+
     /// Closes the reading half of this connection.
     ///
     /// This method will close the reading portion of this connection, causing
@@ -123,6 +196,16 @@ impl UnixStream {
     }
 }
 
+#[cfg(unix)]
+impl Pollable for UnixStream {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+#[cfg(unix)]
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Clone for UnixStream {
     fn clone(&self) -> UnixStream {
         UnixStream { obj: self.obj.clone() }
@@ -133,12 +216,135 @@ impl Reader for UnixStream {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         self.obj.read(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.obj.readv(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 impl Writer for UnixStream {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.obj.write(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.obj.writev(bufs).map_err(IoError::from_rtio_error)
+    }
+}
+
+/// The identity of the process at the other end of a `UnixStream`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct PeerCredentials {
+    /// The id of the process, where the platform provides it.
+    pub pid: Option<libc::pid_t>,
+    /// The effective user id of the process.
+    pub uid: u64,
+    /// The effective group id of the process.
+    pub gid: u64,
+}
+
+/// A unix domain socket which sends and receives individual datagrams.
+///
+/// Each datagram is delivered whole, and in order, to the socket bound to the
+/// path it was sent to. This is only supported by the native runtime, and not
+/// on windows.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![allow(unused_must_use)]
+/// use std::io::net::unix::UnixDatagram;
+///
+/// let mut socket = UnixDatagram::bind(&Path::new("path/to/my/socket")).unwrap();
+/// let mut buf = [0, ..128];
+/// match socket.recv_from(buf) {
+///     Ok((amt, Some(src))) => {
+///         socket.send_to(buf.slice_to(amt), &src);
+///     }
+///     Ok((_, None)) => println!("the sender has no address to reply to"),
+///     Err(e) => println!("couldn't receive a datagram: {}", e),
+/// }
+/// ```
+pub struct UnixDatagram {
+    obj: Box<RtioUnixDatagram + Send>,
+}
+
+impl UnixDatagram {
+    /// Creates a datagram socket bound to `path`, which other sockets can
+    /// then send datagrams to. The path is removed when the socket is closed.
+    pub fn bind<P: ToCStr>(path: &P) -> IoResult<UnixDatagram> {
+        LocalIo::maybe_raise(|io| {
+            io.unix_datagram_bind(Some(&path.to_c_str())).map(|s| {
+                UnixDatagram { obj: s }
+            })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Creates a datagram socket which isn't bound to a path. It can send
+    /// datagrams, but as it has no address nothing can be sent to it.
+    pub fn unbound() -> IoResult<UnixDatagram> {
+        LocalIo::maybe_raise(|io| {
+            io.unix_datagram_bind(None).map(|s| UnixDatagram { obj: s })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Receives a single datagram into `buf`, returning its length and the
+    /// path of the socket which sent it, if it has one. Any part of the
+    /// datagram which doesn't fit in `buf` is discarded.
+    pub fn recv_from(&mut self, buf: &mut [u8])
+                     -> IoResult<(uint, Option<Path>)> {
+        match self.obj.recv_from(buf) {
+            Ok((amt, src)) => {
+                Ok((amt, src.map(|src| Path::new(src.as_bytes_no_nul()))))
+            }
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        }
+    }
+
+    /// Sends all of `buf` as a single datagram to the socket bound to `dst`.
+    pub fn send_to<P: ToCStr>(&mut self, buf: &[u8], dst: &P) -> IoResult<()> {
+        self.obj.send_to(buf, &dst.to_c_str()).map_err(IoError::from_rtio_error)
+    }
+
+    /// Sets the read/write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_timeout(timeout_ms)
+    }
+
+    /// Sets the read timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+}
+
+impl Pollable for UnixDatagram {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl Clone for UnixDatagram {
+    /// Creates a new handle to this socket, allowing for simultaneous reads
+    /// and writes of it.
+    fn clone(&self) -> UnixDatagram {
+        UnixDatagram { obj: self.obj.clone() }
+    }
 }
 
 /// A value that can listen for incoming named pipe connection requests.
@@ -175,6 +381,24 @@ impl UnixListener {
             io.unix_bind(&path.to_c_str()).map(|s| UnixListener { obj: s })
         }).map_err(IoError::from_rtio_error)
     }
+
+    /// Creates a `UnixListener` from a unix domain socket which has already
+    /// been bound, taking ownership of the file descriptor.
+    ///
+    /// Unlike a listener created with `bind`, the socket's path is not removed
+    /// from the filesystem when the listener is dropped. The descriptor is
+    /// closed when the listener is dropped, and also if an error is returned.
+    /// This is not supported on windows.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<UnixListener> {
+        LocalIo::maybe_raise(|io| {
+            io.unix_listener_from_raw_fd(fd).map(|s| UnixListener { obj: s })
+        }).map_err(IoError::from_rtio_error)
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
 }
 
 impl Listener<UnixStream, UnixAcceptor> for UnixListener {
@@ -210,6 +434,11 @@ impl UnixAcceptor {
     }
 }
 
+#[cfg(unix)]
+impl AsRawFd for UnixAcceptor {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Acceptor<UnixStream> for UnixAcceptor {
     fn accept(&mut self) -> IoResult<UnixStream> {
         self.obj.accept().map(|s| {
@@ -686,4 +915,52 @@ mod tests {
 
         rx2.recv();
     })
+
+    iotest!(fn vectored_io() {
+        let addr = next_test_unix();
+        let mut acceptor = UnixListener::bind(&addr).listen().unwrap();
+        spawn(proc() {
+            let mut s = UnixStream::connect(&addr).unwrap();
+            s.write_vectored([b"hello", b"", b" world"]).unwrap();
+            // with a timeout the buffers may need to outlive the write
+            s.set_write_timeout(Some(1000));
+            s.write_vectored([b"!", b"!"]).unwrap();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut a = [0u8, ..5];
+        let mut b = [0u8, ..16];
+        let n = s.read_vectored([a.as_mut_slice(), b.as_mut_slice()]).unwrap();
+        let mut got = Vec::from_slice(a.as_slice()).append(b.as_slice());
+        got.truncate(n);
+        got.push_all(s.read_to_end().unwrap().as_slice());
+        assert_eq!(got.as_slice(), b"hello world!!");
+    })
+
+    #[cfg(unix)]
+    iotest!(fn from_raw_fd() {
+        use libc;
+
+        let addr = next_test_unix();
+        // the original listener owns the path, so it's kept alive until the
+        // end of the test
+        let original = UnixListener::bind(&addr).unwrap();
+        let fd = unsafe { libc::dup(original.as_raw_fd()) };
+        assert!(fd >= 0);
+        let mut acceptor = UnixListener::from_raw_fd(fd).unwrap()
+                                        .listen().unwrap();
+        assert_eq!(acceptor.as_raw_fd(), fd);
+
+        spawn(proc() {
+            let mut s = UnixStream::connect(&addr).unwrap();
+            s.write([1, 2]).unwrap();
+        });
+
+        let s = acceptor.accept().unwrap();
+        let fd = unsafe { libc::dup(s.as_raw_fd()) };
+        drop(s);
+        let mut s = UnixStream::from_raw_fd(fd).unwrap();
+        assert_eq!(s.read_to_end().unwrap(), vec![1, 2]);
+        drop(original);
+    })
 }
@@ -18,6 +18,8 @@
 use prelude::*;
 
 use io::{IoResult, IoError};
+#[cfg(unix)] use io::AsRawFd;
+#[cfg(unix)] use io::poll::Pollable;
 use libc;
 use os;
 use owned::Box;
@@ -61,6 +63,14 @@ impl PipeStream {
         }).map_err(IoError::from_rtio_error)
     }
 
+    /// Consumes a file descriptor to return a pipe stream.
+    ///
+    /// This is the same as `PipeStream::open`, and is provided for symmetry
+    /// with the other types implementing `AsRawFd`.
+    pub fn from_raw_fd(fd: libc::c_int) -> IoResult<PipeStream> {
+        PipeStream::open(fd)
+    }
+
     #[doc(hidden)]
     pub fn new(inner: Box<RtioPipe + Send>) -> PipeStream {
         PipeStream { obj: inner }
@@ -99,6 +109,16 @@ impl PipeStream {
     }
 }
 
+#[cfg(unix)]
+impl Pollable for PipeStream {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+#[cfg(unix)]
+impl AsRawFd for PipeStream {
+    fn as_raw_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
 impl Clone for PipeStream {
     fn clone(&self) -> PipeStream {
         PipeStream { obj: self.obj.clone() }
@@ -109,12 +129,20 @@ impl Reader for PipeStream {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         self.obj.read(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.obj.readv(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 impl Writer for PipeStream {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.obj.write(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.obj.writev(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,240 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+
+Readiness-based multiplexing of I/O objects
+
+A `Poller` watches any number of sockets and pipes at once and reports which of
+them are ready to be read from or written to, so a single task can serve many
+connections without blocking on any one of them.
+
+Each object is registered along with a token of the caller's choosing, which is
+handed back in the events for that object. Readiness is level-triggered: an
+object which is still ready (for example because not all of the available data
+was read) is reported again by the next call to `poll`.
+
+This is implemented with epoll by the native runtime, which currently only
+supports it on linux and android, and with poll handles by the green runtime.
+The green runtime can't register objects on windows, where their sockets
+belong to its event loop.
+
+# Example
+
+```rust,no_run
+# #![allow(unused_must_use)]
+use std::io::{TcpListener, Listener, Acceptor};
+use std::io::poll::{Poller, Readable};
+
+let mut acceptor = TcpListener::bind("127.0.0.1", 8080).listen().unwrap();
+let mut poller = Poller::new().unwrap();
+poller.register(&acceptor, 0, Readable);
+
+loop {
+    for event in poller.poll(None).unwrap().iter() {
+        if event.token == 0 && event.readiness.contains(Readable) {
+            let stream = acceptor.accept();
+            // register the new stream with its own token
+        }
+    }
+}
+```
+
+*/
+
+use io::{IoResult, IoError};
+use iter::Iterator;
+use kinds::Send;
+use libc::c_int;
+use option::{Option, Some, None};
+use owned::Box;
+use result::{Ok, Err};
+use rt::rtio::{IoFactory, LocalIo, RtioPoller};
+use rt::rtio;
+use vec::Vec;
+
+bitflags!(
+    #[doc="The readiness of an I/O object, or the readiness a `Poller` is
+interested in for an object."]
+    #[deriving(Show)]
+    flags Readiness: u32 {
+        #[doc="The object can be read from, or accepted from for an
+acceptor, without blocking."]
+        static Readable = 0x1,
+        #[doc="The object can be written to without blocking."]
+        static Writable = 0x2,
+        #[doc="The peer hung up or an error is pending on the object. This is
+always reported, whatever the interest the object was registered with."]
+        static Hangup   = 0x4
+    }
+)
+
+/// An I/O object which can be registered with a `Poller`.
+pub trait Pollable {
+    /// The underlying file descriptor watched by the poller.
+    #[doc(hidden)]
+    fn poll_fd(&self) -> c_int;
+}
+
+/// The readiness of one registered object, as returned by `Poller::poll`.
+#[deriving(Show)]
+pub struct Event {
+    /// The token the object was registered with.
+    pub token: uint,
+    /// What the object is ready for.
+    pub readiness: Readiness,
+}
+
+/// Watches registered I/O objects for readiness.
+pub struct Poller {
+    obj: Box<RtioPoller + Send>,
+}
+
+impl Poller {
+    /// Creates a new poller with no objects registered.
+    pub fn new() -> IoResult<Poller> {
+        LocalIo::maybe_raise(|io| {
+            io.poller_init().map(|p| Poller { obj: p })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Starts watching `handle`, reporting its events with `token`.
+    ///
+    /// It is an error to register an object which is already registered, use
+    /// `reregister` to change its token or interest instead.
+    pub fn register<P: Pollable>(&mut self, handle: &P, token: uint,
+                                 interest: Readiness) -> IoResult<()> {
+        self.obj.register(handle.poll_fd(), token,
+                          interest.contains(Readable),
+                          interest.contains(Writable))
+            .map_err(IoError::from_rtio_error)
+    }
+
+    /// Changes the token and interest of an already registered object.
+    pub fn reregister<P: Pollable>(&mut self, handle: &P, token: uint,
+                                   interest: Readiness) -> IoResult<()> {
+        self.obj.reregister(handle.poll_fd(), token,
+                            interest.contains(Readable),
+                            interest.contains(Writable))
+            .map_err(IoError::from_rtio_error)
+    }
+
+    /// Stops watching `handle`.
+    ///
+    /// Objects must be deregistered before they are closed, as the poller
+    /// may otherwise keep watching a descriptor which is reused by a later
+    /// object.
+    pub fn deregister<P: Pollable>(&mut self, handle: &P) -> IoResult<()> {
+        self.obj.deregister(handle.poll_fd()).map_err(IoError::from_rtio_error)
+    }
+
+    /// Blocks the current task until at least one registered object is ready,
+    /// or `timeout_ms` milliseconds have passed, and returns the ready
+    /// objects. A timeout of `None` waits forever, and a timeout of `Some(0)`
+    /// returns immediately.
+    ///
+    /// An empty list is returned if the timeout expired.
+    pub fn poll(&mut self, timeout_ms: Option<u64>) -> IoResult<Vec<Event>> {
+        let mut events = Vec::new();
+        try!(self.obj.poll(&mut events, timeout_ms).map_err(IoError::from_rtio_error));
+        Ok(events.move_iter().map(|e: rtio::PollEvent| {
+            let mut readiness = Readiness::empty();
+            if e.readable { readiness.insert(Readable) }
+            if e.writable { readiness.insert(Writable) }
+            if e.hangup { readiness.insert(Hangup) }
+            Event { token: e.token, readiness: readiness }
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    iotest!(fn poll_timeout() {
+        use io::poll::Poller;
+
+        let mut poller = Poller::new().unwrap();
+        assert_eq!(poller.poll(Some(0)).unwrap().len(), 0);
+        assert_eq!(poller.poll(Some(10)).unwrap().len(), 0);
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+
+    iotest!(fn poll_readable() {
+        use io::{TcpListener, TcpStream, Listener, Acceptor};
+        use io::poll::{Poller, Readable, Writable};
+        use io::test::next_test_ip4;
+
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr.ip.to_str().as_slice(),
+                                             addr.port).listen().unwrap();
+        let mut poller = Poller::new().unwrap();
+        poller.register(&acceptor, 1, Readable).unwrap();
+
+        let (tx, rx) = channel();
+        spawn(proc() {
+            let mut stream = TcpStream::connect(addr.ip.to_str().as_slice(),
+                                                addr.port).unwrap();
+            rx.recv();
+            stream.write([1]).unwrap();
+            rx.recv();
+        });
+
+        let events = poller.poll(None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.get(0).token, 1);
+        assert!(events.get(0).readiness.contains(Readable));
+
+        poller.deregister(&acceptor).unwrap();
+        let mut stream = acceptor.accept().unwrap();
+        poller.register(&stream, 2, Readable | Writable).unwrap();
+
+        // Writable straight away, but nothing to read yet.
+        let events = poller.poll(None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events.get(0).readiness.contains(Writable));
+        assert!(!events.get(0).readiness.contains(Readable));
+
+        poller.reregister(&stream, 3, Readable).unwrap();
+        tx.send(());
+        let events = poller.poll(None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.get(0).token, 3);
+        assert!(events.get(0).readiness.contains(Readable));
+
+        let mut buf = [0];
+        assert_eq!(stream.read(buf), Ok(1));
+        assert_eq!(buf[0], 1);
+        poller.deregister(&stream).unwrap();
+        tx.send(());
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+
+    #[cfg(unix)]
+    iotest!(fn poll_pipe() {
+        use io::PipeStream;
+        use io::poll::{Poller, Readable};
+
+        let mut pipe = PipeStream::pair().unwrap();
+        let mut poller = Poller::new().unwrap();
+        poller.register(&pipe.reader, 7, Readable).unwrap();
+        assert_eq!(poller.poll(Some(0)).unwrap().len(), 0);
+
+        pipe.writer.write([1]).unwrap();
+        let events = poller.poll(None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.get(0).token, 7);
+        assert!(events.get(0).readiness.contains(Readable));
+
+        // The pipe is still usable while registered, and stops being ready
+        // once it has been read from.
+        let mut buf = [0];
+        assert_eq!(pipe.reader.read(buf), Ok(1));
+        assert_eq!(buf[0], 1);
+        assert_eq!(poller.poll(Some(10)).unwrap().len(), 0);
+        poller.deregister(&pipe.reader).unwrap();
+    } #[ignore(cfg(not(target_os = "linux"), not(target_os = "android")))])
+}
@@ -60,6 +60,7 @@ use c_str::CString;
 pub struct Process {
     handle: Box<RtioProcess + Send>,
     forget: bool,
+    pgid: Option<libc::pid_t>,
 
     /// Handle to the child's stdin, if the `stdin` field of this process's
     /// `ProcessConfig` was `CreatePipe`. By default, this handle is `Some`.
@@ -76,6 +77,11 @@ pub struct Process {
     /// Extra I/O handles as configured by the original `ProcessConfig` when
     /// this process was created. This is by default empty.
     pub extra_io: Vec<Option<io::PipeStream>>,
+
+    /// The master end of the pseudo-terminal the child is attached to, if any
+    /// of its stdio streams were configured as `Pty`. The handles of those
+    /// streams are `None`, as everything goes through the terminal.
+    pub pty: Option<io::pty::PtyStream>,
 }
 
 /// The `Command` type acts as a process builder, providing fine-grained control
@@ -108,6 +114,8 @@ pub struct Command {
     uid: Option<uint>,
     gid: Option<uint>,
     detach: bool,
+    process_group: Option<libc::pid_t>,
+    rlimits: Vec<(Resource, u64, u64)>,
 }
 
 // FIXME (#12938): Until DST lands, we cannot decompose &str into & and str, so
@@ -142,6 +150,8 @@ impl Command {
             uid: None,
             gid: None,
             detach: false,
+            process_group: None,
+            rlimits: Vec::new(),
         }
     }
 
@@ -232,6 +242,32 @@ impl Command {
         self
     }
 
+    /// Moves the child process into the process group `id`, or into a new
+    /// group which it leads if `id` is 0, so that the whole group can be
+    /// signalled with `Process::signal_group`. Note that a detached child is
+    /// already the leader of a group of its own.
+    ///
+    /// Setting this on windows, or under the green runtime, will cause the
+    /// spawn to fail.
+    pub fn process_group<'a>(&'a mut self, id: libc::pid_t) -> &'a mut Command {
+        self.process_group = Some(id);
+        self
+    }
+
+    /// Limits the child process's consumption of `resource`. The soft limit is
+    /// the one which is enforced, and the child may raise it up to the hard
+    /// limit. A limit of `u64::MAX` stands for no limit.
+    ///
+    /// The limits are applied before the user and group ids of the child are
+    /// changed, so a privileged parent may raise hard limits for an
+    /// unprivileged child. Setting any limit on windows, or under the green
+    /// runtime, will cause the spawn to fail.
+    pub fn rlimit<'a>(&'a mut self, resource: Resource, soft: u64,
+                      hard: u64) -> &'a mut Command {
+        self.rlimits.push((resource, soft, hard));
+        self
+    }
+
     /// Executes the command as a child process, which is returned.
     pub fn spawn(&self) -> IoResult<Process> {
         fn to_rtio(p: StdioContainer) -> rtio::StdioContainer {
@@ -239,10 +275,27 @@ impl Command {
                 Ignored => rtio::Ignored,
                 InheritFd(fd) => rtio::InheritFd(fd),
                 CreatePipe(a, b) => rtio::CreatePipe(a, b),
+                Pty => rtio::CreatePty,
+            }
+        }
+        fn to_rtio_resource(r: Resource) -> rtio::RlimitResource {
+            match r {
+                CpuTime => rtio::RlimitCpuTime,
+                FileSize => rtio::RlimitFileSize,
+                DataSize => rtio::RlimitDataSize,
+                StackSize => rtio::RlimitStackSize,
+                CoreSize => rtio::RlimitCoreSize,
+                Processes => rtio::RlimitProcesses,
+                OpenFiles => rtio::RlimitOpenFiles,
+                AddressSpace => rtio::RlimitAddressSpace,
             }
         }
         let extra_io: Vec<rtio::StdioContainer> =
             self.extra_io.iter().map(|x| to_rtio(*x)).collect();
+        let rlimits: Vec<(rtio::RlimitResource, u64, u64)> =
+            self.rlimits.iter().map(|&(r, soft, hard)| {
+                (to_rtio_resource(r), soft, hard)
+            }).collect();
         LocalIo::maybe_raise(|io| {
             let cfg = ProcessConfig {
                 program: &self.program,
@@ -256,18 +309,30 @@ impl Command {
                 uid: self.uid,
                 gid: self.gid,
                 detach: self.detach,
+                process_group: self.process_group,
+                rlimits: rlimits.as_slice(),
             };
-            io.spawn(cfg).map(|(p, io)| {
+            io.spawn(cfg).map(|(p, io, pty)| {
                 let mut io = io.move_iter().map(|p| {
                     p.map(|p| io::PipeStream::new(p))
                 });
+                // Children which lead a session lead a process group as well
+                let new_session = cfg!(unix) && (self.detach || pty.is_some());
+                let pgid = match self.process_group {
+                    Some(0) => Some(p.id()),
+                    Some(id) => Some(id),
+                    None if new_session => Some(p.id()),
+                    None => None,
+                };
                 Process {
                     handle: p,
                     forget: false,
+                    pgid: pgid,
                     stdin: io.next().unwrap(),
                     stdout: io.next().unwrap(),
                     stderr: io.next().unwrap(),
                     extra_io: io.collect(),
+                    pty: pty.map(|p| io::pty::PtyStream::new(p)),
                 }
             })
         }).map_err(IoError::from_rtio_error)
@@ -356,6 +421,38 @@ pub enum StdioContainer {
     /// second is whether it is writable. These properties are from the view of
     /// the *child* process, not the parent process.
     CreatePipe(bool /* readable */, bool /* writable */),
+
+    /// Attaches the stream to a pseudo-terminal, which the child sees as its
+    /// controlling terminal. All of the streams configured this way share one
+    /// terminal, the master end of which is the `pty` field of the spawned
+    /// process.
+    ///
+    /// This is only supported on unix. The child is placed in a session of
+    /// its own, but under the green runtime the terminal doesn't become its
+    /// controlling terminal.
+    Pty,
+}
+
+/// A resource whose consumption by a child process can be limited with
+/// `Command::rlimit`.
+pub enum Resource {
+    /// The CPU time used, in seconds. The child is sent `SIGXCPU` when it
+    /// exceeds the soft limit.
+    CpuTime,
+    /// The size of the files the child may create, in bytes.
+    FileSize,
+    /// The size of the child's data segment, in bytes.
+    DataSize,
+    /// The size of the child's stack, in bytes.
+    StackSize,
+    /// The size of the core dump the child may leave, in bytes.
+    CoreSize,
+    /// The number of processes the child's user may have.
+    Processes,
+    /// One more than the highest file descriptor number the child may open.
+    OpenFiles,
+    /// The size of the child's virtual address space, in bytes.
+    AddressSpace,
 }
 
 /// Describes the result of a process after it has terminated.
@@ -432,6 +529,31 @@ impl Process {
         self.handle.kill(signal).map_err(IoError::from_rtio_error)
     }
 
+    /// Sends the specified signal to every process in the child's process
+    /// group. This is only possible for children which were put in a process
+    /// group with `Command::process_group`, or which lead a group of their
+    /// own because they were spawned detached.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the child isn't in a process group of its
+    /// own, or if the signal couldn't be delivered to any process of the
+    /// group.
+    pub fn signal_group(&mut self, signal: int) -> IoResult<()> {
+        let pgid = match self.pgid {
+            Some(pgid) => pgid,
+            None => return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "the process isn't in a process group of its own",
+                detail: None,
+            })
+        };
+        // a negative id sends the signal to the whole group
+        LocalIo::maybe_raise(|io| {
+            io.kill(-pgid, signal)
+        }).map_err(IoError::from_rtio_error)
+    }
+
     /// Sends a signal to this child requesting that it exits. This is
     /// equivalent to sending a SIGTERM on unix platforms.
     pub fn signal_exit(&mut self) -> IoResult<()> {
@@ -565,6 +687,7 @@ impl Drop for Process {
         drop(self.stdout.take());
         drop(self.stderr.take());
         drop(mem::replace(&mut self.extra_io, Vec::new()));
+        drop(self.pty.take());
 
         self.set_timeout(None);
         let _ = self.wait().unwrap();
@@ -658,12 +781,34 @@ mod tests {
         assert_eq!(out, "foobar\n".to_string());
     })
 
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn pty_works() {
+        let mut p = Command::new("/bin/sh")
+                            .arg("-c").arg("test -t 0 && test -t 1 && read line && echo $line")
+                            .stdin(Pty)
+                            .stdout(Pty)
+                            .spawn().unwrap();
+        assert!(p.stdin.is_none() && p.stdout.is_none());
+        p.pty.get_mut_ref().write("foobar\n".as_bytes()).unwrap();
+        // the input is echoed by the terminal before the child sees it
+        let out = read_all(p.pty.get_mut_ref() as &mut Reader);
+        assert!(p.wait().unwrap().success());
+        assert_eq!(out, "foobar\r\nfoobar\r\n".to_string());
+    })
+
     #[cfg(not(target_os="android"))]
     iotest!(fn detach_works() {
         let mut p = Command::new("true").detached().spawn().unwrap();
         assert!(p.wait().unwrap().success());
     })
 
+    #[cfg(not(target_os="android"))]
+    iotest!(fn signal_group_needs_group() {
+        let mut p = Command::new("true").spawn().unwrap();
+        assert!(p.signal_group(0).is_err());
+        assert!(p.wait().unwrap().success());
+    })
+
     #[cfg(windows)]
     iotest!(fn uid_fails_on_windows() {
         assert!(Command::new("test").uid(10).spawn().is_err());
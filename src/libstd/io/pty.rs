@@ -0,0 +1,129 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+
+Pseudo-terminals
+
+A pseudo-terminal is a pair of connected streams which behave like a terminal
+device: programs attached to the slave end see a TTY, with line editing, echo
+and a window size, while whatever is written to them can be read from the
+master end, and whatever is written to the master end is what they see as
+typed input.
+
+The easiest way to run a child process on a pseudo-terminal is to configure its
+stdio as `Pty` with `Command`, which makes the master end available as the
+`pty` field of the spawned `Process`. A standalone pseudo-terminal can be
+created with `open`.
+
+Pseudo-terminals are only supported on unix platforms.
+
+# Example
+
+```rust,no_run
+# #![allow(unused_must_use)]
+use std::io::pty;
+
+let (mut master, mut slave) = pty::open().unwrap();
+master.set_winsize(80, 24);
+master.write(b"hello\n");
+let line = slave.read_exact(6).unwrap();
+```
+
+*/
+
+use clone::Clone;
+use io::{IoResult, IoError, Reader, Writer};
+use io::poll::Pollable;
+use kinds::Send;
+use libc;
+use owned::Box;
+use rt::rtio::{IoFactory, LocalIo, RtioPty};
+
+/// One end of a pseudo-terminal.
+pub struct PtyStream {
+    obj: Box<RtioPty + Send>,
+}
+
+/// Opens a new pseudo-terminal, returning its master and slave ends, in that
+/// order.
+pub fn open() -> IoResult<(PtyStream, PtyStream)> {
+    LocalIo::maybe_raise(|io| {
+        io.pty_open().map(|(master, slave)| {
+            (PtyStream { obj: master }, PtyStream { obj: slave })
+        })
+    }).map_err(IoError::from_rtio_error)
+}
+
+impl PtyStream {
+    #[doc(hidden)]
+    pub fn new(inner: Box<RtioPty + Send>) -> PtyStream {
+        PtyStream { obj: inner }
+    }
+
+    /// Sets the window size of the terminal, in characters. Programs attached
+    /// to the terminal are sent `SIGWINCH` when it changes.
+    pub fn set_winsize(&mut self, width: int, height: int) -> IoResult<()> {
+        self.obj.set_winsize(width, height).map_err(IoError::from_rtio_error)
+    }
+
+    /// Returns the window size of the terminal, as `(width, height)` in
+    /// characters.
+    pub fn winsize(&mut self) -> IoResult<(int, int)> {
+        self.obj.get_winsize().map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Pollable for PtyStream {
+    fn poll_fd(&self) -> libc::c_int { self.obj.raw_fd() }
+}
+
+impl Clone for PtyStream {
+    fn clone(&self) -> PtyStream {
+        PtyStream { obj: self.obj.clone() }
+    }
+}
+
+impl Reader for PtyStream {
+    /// Reading the master end reports the end of the stream once every copy
+    /// of the slave end has been closed.
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        self.obj.read(buf).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Writer for PtyStream {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.obj.write(buf).map_err(IoError::from_rtio_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    iotest!(fn pty_smoke() {
+        use io::pty;
+
+        let (mut master, mut slave) = pty::open().unwrap();
+        master.write(b"hello\n").unwrap();
+        assert_eq!(slave.read_exact(6).unwrap(), b"hello\n".to_vec());
+
+        // the terminal echoes the input back to the master
+        assert_eq!(master.read_exact(7).unwrap(), b"hello\r\n".to_vec());
+    } #[ignore(cfg(windows))])
+
+    iotest!(fn pty_winsize() {
+        use io::pty;
+
+        let (mut master, mut slave) = pty::open().unwrap();
+        master.set_winsize(132, 43).unwrap();
+        assert_eq!(master.winsize().unwrap(), (132, 43));
+        assert_eq!(slave.winsize().unwrap(), (132, 43));
+    } #[ignore(cfg(windows))])
+}
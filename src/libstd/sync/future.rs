@@ -22,6 +22,24 @@
  * make_a_sandwich();
  * println!("fib(5000) = {}", delayed_fib.get())
  * ```
+ *
+ * Futures can be chained with `map` and `and_then`, and combined with
+ * `join_all` and `select_any`. If the computation behind a future fails, the
+ * future fails too, which `try_unwrap` and `try_get` report as an `Err`.
+ * Dropping a future cancels any computations which were only going to feed
+ * into it: chained functions are never called, and tasks started with
+ * `spawn_cancellable` can check their `Cancellation` to stop early.
+ *
+ * ```rust
+ * use std::sync::Future;
+ * # fn fetch(replica: uint) -> String { replica.to_str() }
+ * let requests = Vec::from_fn(3, |i| Future::spawn(proc() fetch(i)));
+ * let first = Future::select_any(requests).map(proc(first) {
+ *     let (_, reply) = first;
+ *     reply.len()
+ * });
+ * assert!(first.try_unwrap().is_ok());
+ * ```
  */
 
 #![allow(missing_doc)]
@@ -29,18 +47,75 @@
 use core::prelude::*;
 use core::mem::replace;
 
-use comm::{Receiver, channel};
+use comm::{Receiver, Sender, Select, Handle, channel, Disconnected, Empty};
+use owned::Box;
 use task::spawn;
+use vec::Vec;
 
 /// A type encapsulating the result of a computation which may not be complete
 pub struct Future<A> {
     state: FutureState<A>,
+    // Dropped along with the future, which lets the task computing its value
+    // know that the value is no longer wanted.
+    cancel: Option<Sender<()>>,
 }
 
 enum FutureState<A> {
-    Pending(proc():Send -> A),
+    Pending(proc():Send -> Result<A, FutureError>),
+    Waiting(Box<Source<A> + Send>),
     Evaluating,
-    Forced(A)
+    Forced(A),
+    Broken,
+}
+
+/// The reasons for which the value of a future may not be available.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum FutureError {
+    /// The value is still being computed by another task. This is only
+    /// returned by `try_get`.
+    NotReady,
+    /// The computation failed, or gave up, before producing a value.
+    Failed,
+}
+
+/// A handle given to computations started with `Future::spawn_cancellable`,
+/// through which they can find out whether their result is still wanted.
+pub struct Cancellation {
+    rx: Receiver<()>,
+}
+
+// The receiving end of a value which is being computed by another task. This
+// only exists so that futures of values which aren't `Send` can still hold a
+// `Receiver` in their type.
+trait Source<A> {
+    fn poll(&self) -> Option<Result<A, ()>>;
+    fn wait(&self) -> Result<A, ()>;
+    fn handle<'a>(&'a self, sel: &'a Select) -> Handle<'a, A>;
+}
+
+impl<A: Send> Source<A> for Receiver<A> {
+    fn poll(&self) -> Option<Result<A, ()>> {
+        match self.try_recv() {
+            Ok(v) => Some(Ok(v)),
+            Err(Empty) => None,
+            Err(Disconnected) => Some(Err(())),
+        }
+    }
+    fn wait(&self) -> Result<A, ()> { self.recv_opt() }
+    fn handle<'a>(&'a self, sel: &'a Select) -> Handle<'a, A> {
+        sel.handle(self)
+    }
+}
+
+impl Cancellation {
+    /// Returns whether the future for this computation has been dropped, in
+    /// which case its result will never be used.
+    pub fn is_cancelled(&self) -> bool {
+        match self.rx.try_recv() {
+            Err(Disconnected) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Methods on the `future` type
@@ -49,16 +124,47 @@ impl<A:Clone> Future<A> {
         //! Get the value of the future.
         (*(self.get_ref())).clone()
     }
+
+    /// Gets the value of the future if it is available, without blocking.
+    ///
+    /// Returns `Err(NotReady)` if the value is still being computed by another
+    /// task, or `Err(Failed)` if the computation failed. Futures created with
+    /// `from_fn` are only ever computed on demand, so this computes their value
+    /// instead.
+    pub fn try_get(&mut self) -> Result<A, FutureError> {
+        self.poll();
+        match self.state {
+            Forced(ref v) => Ok(v.clone()),
+            Broken => Err(Failed),
+            Waiting(..) => Err(NotReady),
+            Pending(..) | Evaluating => fail!("Logic error."),
+        }
+    }
 }
 
 impl<A> Future<A> {
     /// Gets the value from this future, forcing evaluation.
-    pub fn unwrap(mut self) -> A {
-        self.get_ref();
-        let state = replace(&mut self.state, Evaluating);
-        match state {
-            Forced(v) => v,
-            _ => fail!( "Logic error." ),
+    ///
+    /// # Failure
+    ///
+    /// Fails if the computation of the value failed. See `try_unwrap` for a
+    /// version of this which doesn't fail.
+    pub fn unwrap(self) -> A {
+        match self.try_unwrap() {
+            Ok(v) => v,
+            Err(..) => fail!("Future failed to produce a value"),
+        }
+    }
+
+    /// Gets the value from this future, forcing evaluation, or returns
+    /// `Err(Failed)` if the computation of the value failed. If the value was
+    /// being computed by another task, this is the case when that task failed.
+    pub fn try_unwrap(mut self) -> Result<A, FutureError> {
+        self.force();
+        match replace(&mut self.state, Evaluating) {
+            Forced(v) => Ok(v),
+            Broken => Err(Failed),
+            _ => fail!("Logic error."),
         }
     }
 
@@ -67,19 +173,14 @@ impl<A> Future<A> {
         * Executes the future's closure and then returns a reference
         * to the result.  The reference lasts as long as
         * the future.
+        *
+        * Fails if the computation of the value failed.
         */
+        self.force();
         match self.state {
-            Forced(ref v) => return v,
-            Evaluating => fail!("Recursive forcing of future!"),
-            Pending(_) => {
-                match replace(&mut self.state, Evaluating) {
-                    Forced(_) | Evaluating => fail!("Logic error."),
-                    Pending(f) => {
-                        self.state = Forced(f());
-                        self.get_ref()
-                    }
-                }
-            }
+            Forced(ref v) => v,
+            Broken => fail!("Future failed to produce a value"),
+            _ => fail!("Logic error."),
         }
     }
 
@@ -91,7 +192,7 @@ impl<A> Future<A> {
          * not block.
          */
 
-        Future {state: Forced(val)}
+        Future {state: Forced(val), cancel: None}
     }
 
     pub fn from_fn(f: proc():Send -> A) -> Future<A> {
@@ -103,7 +204,39 @@ impl<A> Future<A> {
          * function. It is not spawned into another task.
          */
 
-        Future {state: Pending(f)}
+        Future {state: Pending(proc() Ok(f())), cancel: None}
+    }
+
+    // Blocks until the value of the future has been computed, or its
+    // computation has failed.
+    fn force(&mut self) {
+        let result = match replace(&mut self.state, Evaluating) {
+            Pending(f) => f(),
+            Waiting(source) => source.wait().map_err(|()| Failed),
+            Evaluating => fail!("Recursive forcing of future!"),
+            state => { self.state = state; return }
+        };
+        self.state = match result {
+            Ok(v) => Forced(v),
+            Err(..) => Broken,
+        };
+    }
+
+    // Moves the future on as far as it can go without blocking on another
+    // task. Futures created with `from_fn` are evaluated here.
+    fn poll(&mut self) {
+        let result = match self.state {
+            Waiting(ref source) => match source.poll() {
+                Some(result) => result,
+                None => return,
+            },
+            Pending(..) | Evaluating => return self.force(),
+            Forced(..) | Broken => return,
+        };
+        self.state = match result {
+            Ok(v) => Forced(v),
+            Err(()) => Broken,
+        };
     }
 }
 
@@ -113,12 +246,12 @@ impl<A:Send> Future<A> {
          * Create a future from a port
          *
          * The first time that the value is requested the task will block
-         * waiting for the result to be received on the port.
+         * waiting for the result to be received on the port. If the sending
+         * half of the port goes away without sending a value, the future
+         * fails.
          */
 
-        Future::from_fn(proc() {
-            rx.recv()
-        })
+        Future {state: Waiting(box rx as Box<Source<A> + Send>), cancel: None}
     }
 
     pub fn spawn(blk: proc():Send -> A) -> Future<A> {
@@ -126,24 +259,163 @@ impl<A:Send> Future<A> {
          * Create a future from a unique closure.
          *
          * The closure will be run in a new task and its result used as the
-         * value of the future.
+         * value of the future. If the task fails, so does the future.
          */
 
+        Future::spawn_cancellable(proc(_) blk())
+    }
+
+    /// Creates a future from a unique closure which is run in a new task, as
+    /// `spawn` does. The closure is given a `Cancellation`, which it can check
+    /// to stop early if the future has been dropped in the meantime.
+    pub fn spawn_cancellable(blk: proc(Cancellation):Send -> A) -> Future<A> {
+        Future::from_task(proc(cancellation, tx) {
+            // Don't fail if the other end has hung up
+            let _ = tx.send_opt(blk(cancellation));
+        })
+    }
+
+    /// Creates a future for the result of applying `f` to the value of this
+    /// one.
+    ///
+    /// If this future is being computed by another task, `f` is applied in a
+    /// new task as soon as the value is ready, so the new future can be polled
+    /// with `try_get`. Otherwise `f` is applied when the new future's value is
+    /// requested. If this future fails, `f` is never called and the new future
+    /// fails as well.
+    pub fn map<B:Send>(self, f: proc(A):Send -> B) -> Future<B> {
+        self.and_then(proc(v) Future::from_value(f(v)))
+    }
+
+    /// Creates a future for the value of the future returned by applying `f`
+    /// to the value of this one. This behaves as `map` does, with the new
+    /// future failing if either this future or the one returned by `f` fails.
+    pub fn and_then<B:Send>(self, f: proc(A):Send -> Future<B>) -> Future<B> {
+        let waiting = match self.state { Waiting(..) => true, _ => false };
+        if !waiting {
+            return Future {
+                state: Pending(proc() {
+                    match self.try_unwrap() {
+                        Ok(v) => f(v).try_unwrap(),
+                        Err(e) => Err(e),
+                    }
+                }),
+                cancel: None,
+            }
+        }
+
+        Future::from_task(proc(cancellation, tx) {
+            let next = match self.try_unwrap_unless(&cancellation) {
+                Some(Ok(v)) => f(v),
+                _ => return,
+            };
+            match next.try_unwrap_unless(&cancellation) {
+                Some(Ok(v)) => { let _ = tx.send_opt(v); }
+                _ => {}
+            }
+        })
+    }
+
+    /// Creates a future for the values of all of `futures`, in order.
+    ///
+    /// The values are collected in a new task. If any of the futures fails,
+    /// the new future fails too and the rest of the futures are dropped, which
+    /// cancels them.
+    pub fn join_all(futures: Vec<Future<A>>) -> Future<Vec<A>> {
+        Future::from_task(proc(cancellation, tx) {
+            let mut values = Vec::with_capacity(futures.len());
+            for future in futures.move_iter() {
+                match future.try_unwrap_unless(&cancellation) {
+                    Some(Ok(v)) => values.push(v),
+                    _ => return,
+                }
+            }
+            let _ = tx.send_opt(values);
+        })
+    }
+
+    /// Creates a future for the value of whichever of `futures` successfully
+    /// produces a value first, along with its index in `futures`.
+    ///
+    /// The futures are waited on in a new task. Once one of them has produced
+    /// a value the rest are dropped, which cancels them. The new future only
+    /// fails if all of `futures` fail.
+    pub fn select_any(futures: Vec<Future<A>>) -> Future<(uint, A)> {
+        Future::from_task(proc(cancellation, tx) {
+            let mut futures = futures;
+            loop {
+                let mut waiting = false;
+                for (i, future) in futures.mut_iter().enumerate() {
+                    future.poll();
+                    match replace(&mut future.state, Broken) {
+                        Forced(v) => { let _ = tx.send_opt((i, v)); return }
+                        Waiting(source) => {
+                            future.state = Waiting(source);
+                            waiting = true;
+                        }
+                        _ => {}
+                    }
+                }
+                if !waiting { return }
+
+                // Block until one of the remaining futures is ready, or this
+                // selection is itself cancelled.
+                let sel = Select::new();
+                let mut cancel = sel.handle(&cancellation.rx);
+                let mut handles: Vec<Handle<A>> = futures.iter().filter_map(|f| {
+                    match f.state {
+                        Waiting(ref source) => Some(source.handle(&sel)),
+                        _ => None,
+                    }
+                }).collect();
+                unsafe {
+                    cancel.add();
+                    for handle in handles.mut_iter() { handle.add(); }
+                }
+                if sel.wait() == cancel.id() { return }
+            }
+        })
+    }
+
+    // Creates a future whose value is computed by `blk` in a new task. The
+    // future fails if `blk` returns without sending a value.
+    fn from_task(blk: proc(Cancellation, Sender<A>):Send) -> Future<A> {
         let (tx, rx) = channel();
+        let (cancel_tx, cancel_rx) = channel();
 
         spawn(proc() {
-            // Don't fail if the other end has hung up
-            let _ = tx.send_opt(blk());
+            blk(Cancellation { rx: cancel_rx }, tx);
         });
 
-        Future::from_receiver(rx)
+        Future {
+            state: Waiting(box rx as Box<Source<A> + Send>),
+            cancel: Some(cancel_tx),
+        }
+    }
+
+    // Unwraps the future as `try_unwrap` does, unless the computation is
+    // cancelled before the value is ready, in which case `None` is returned
+    // and the future is dropped.
+    fn try_unwrap_unless(self, cancellation: &Cancellation)
+                         -> Option<Result<A, FutureError>> {
+        let ready = match self.state {
+            Waiting(ref source) => {
+                let sel = Select::new();
+                let mut value = source.handle(&sel);
+                let mut cancel = sel.handle(&cancellation.rx);
+                unsafe { value.add(); cancel.add(); }
+                sel.wait() == value.id()
+            }
+            _ => true,
+        };
+        if ready { Some(self.try_unwrap()) } else { None }
     }
 }
 
 #[cfg(test)]
 mod test {
     use prelude::*;
-    use sync::Future;
+    use sync::{Future, NotReady, Failed};
     use task;
     use comm::{channel, Sender};
 
@@ -209,6 +481,104 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_try_get() {
+        let (tx, rx) = channel();
+        let mut f = Future::from_receiver(rx);
+        assert_eq!(f.try_get(), Err(NotReady));
+        tx.send("kelp".to_string());
+        assert_eq!(f.try_get(), Ok("kelp".to_string()));
+        assert_eq!(f.get(), "kelp".to_string());
+    }
+
+    #[test]
+    fn test_try_get_failed() {
+        let (tx, rx) = channel::<String>();
+        let mut f = Future::from_receiver(rx);
+        drop(tx);
+        assert_eq!(f.try_get(), Err(Failed));
+    }
+
+    #[test]
+    fn test_try_unwrap() {
+        let f = Future::spawn(proc() "crab".to_string());
+        assert_eq!(f.try_unwrap(), Ok("crab".to_string()));
+        let f = Future::spawn(proc() -> String { fail!() });
+        assert_eq!(f.try_unwrap(), Err(Failed));
+    }
+
+    #[test]
+    fn test_map() {
+        let f = Future::from_value(2i).map(proc(x) x * 3);
+        assert_eq!(f.unwrap(), 6);
+        let f = Future::spawn(proc() 2i).map(proc(x) x * 3);
+        assert_eq!(f.unwrap(), 6);
+    }
+
+    #[test]
+    fn test_map_failure() {
+        let f = Future::spawn(proc() -> int { fail!() }).map(proc(x) x * 3);
+        assert_eq!(f.try_unwrap(), Err(Failed));
+    }
+
+    #[test]
+    fn test_and_then() {
+        let f = Future::from_fn(proc() 2i)
+                       .and_then(proc(x) Future::spawn(proc() x + 1));
+        assert_eq!(f.unwrap(), 3);
+        let f = Future::spawn(proc() 2i)
+                       .and_then(proc(x) Future::spawn(proc() -> int {
+                           fail!("{}", x)
+                       }));
+        assert_eq!(f.try_unwrap(), Err(Failed));
+    }
+
+    #[test]
+    fn test_join_all() {
+        let futures = Vec::from_fn(5, |i| Future::spawn(proc() i));
+        assert_eq!(Future::join_all(futures).unwrap(), vec!(0u, 1, 2, 3, 4));
+        let futures = vec!(Future::from_value(1i),
+                           Future::spawn(proc() fail!()));
+        assert_eq!(Future::join_all(futures).try_unwrap(), Err(Failed));
+    }
+
+    #[test]
+    fn test_select_any() {
+        let (_tx, rx) = channel();
+        let futures = vec!(Future::from_receiver(rx),
+                           Future::spawn(proc() -> int { fail!() }),
+                           Future::spawn(proc() 3i));
+        assert_eq!(Future::select_any(futures).unwrap(), (2, 3));
+
+        let futures = vec!(Future::spawn(proc() -> int { fail!() }),
+                           Future::spawn(proc() -> int { fail!() }));
+        assert_eq!(Future::select_any(futures).try_unwrap(), Err(Failed));
+    }
+
+    #[test]
+    fn test_select_any_cancels_others() {
+        let (tx, rx) = channel();
+        let slow = Future::spawn_cancellable(proc(cancellation) {
+            while !cancellation.is_cancelled() { task::deschedule() }
+            tx.send(());
+            0i
+        });
+        let futures = vec!(slow, Future::from_value(1i));
+        assert_eq!(Future::select_any(futures).unwrap(), (1, 1));
+        rx.recv();
+    }
+
+    #[test]
+    fn test_cancel_on_drop() {
+        let (tx, rx) = channel();
+        let f = Future::spawn_cancellable(proc(cancellation) {
+            while !cancellation.is_cancelled() { task::deschedule() }
+            tx.send(());
+        }).map(proc(()) 1i);
+        drop(f);
+        rx.recv();
+    }
+
     #[test]
     fn test_dropped_future_doesnt_fail() {
         struct Bomb(Sender<bool>);
@@ -18,13 +18,14 @@
 #![experimental]
 
 pub use core_sync::{atomics, deque, mpmc_bounded_queue, mpsc_queue, spsc_queue};
+pub use core_sync::concurrent_hashmap;
 pub use core_sync::{Arc, Weak, Mutex, MutexGuard, Condvar, Barrier};
 pub use core_sync::{RWLock, RWLockReadGuard, RWLockWriteGuard};
 pub use core_sync::{Semaphore, SemaphoreGuard};
 pub use core_sync::one::{Once, ONCE_INIT};
 
-pub use self::future::Future;
-pub use self::task_pool::TaskPool;
+pub use self::future::{Future, FutureError, NotReady, Failed, Cancellation};
+pub use self::task_pool::{TaskPool, Scope};
 
 mod future;
 mod task_pool;
@@ -1,4 +1,4 @@
-// Copyright 2012 The Rust Project Developers. See the COPYRIGHT
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
 // file at the top-level directory of this distribution and at
 // http://rust-lang.org/COPYRIGHT.
 //
@@ -9,31 +9,78 @@
 // except according to those terms.
 
 //! Abstraction of a task pool for basic parallelism.
+//!
+//! The pool is made up of a fixed number of tasks, each of which owns a
+//! work-stealing deque of jobs (see `sync::deque`). Jobs handed to the pool
+//! from the outside are put on a shared queue from which any idle task can pick
+//! them up, while jobs created by a running job are pushed onto the deque of
+//! the task running it. A task which runs out of jobs of its own steals from
+//! the other tasks' deques before going to sleep, so no task sits idle while
+//! another one has work queued up behind a slow job.
+//!
+//! A task which fails while running a job isn't replaced, as its local data
+//! can't be recreated. Once every task in the pool has failed, the jobs which
+//! are still queued up are dropped and no new ones are accepted, so nothing
+//! waits on the pool forever.
 
 use core::prelude::*;
 
+use any::Any;
+use collections::{Deque, RingBuf};
+use comm::{channel, Sender, Receiver};
+use kinds::marker;
+use mem;
+use os;
+use owned::Box;
+use sync::atomics::{AtomicUint, AtomicBool, SeqCst};
+use sync::deque::{BufferPool, Worker, Stealer, Data, Empty, Abort};
+use sync::{Arc, Mutex, Future};
 use task;
-use task::spawn;
 use vec::Vec;
-use comm::{channel, Sender};
-
-enum Msg<T> {
-    Execute(proc(&T):Send),
-    Quit
-}
 
 /// A task pool used to execute functions in parallel.
 pub struct TaskPool<T> {
-    channels: Vec<Sender<Msg<T>>>,
-    next_index: uint,
+    shared: Arc<Shared<T>>,
+    workers: Vec<Future<Result<(), Box<Any + Send>>>>,
+}
+
+// A unit of work for the pool. Jobs are given access to the local data of the
+// task which runs them, and to that task's deque so they can create more jobs.
+struct Job<T> {
+    run: proc(&Context<T>):Send,
+}
+
+struct Context<'a, T> {
+    data: &'a T,
+    deque: &'a Worker<Job<T>>,
+    shared: &'a Shared<T>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+
+    // The number of jobs which have been queued up anywhere but not yet taken
+    // by a task, and the number of tasks which are waiting on the condition
+    // variable for a job. A task only goes to sleep with the lock held after
+    // having seen no pending jobs, and pushing a job onto a deque only signals
+    // (under the lock) when some task is asleep. Both counters are sequentially
+    // consistent, so either the sleeping task sees the new job or the pushing
+    // task sees the sleeping one.
+    pending: AtomicUint,
+    sleeping: AtomicUint,
+}
+
+struct State<T> {
+    injected: RingBuf<Job<T>>,
+    shutdown: bool,
+    // The number of tasks which haven't failed.
+    live: uint,
 }
 
 #[unsafe_destructor]
 impl<T> Drop for TaskPool<T> {
     fn drop(&mut self) {
-        for channel in self.channels.mut_iter() {
-            channel.send(Quit);
-        }
+        self.shared.shut_down();
     }
 }
 
@@ -50,38 +97,443 @@ impl<T> TaskPool<T> {
                -> TaskPool<T> {
         assert!(n_tasks >= 1);
 
-        let channels = Vec::from_fn(n_tasks, |i| {
-            let (tx, rx) = channel::<Msg<T>>();
-            let init_fn = init_fn_factory();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                injected: RingBuf::new(),
+                shutdown: false,
+                live: n_tasks,
+            }),
+            pending: AtomicUint::new(0),
+            sleeping: AtomicUint::new(0),
+        });
 
-            let task_body = proc() {
-                let local_data = init_fn(i);
-                loop {
-                    match rx.recv() {
-                        Execute(f) => f(&local_data),
-                        Quit => break
-                    }
-                }
-            };
+        let pool = BufferPool::new();
+        let mut deques = Vec::with_capacity(n_tasks);
+        let mut stealers = Vec::with_capacity(n_tasks);
+        for _ in range(0, n_tasks) {
+            let (deque, stealer) = pool.deque();
+            deques.push(deque);
+            stealers.push(stealer);
+        }
+
+        let workers = deques.move_iter().enumerate().map(|(i, deque)| {
+            let init_fn = init_fn_factory();
+            let stealers = stealers.clone();
+            let shared = shared.clone();
 
             // Run on this scheduler.
-            task::spawn(task_body);
+            task::try_future(proc() {
+                let _sentinel = Sentinel { shared: &*shared };
+                let local_data = init_fn(i);
+                work(i, &local_data, deque, stealers.as_slice(), &*shared);
+            })
+        }).collect();
 
-            tx
-        });
+        TaskPool { shared: shared, workers: workers }
+    }
 
-        return TaskPool {
-            channels: channels,
-            next_index: 0,
-        };
+    /// Spawns a new task pool with one task for each CPU, as reported by
+    /// `os::num_cpus`. See `TaskPool::new` for the meaning of
+    /// `init_fn_factory`.
+    pub fn with_num_cpus(init_fn_factory: || -> proc(uint):Send -> T)
+                         -> TaskPool<T> {
+        TaskPool::new(os::num_cpus(), init_fn_factory)
     }
 
     /// Executes the function `f` on a task in the pool. The function
     /// receives a reference to the local data returned by the `init_fn`.
+    ///
+    /// The function is run by whichever task in the pool gets to it first.
+    /// If it fails, the task running it fails along with it and the pool
+    /// carries on with one task fewer. Should that leave no tasks at all, the
+    /// function may never be run.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if every task in the pool has failed.
     pub fn execute(&mut self, f: proc(&T):Send) {
-        self.channels.get(self.next_index).send(Execute(f));
-        self.next_index += 1;
-        if self.next_index == self.channels.len() { self.next_index = 0; }
+        self.shared.inject(Job { run: proc(cx) f(cx.data) });
+    }
+
+    /// Executes the function `f` on a task in the pool as `execute` does,
+    /// returning a future for the value it returns.
+    ///
+    /// If `f` is never run because every task in the pool failed, unwrapping
+    /// the future fails.
+    pub fn submit<A: Send>(&mut self, f: proc(&T):Send -> A) -> Future<A> {
+        let (tx, rx) = channel();
+        self.execute(proc(data) {
+            // Don't fail if the future has been dropped
+            let _ = tx.send_opt(f(data));
+        });
+        Future::from_receiver(rx)
+    }
+
+    /// Applies `f` to each element of `data` in parallel, blocking until every
+    /// element has been processed and returning the results in order.
+    ///
+    /// The work is split up by halving the slice until single elements are
+    /// left, with idle tasks in the pool stealing the halves which haven't been
+    /// started on yet. As this function doesn't return until all the work is
+    /// done, `data` can be borrowed rather than sent to the pool.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if `f` fails for any of the elements, or if
+    /// every task in the pool has failed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::TaskPool;
+    ///
+    /// fn square(x: &uint) -> uint { *x * *x }
+    ///
+    /// let f: || -> proc(uint):Send -> () = || proc(_) ();
+    /// let mut pool = TaskPool::new(4, f);
+    /// let numbers = Vec::from_fn(100, |i| i);
+    /// let squares = pool.map(numbers.as_slice(), square);
+    /// assert_eq!(*squares.get(9), 81);
+    /// ```
+    pub fn map<A: Share, B: Send>(&mut self, data: &[A],
+                                  f: fn(&A) -> B) -> Vec<B> {
+        if data.len() == 0 { return Vec::new() }
+
+        let mut results: Vec<Option<B>> = Vec::from_fn(data.len(), |_| None);
+        let progress = Arc::new(Progress::new(data.len()));
+        let (tx, rx) = channel();
+        let job = MapJob {
+            data: data.as_ptr() as uint,
+            results: results.as_mut_ptr() as uint,
+            f: f,
+            lo: 0,
+            hi: data.len(),
+            progress: progress.clone(),
+            done: tx,
+        };
+        self.shared.inject(Job { run: proc(cx) job.run(cx) });
+
+        // Every job signs off on its elements as it goes away, even if it
+        // fails, so once this returns nothing in the pool refers to `data` or
+        // `results` any more.
+        rx.recv();
+        if progress.failed.load(SeqCst) {
+            fail!("a job failed in TaskPool::map");
+        }
+        results.move_iter().map(|r| r.unwrap()).collect()
+    }
+
+    /// Runs `f` with a `Scope` through which it can hand jobs to the pool,
+    /// blocking until every one of those jobs has finished.
+    ///
+    /// This is the fork-join counterpart to `execute`. As none of the jobs
+    /// outlive this call, even if `f` fails, they can borrow data from the
+    /// caller rather than having it sent to the pool.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if any of the jobs fails, once all of them are
+    /// done.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::TaskPool;
+    /// use std::sync::atomics::{AtomicUint, SeqCst};
+    ///
+    /// fn count(total: &AtomicUint, _: &()) { total.fetch_add(1, SeqCst); }
+    ///
+    /// let f: || -> proc(uint):Send -> () = || proc(_) ();
+    /// let mut pool = TaskPool::new(4, f);
+    /// let total = AtomicUint::new(0);
+    /// pool.scope(|scope| {
+    ///     for _ in range(0u, 10) { scope.execute(&total, count); }
+    /// });
+    /// assert_eq!(total.load(SeqCst), 10);
+    /// ```
+    pub fn scope<'a, R>(&mut self, f: |&Scope<'a, T>| -> R) -> R {
+        let (tx, rx) = channel();
+        let progress = Arc::new(Progress::new(1));
+        let ret = {
+            let scope = Scope {
+                shared: self.shared.clone(),
+                progress: progress.clone(),
+                done: tx,
+                finished: rx,
+                marker: marker::InvariantLifetime,
+            };
+            f(&scope)
+        };
+        if progress.failed.load(SeqCst) {
+            fail!("a job failed in TaskPool::scope");
+        }
+        ret
+    }
+
+    /// Shuts the pool down, waiting for all of its tasks to exit.
+    ///
+    /// Any jobs which have already been handed to the pool are run before the
+    /// tasks exit. Simply dropping the pool also shuts it down in this way,
+    /// but without waiting for the tasks.
+    pub fn shutdown(mut self) {
+        self.shared.shut_down();
+        for worker in mem::replace(&mut self.workers, Vec::new()).move_iter() {
+            // Tasks which failed while running a job have already exited.
+            let _ = worker.unwrap();
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    fn inject(&self, job: Job<T>) {
+        let mut state = self.state.lock();
+        assert!(!state.shutdown);
+        if state.live == 0 {
+            // Don't fail with the lock held, the pool still needs it to shut
+            // down.
+            drop(state);
+            fail!("every task in the TaskPool has failed");
+        }
+        state.injected.push_back(job);
+        self.pending.fetch_add(1, SeqCst);
+        state.cond.signal();
+    }
+
+    // Called after a job has been pushed onto a task's deque.
+    fn pushed(&self) {
+        self.pending.fetch_add(1, SeqCst);
+        if self.sleeping.load(SeqCst) > 0 {
+            self.state.lock().cond.signal();
+        }
+    }
+
+    fn taken(&self) {
+        self.pending.fetch_sub(1, SeqCst);
+    }
+
+    fn shut_down(&self) {
+        let mut state = self.state.lock();
+        state.shutdown = true;
+        state.cond.broadcast();
+    }
+
+    // Called as a task in the pool fails. Once the last one has gone, the jobs
+    // left in the shared queue will never run, so they're dropped to let
+    // whoever is waiting on them know. The jobs left in the tasks' deques are
+    // dropped along with the deques.
+    fn task_failed(&self) {
+        let jobs = {
+            let mut state = self.state.lock();
+            state.live -= 1;
+            if state.live > 0 { return }
+            mem::replace(&mut state.injected, RingBuf::new())
+        };
+        drop(jobs);
+    }
+}
+
+// Lets the pool know when the task owning this fails.
+struct Sentinel<'a, T> {
+    shared: &'a Shared<T>,
+}
+
+#[unsafe_destructor]
+impl<'a, T> Drop for Sentinel<'a, T> {
+    fn drop(&mut self) {
+        if task::failing() {
+            self.shared.task_failed();
+        }
+    }
+}
+
+impl<'a, T> Context<'a, T> {
+    fn push(&self, job: Job<T>) {
+        self.deque.push(job);
+        self.shared.pushed();
+    }
+}
+
+// The body of each task in the pool, which runs jobs until the pool is shut
+// down.
+fn work<T>(index: uint, data: &T, deque: Worker<Job<T>>,
+           stealers: &[Stealer<Job<T>>], shared: &Shared<T>) {
+    let cx = Context { data: data, deque: &deque, shared: shared };
+    loop {
+        match next_job(index, &deque, stealers, shared) {
+            Some(Job { run }) => run(&cx),
+            None => break,
+        }
+    }
+}
+
+// Finds the next job for the task at `index` to run, first from its own deque,
+// then by stealing from the other tasks and finally from the shared queue. If
+// there are no jobs anywhere this blocks, returning `None` once the pool has
+// been shut down with no more jobs left to run.
+fn next_job<T>(index: uint, deque: &Worker<Job<T>>,
+               stealers: &[Stealer<Job<T>>],
+               shared: &Shared<T>) -> Option<Job<T>> {
+    loop {
+        match deque.pop() {
+            Some(job) => { shared.taken(); return Some(job) }
+            None => {}
+        }
+        for i in range(1, stealers.len()) {
+            match stealers[(index + i) % stealers.len()].steal() {
+                Data(job) => { shared.taken(); return Some(job) }
+                Empty | Abort => {}
+            }
+        }
+
+        let mut state = shared.state.lock();
+        match state.injected.pop_front() {
+            Some(job) => { shared.taken(); return Some(job) }
+            None => {}
+        }
+        // If there are pending jobs then some other task has just pushed one
+        // onto its deque, so go back around and steal it.
+        if shared.pending.load(SeqCst) == 0 {
+            if state.shutdown { return None }
+            shared.sleeping.fetch_add(1, SeqCst);
+            if shared.pending.load(SeqCst) == 0 {
+                state.cond.wait();
+            }
+            shared.sleeping.fetch_sub(1, SeqCst);
+        }
+    }
+}
+
+// A range of the elements of a `TaskPool::map` which is yet to be processed.
+// The pointers to the input and output are stored as integers so that jobs can
+// be sent to the pool, see `TaskPool::map` for why this is safe.
+struct MapJob<A, B> {
+    data: uint,
+    results: uint,
+    f: fn(&A) -> B,
+    lo: uint,
+    hi: uint,
+    progress: Arc<Progress>,
+    done: Sender<()>,
+}
+
+// The amount of work left for a `TaskPool::map` or `TaskPool::scope`, which
+// wait to hear from the job that finishes it off. Jobs sign off on their share
+// of the work as they go away, whether or not they ran.
+struct Progress {
+    remaining: AtomicUint,
+    failed: AtomicBool,
+}
+
+impl Progress {
+    fn new(remaining: uint) -> Progress {
+        Progress {
+            remaining: AtomicUint::new(remaining),
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    fn sign_off(&self, n: uint, done: &Sender<()>) {
+        if task::failing() {
+            self.failed.store(true, SeqCst);
+        }
+        if self.remaining.fetch_sub(n, SeqCst) == n {
+            let _ = done.send_opt(());
+        }
+    }
+}
+
+impl<A: Share, B: Send> MapJob<A, B> {
+    fn run<T>(mut self, cx: &Context<T>) {
+        // Hand off the upper half of the range until only a single element is
+        // left, so that idle tasks can steal the other halves.
+        while self.hi - self.lo > 1 {
+            let mid = self.lo + (self.hi - self.lo) / 2;
+            let upper = MapJob {
+                data: self.data,
+                results: self.results,
+                f: self.f,
+                lo: mid,
+                hi: self.hi,
+                progress: self.progress.clone(),
+                done: self.done.clone(),
+            };
+            self.hi = mid;
+            cx.push(Job { run: proc(cx) upper.run(cx) });
+        }
+        unsafe {
+            let input = &*(self.data as *const A).offset(self.lo as int);
+            let output = (self.results as *mut Option<B>).offset(self.lo as int);
+            *output = Some((self.f)(input));
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<A, B> Drop for MapJob<A, B> {
+    fn drop(&mut self) {
+        self.progress.sign_off(self.hi - self.lo, &self.done);
+    }
+}
+
+/// A handle for handing jobs which borrow data living for `'a` to a
+/// `TaskPool`, see `TaskPool::scope`.
+pub struct Scope<'a, T> {
+    shared: Arc<Shared<T>>,
+    progress: Arc<Progress>,
+    done: Sender<()>,
+    finished: Receiver<()>,
+    marker: marker::InvariantLifetime<'a>,
+}
+
+impl<'a, T> Scope<'a, T> {
+    /// Executes the function `f` on a task in the pool, giving it `data` along
+    /// with the local data of the task which runs it.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if every task in the pool has failed.
+    pub fn execute<A: Share>(&self, data: &'a A, f: fn(&A, &T)) {
+        self.progress.remaining.fetch_add(1, SeqCst);
+        let job = ScopeJob {
+            data: data as *const A as uint,
+            f: f,
+            progress: self.progress.clone(),
+            done: self.done.clone(),
+        };
+        self.shared.inject(Job { run: proc(cx) job.run(cx) });
+    }
+}
+
+#[unsafe_destructor]
+impl<'a, T> Drop for Scope<'a, T> {
+    fn drop(&mut self) {
+        // The scope holds on to a share of the work itself until now, so the
+        // jobs can't all have signed off before this point.
+        self.progress.sign_off(1, &self.done);
+        self.finished.recv();
+    }
+}
+
+// A job handed to the pool through a `Scope`. As with `MapJob`, the borrowed
+// data is stored as an integer, which is safe as the scope doesn't go away
+// before the job does.
+struct ScopeJob<A, T> {
+    data: uint,
+    f: fn(&A, &T),
+    progress: Arc<Progress>,
+    done: Sender<()>,
+}
+
+impl<A: Share, T> ScopeJob<A, T> {
+    fn run(self, cx: &Context<T>) {
+        unsafe { (self.f)(&*(self.data as *const A), cx.data) }
+    }
+}
+
+#[unsafe_destructor]
+impl<A, T> Drop for ScopeJob<A, T> {
+    fn drop(&mut self) {
+        self.progress.sign_off(1, &self.done);
     }
 }
 
@@ -100,3 +552,124 @@ fn test_zero_tasks_failure() {
     let f: || -> proc(uint):Send -> uint = || { proc(i) i };
     TaskPool::new(0, f);
 }
+
+#[test]
+fn test_submit() {
+    let f: || -> proc(uint):Send -> uint = || { proc(i) i };
+    let mut pool = TaskPool::new(4, f);
+    let futures = Vec::from_fn(8, |i| pool.submit(proc(_) i * 2));
+    for (i, future) in futures.move_iter().enumerate() {
+        assert_eq!(future.unwrap(), i * 2);
+    }
+}
+
+#[test]
+fn test_slow_job_does_not_block_others() {
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(2, f);
+
+    // Tie up one task until all the other jobs have run on the other one.
+    let (block_tx, block_rx) = channel::<()>();
+    pool.execute(proc(_) block_rx.recv());
+    let (tx, rx) = channel();
+    for i in range(0u, 10) {
+        let tx = tx.clone();
+        pool.execute(proc(_) tx.send(i));
+    }
+    for _ in range(0u, 10) { rx.recv(); }
+    block_tx.send(());
+    pool.shutdown();
+}
+
+#[test]
+fn test_shutdown_runs_queued_jobs() {
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(2, f);
+    let (tx, rx) = channel();
+    for i in range(0u, 100) {
+        let tx = tx.clone();
+        pool.execute(proc(_) tx.send(i));
+    }
+    pool.shutdown();
+    drop(tx);
+    assert_eq!(rx.iter().count(), 100);
+}
+
+#[test]
+fn test_map() {
+    fn double(x: &uint) -> uint { *x * 2 }
+
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(4, f);
+    let data = Vec::from_fn(1000, |i| i);
+    let doubled = pool.map(data.as_slice(), double);
+    assert_eq!(doubled, Vec::from_fn(1000, |i| i * 2));
+    assert_eq!(pool.map([].as_slice(), double), Vec::new());
+    pool.shutdown();
+}
+
+#[test]
+#[should_fail]
+fn test_map_failure() {
+    fn check(x: &uint) -> uint { assert!(*x != 7); *x }
+
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(4, f);
+    pool.map(Vec::from_fn(16, |i| i).as_slice(), check);
+}
+
+#[test]
+fn test_scope() {
+    fn add(total: &AtomicUint, n: &uint) { total.fetch_add(*n, SeqCst); }
+
+    let f: || -> proc(uint):Send -> uint = || { proc(_) 1 };
+    let mut pool = TaskPool::new(4, f);
+    let total = AtomicUint::new(0);
+    let ret = pool.scope(|scope| {
+        for _ in range(0u, 100) { scope.execute(&total, add); }
+        7u
+    });
+    assert_eq!(ret, 7);
+    assert_eq!(total.load(SeqCst), 100);
+    pool.scope(|_| ());
+    pool.shutdown();
+}
+
+#[test]
+#[should_fail]
+fn test_scope_failure() {
+    fn check(total: &AtomicUint, _: &()) {
+        assert!(total.fetch_add(1, SeqCst) != 7);
+    }
+
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(4, f);
+    let total = AtomicUint::new(0);
+    pool.scope(|scope| {
+        for _ in range(0u, 16) { scope.execute(&total, check); }
+    });
+}
+
+#[test]
+fn test_failed_task_leaves_others_running() {
+    fn double(x: &uint) -> uint { *x * 2 }
+
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(2, f);
+    let failed: Future<()> = pool.submit(proc(_) fail!());
+    assert!(failed.try_unwrap().is_err());
+    let doubled = pool.map(Vec::from_fn(100, |i| i).as_slice(), double);
+    assert_eq!(doubled, Vec::from_fn(100, |i| i * 2));
+    pool.shutdown();
+}
+
+#[test]
+#[should_fail]
+fn test_all_tasks_failed() {
+    let f: || -> proc(uint):Send -> () = || { proc(_) () };
+    let mut pool = TaskPool::new(1, f);
+    pool.execute(proc(_) fail!());
+    // Either the job is dropped along with the last task or it's refused
+    // outright, but it doesn't wait forever.
+    pool.submit(proc(_) ()).unwrap();
+}
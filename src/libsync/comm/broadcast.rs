@@ -0,0 +1,265 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Broadcast channels
+//!
+//! A broadcast channel delivers a copy of every message to each of its
+//! subscribers. Each subscriber is backed by an ordinary channel of its own, so
+//! subscribers never hold each other up and they can take part in a `Select`
+//! like any other receiver. The list of subscribers is kept behind a mutex
+//! which is safe to hold across a context switch, because waking up a
+//! subscriber as part of a broadcast may switch to it.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use collections::Vec;
+use core::mem;
+use rustrt::task::BlockedTask;
+
+use comm::{Sender, Receiver, Messages, TryRecvError, channel};
+use comm::select::{Packet, Receivable};
+use lock::Mutex;
+
+/// The sending half of a broadcast channel. This half can be cloned to
+/// broadcast from many tasks.
+pub struct Broadcaster<T> {
+    inner: Arc<Mutex<State<T>>>,
+}
+
+/// A subscription to a broadcast channel, which receives a copy of every
+/// message broadcast after it was created.
+pub struct Subscriber<T> {
+    rx: Receiver<T>,
+    inner: Arc<Mutex<State<T>>>,
+}
+
+struct State<T> {
+    subscribers: Vec<Sender<T>>,
+    broadcasters: uint,
+}
+
+/// Creates a new broadcast channel, returning the broadcasting half along with
+/// a first subscriber.
+///
+/// More subscribers can be created with `Broadcaster::subscribe`, or by
+/// cloning a `Subscriber`.
+///
+/// # Example
+///
+/// ```
+/// use std::comm::broadcast;
+///
+/// let (tx, rx1) = broadcast();
+/// let rx2 = tx.subscribe();
+/// tx.send("hello".to_string());
+/// assert_eq!(rx1.recv().as_slice(), "hello");
+/// assert_eq!(rx2.recv().as_slice(), "hello");
+/// ```
+pub fn broadcast<T: Send + Clone>() -> (Broadcaster<T>, Subscriber<T>) {
+    let (tx, rx) = channel();
+    let inner = Arc::new(Mutex::new(State {
+        subscribers: vec![tx],
+        broadcasters: 1,
+    }));
+    (Broadcaster { inner: inner.clone() }, Subscriber { rx: rx, inner: inner })
+}
+
+// Adds a new subscriber to the channel. If all broadcasters are already gone,
+// the new subscriber is disconnected from the start.
+fn subscribe<T: Send + Clone>(inner: &Arc<Mutex<State<T>>>) -> Subscriber<T> {
+    let (tx, rx) = channel();
+    {
+        let mut state = inner.lock();
+        if state.broadcasters > 0 {
+            state.subscribers.push(tx);
+        }
+    }
+    Subscriber { rx: rx, inner: inner.clone() }
+}
+
+impl<T: Send + Clone> Broadcaster<T> {
+    /// Sends a copy of a value to every current subscriber.
+    ///
+    /// Subscribers which have been dropped are forgotten about along the way.
+    /// This never blocks, as each subscriber buffers the messages it has yet to
+    /// receive.
+    pub fn send(&self, t: T) {
+        let mut state = self.inner.lock();
+        state.subscribers.retain(|tx| tx.send_opt(t.clone()).is_ok());
+    }
+
+    /// Creates a new subscriber, which receives every message sent after this
+    /// call returns.
+    pub fn subscribe(&self) -> Subscriber<T> {
+        subscribe(&self.inner)
+    }
+
+    /// Returns the number of subscribers which were still around as of the
+    /// last broadcast.
+    pub fn subscribers(&self) -> uint {
+        self.inner.lock().subscribers.len()
+    }
+}
+
+impl<T: Send + Clone> Clone for Broadcaster<T> {
+    fn clone(&self) -> Broadcaster<T> {
+        self.inner.lock().broadcasters += 1;
+        Broadcaster { inner: self.inner.clone() }
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send + Clone> Drop for Broadcaster<T> {
+    fn drop(&mut self) {
+        // Once the last broadcaster is gone, dropping every subscriber's
+        // sender disconnects them all.
+        let _subscribers = {
+            let mut state = self.inner.lock();
+            state.broadcasters -= 1;
+            if state.broadcasters == 0 {
+                mem::replace(&mut state.subscribers, Vec::new())
+            } else {
+                Vec::new()
+            }
+        };
+    }
+}
+
+impl<T: Send + Clone> Subscriber<T> {
+    /// Blocks waiting for the next broadcast message. This has the same
+    /// semantics as `Receiver::recv`, failing if all broadcasters have gone
+    /// away.
+    pub fn recv(&self) -> T { self.rx.recv() }
+
+    /// Blocks waiting for the next broadcast message, returning `Err` if all
+    /// broadcasters have gone away. See `Receiver::recv_opt`.
+    pub fn recv_opt(&self) -> Result<T, ()> { self.rx.recv_opt() }
+
+    /// Attempts to receive the next broadcast message without blocking. See
+    /// `Receiver::try_recv`.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> { self.rx.try_recv() }
+
+    /// Blocks waiting for the next broadcast message for at most `timeout_ms`
+    /// milliseconds. See `Receiver::recv_timeout`.
+    pub fn recv_timeout(&self, timeout_ms: u64) -> Result<T, TryRecvError> {
+        self.rx.recv_timeout(timeout_ms)
+    }
+
+    /// Returns an iterator which will block waiting for messages, returning
+    /// `None` once all broadcasters have gone away.
+    pub fn iter<'a>(&'a self) -> Messages<'a, T> { self.rx.iter() }
+}
+
+/// Cloning a subscriber creates a new subscription, which receives every
+/// message sent after the clone is made. Messages which the original has yet
+/// to receive are not copied over.
+impl<T: Send + Clone> Clone for Subscriber<T> {
+    fn clone(&self) -> Subscriber<T> {
+        subscribe(&self.inner)
+    }
+}
+
+impl<T: Send + Clone> Packet for Subscriber<T> {
+    fn can_recv(&self) -> bool { self.rx.can_recv() }
+    fn start_selection(&self, task: BlockedTask) -> Result<(), BlockedTask> {
+        self.rx.start_selection(task)
+    }
+    fn abort_selection(&self) -> bool { self.rx.abort_selection() }
+}
+
+impl<T: Send + Clone> Receivable<T> for Subscriber<T> {
+    fn recv_opt(&self) -> Result<T, ()> { self.rx.recv_opt() }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::*;
+
+    test!(fn smoke() {
+        let (tx, rx1) = broadcast::<int>();
+        let rx2 = tx.subscribe();
+        tx.send(1);
+        tx.send(2);
+        assert_eq!(rx1.recv(), 1);
+        assert_eq!(rx1.recv(), 2);
+        assert_eq!(rx2.recv(), 1);
+        assert_eq!(rx2.recv(), 2);
+        assert_eq!(rx1.try_recv(), Err(Empty));
+    })
+
+    test!(fn late_subscribers() {
+        let (tx, rx1) = broadcast::<int>();
+        tx.send(1);
+        let rx2 = rx1.clone();
+        tx.send(2);
+        assert_eq!(rx1.recv(), 1);
+        assert_eq!(rx1.recv(), 2);
+        assert_eq!(rx2.recv(), 2);
+        assert_eq!(rx2.try_recv(), Err(Empty));
+    })
+
+    test!(fn dropped_subscribers() {
+        let (tx, rx1) = broadcast::<int>();
+        let rx2 = tx.subscribe();
+        assert_eq!(tx.subscribers(), 2);
+        drop(rx1);
+        tx.send(1);
+        assert_eq!(tx.subscribers(), 1);
+        assert_eq!(rx2.recv(), 1);
+    })
+
+    test!(fn disconnect() {
+        let (tx, rx1) = broadcast::<int>();
+        let tx2 = tx.clone();
+        drop(tx);
+        tx2.send(1);
+        drop(tx2);
+        assert_eq!(rx1.recv_opt(), Ok(1));
+        assert_eq!(rx1.recv_opt(), Err(()));
+        let rx2 = rx1.clone();
+        assert_eq!(rx2.try_recv(), Err(Disconnected));
+    })
+
+    test!(fn many_tasks() {
+        let (tx, rx) = broadcast::<uint>();
+        let (done_tx, done_rx) = channel();
+        for _ in range(0u, 4) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            spawn(proc() {
+                let mut n = 0u;
+                for i in rx.iter() { n += i; }
+                done_tx.send(n);
+            });
+        }
+        drop(rx);
+        for i in range(0u, 100) { tx.send(i); }
+        drop(tx);
+        for _ in range(0u, 4) { assert_eq!(done_rx.recv(), 4950); }
+    })
+
+    test!(fn select_subscriber() {
+        let (tx, rx1) = broadcast::<int>();
+        let rx2 = tx.subscribe();
+        let (_tx3, rx3) = channel::<int>();
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx.send(1);
+        });
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h3 = s.handle(&rx3);
+        unsafe { h1.add(); h3.add(); }
+        assert_eq!(s.wait(), h1.id());
+        assert_eq!(h1.recv(), 1);
+        assert_eq!(rx2.recv(), 1);
+    })
+}
@@ -43,6 +43,19 @@
 //!    "rendezvous" channel where each sender atomically hands off a message to
 //!    a receiver.
 //!
+//! When one receiver isn't enough, there are two more kinds of channels:
+//!
+//! * `mpmc_channel()` returns a `(MpmcSender, MpmcReceiver)` tuple for a
+//!   bounded channel whose receivers can also be cloned. Each message is
+//!   received by exactly one of the receivers, which makes this suitable for
+//!   handing out work to a number of tasks.
+//!
+//! * `broadcast()` returns a `(Broadcaster, Subscriber)` tuple for a channel
+//!   where every subscriber receives its own copy of each message.
+//!
+//! The receiving halves of both of these can be used with `Select` alongside
+//! ordinary receivers.
+//!
 //! ## Failure Propagation
 //!
 //! In addition to being a core primitive for communicating in rust, channels
@@ -121,7 +134,7 @@
 //! rx.recv();
 //! ```
 //!
-//! Reading from a channel with a timeout requires to use a Timer together
+//! One way of reading from a channel with a timeout is to use a Timer together
 //! with the channel. You can use the select! macro to select either and
 //! handle the timeout case. This first example will break out of the loop
 //! after 10 seconds no matter what:
@@ -144,25 +157,23 @@
 //! }
 //! ```
 //!
-//! This second example is more costly since it allocates a new timer every
-//! time a message is received, but it allows you to timeout after the channel
-//! has been inactive for 5 seconds:
+//! To instead timeout after the channel has been inactive for 5 seconds, the
+//! `recv_timeout` method waits on the channel itself without allocating a
+//! timer every time a message is received:
 //!
 //! ```no_run
-//! use std::io::timer::Timer;
+//! use std::comm::{Empty, Disconnected};
 //!
 //! let (tx, rx) = channel::<int>();
-//! let mut timer = Timer::new().unwrap();
 //!
 //! loop {
-//!     let timeout = timer.oneshot(5000);
-//!
-//!     select! {
-//!         val = rx.recv() => println!("Received {}", val),
-//!         () = timeout.recv() => {
+//!     match rx.recv_timeout(5000) {
+//!         Ok(val) => println!("Received {}", val),
+//!         Err(Empty) => {
 //!             println!("timed out, no message received in 5 seconds")
 //!             break;
 //!         }
+//!         Err(Disconnected) => break,
 //!     }
 //! }
 //! ```
@@ -324,12 +335,17 @@ use alloc::owned::Box;
 use core::cell::Cell;
 use core::kinds::marker;
 use core::mem;
+use core::num::Saturating;
 use core::ty::Unsafe;
 use rustrt::local::Local;
+use rustrt::rtio::IoError;
 use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
 
 pub use comm::select::{Select, Handle};
 pub use comm::duplex::{DuplexStream, duplex};
+pub use comm::mpmc::{MpmcSender, MpmcReceiver, MpmcMessages, mpmc_channel};
+pub use comm::broadcast::{Broadcaster, Subscriber, broadcast};
 
 macro_rules! test (
     { fn $name:ident() $b:block $(#[$a:meta])*} => (
@@ -357,7 +373,9 @@ macro_rules! test (
     )
 )
 
+mod broadcast;
 mod duplex;
+mod mpmc;
 mod oneshot;
 mod select;
 mod shared;
@@ -368,6 +386,17 @@ mod sync;
 // division, this is hit pretty regularly.
 static RESCHED_FREQ: int = 256;
 
+// The timeout methods block through `Task::deschedule_timeout`, which fails to
+// set anything up on runtimes which have no way of timing out (such as a green
+// scheduler without an event loop). There's nothing sensible to return to the
+// caller in that case, so it's turned into task failure.
+fn timeout_unavailable(err: IoError) -> ! {
+    match err.detail {
+        Some(ref detail) => fail!("unable to block with a timeout: {}", detail),
+        None => fail!("unable to block with a timeout"),
+    }
+}
+
 /// The receiving-half of Rust's channel type. This half can only be owned by
 /// one task
 #[unstable]
@@ -774,6 +803,24 @@ impl<T: Send> SyncSender<T> {
     pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
         unsafe { (*self.inner.get()).try_send(t) }
     }
+
+    /// Attempts to send a value on this channel, blocking for at most
+    /// `timeout_ms` milliseconds.
+    ///
+    /// This behaves as `send_opt` does, except that if the value could not be
+    /// sent before the timeout elapses then it's returned back in the `Full`
+    /// variant, as with `try_send`. For a channel with a buffer size of 0 this
+    /// means no receiver took the value in time.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if the current task's runtime is unable to time
+    /// out, for example a green task whose scheduler has no event loop.
+    #[experimental = "the timeout argument may become a duration"]
+    pub fn send_timeout(&self, t: T,
+                        timeout_ms: u64) -> Result<(), TrySendError<T>> {
+        unsafe { (*self.inner.get()).send_timeout(t, timeout_ms) }
+    }
 }
 
 #[unstable]
@@ -891,6 +938,45 @@ impl<T: Send> Receiver<T> {
         }
     }
 
+    /// Blocks waiting for a value on this receiver for at most `timeout_ms`
+    /// milliseconds.
+    ///
+    /// If a value arrives in time then it's returned, otherwise `Empty` is
+    /// returned once the timeout has elapsed. As with `try_recv`,
+    /// `Disconnected` is returned if the channel has hung up.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if the current task's runtime is unable to time
+    /// out, for example a green task whose scheduler has no event loop.
+    #[experimental = "the timeout argument may become a duration"]
+    pub fn recv_timeout(&self, timeout_ms: u64) -> Result<T, TryRecvError> {
+        use comm::select::Packet;
+
+        let deadline = time::precise_time_ms().saturating_add(timeout_ms);
+        loop {
+            match self.try_recv() {
+                Err(Empty) => {}
+                ret => return ret,
+            }
+            let now = time::precise_time_ms();
+            if now >= deadline { return Err(Empty) }
+
+            // Block in the same way as selecting over just this receiver. Once
+            // we're awake again, whether because of data, a disconnect or the
+            // timeout, the selection is aborted and we go back around to see
+            // which one it was.
+            let task: Box<Task> = Local::take();
+            match task.deschedule_timeout(1, deadline - now, |task| {
+                self.start_selection(task)
+            }) {
+                Ok(()) => {}
+                Err(e) => timeout_unavailable(e),
+            }
+            self.abort_selection();
+        }
+    }
+
     /// Attempt to wait for a value on this receiver, but does not fail if the
     /// corresponding channel has hung up.
     ///
@@ -1584,6 +1670,67 @@ mod test {
         t.join();
         pdone.recv();
     })
+
+    test!(fn recv_timeout_empty() {
+        let (tx, rx) = channel::<int>();
+        assert_eq!(rx.recv_timeout(0), Err(Empty));
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        tx.send(1);
+        tx.send(2);
+        assert_eq!(rx.recv_timeout(10), Ok(1));
+        assert_eq!(rx.recv_timeout(0), Ok(2));
+        drop(tx);
+        assert_eq!(rx.recv_timeout(10), Err(Disconnected));
+    })
+
+    test!(fn recv_timeout_flavors() {
+        // oneshot
+        let (tx, rx) = channel::<int>();
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx.send(1);
+        });
+        assert_eq!(rx.recv_timeout(60000), Ok(1));
+
+        // stream
+        let (tx, rx) = channel::<int>();
+        tx.send(1);
+        assert_eq!(rx.recv(), 1);
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx.send(2);
+        });
+        assert_eq!(rx.recv_timeout(60000), Ok(2));
+        assert_eq!(rx.recv_timeout(60000), Err(Disconnected));
+
+        // shared
+        let (tx, rx) = channel::<int>();
+        let tx2 = tx.clone();
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx.send(3);
+        });
+        assert_eq!(rx.recv_timeout(60000), Ok(3));
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        drop(tx2);
+        assert_eq!(rx.recv_timeout(60000), Err(Disconnected));
+    })
+
+    test!(fn recv_timeout_upgrade() {
+        let (tx, rx) = channel::<int>();
+        let (done_tx, done_rx) = channel();
+        spawn(proc() {
+            assert_eq!(rx.recv_timeout(60000), Ok(1));
+            assert_eq!(rx.recv_timeout(60000), Ok(2));
+            assert_eq!(rx.recv_timeout(10), Err(Empty));
+            done_tx.send(());
+        });
+        for _ in range(0u, 100) { task::deschedule() }
+        let tx2 = tx.clone();
+        tx.send(1);
+        tx2.send(2);
+        done_rx.recv();
+    })
 }
 
 #[cfg(test)]
@@ -2098,4 +2245,56 @@ mod sync_tests {
         });
         assert_eq!(rx.recv(), 1);
     } #[ignore(reason = "flaky on libnative")])
+
+    test!(fn recv_timeout() {
+        let (tx, rx) = sync_channel::<int>(1);
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx.send(1);
+        });
+        assert_eq!(rx.recv_timeout(60000), Ok(1));
+        assert_eq!(rx.recv_timeout(60000), Err(Disconnected));
+    })
+
+    test!(fn send_timeout1() {
+        let (tx, rx) = sync_channel::<int>(0);
+        assert_eq!(tx.send_timeout(1, 0), Err(Full(1)));
+        assert_eq!(tx.send_timeout(1, 10), Err(Full(1)));
+        assert_eq!(rx.try_recv(), Err(Empty));
+        drop(rx);
+        assert_eq!(tx.send_timeout(1, 10), Err(RecvDisconnected(1)));
+    })
+
+    test!(fn send_timeout2() {
+        let (tx, rx) = sync_channel::<int>(1);
+        assert_eq!(tx.send_timeout(1, 10), Ok(()));
+        assert_eq!(tx.send_timeout(2, 10), Err(Full(2)));
+        assert_eq!(rx.recv(), 1);
+        assert_eq!(rx.try_recv(), Err(Empty));
+    })
+
+    test!(fn send_timeout3() {
+        let (tx, rx) = sync_channel::<int>(0);
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            assert_eq!(rx.recv(), 1);
+        });
+        assert_eq!(tx.send_timeout(1, 60000), Ok(()));
+    })
+
+    test!(fn send_timeout_wakes_next_sender() {
+        // A sender which times out while it's the blocked sender of a
+        // rendezvous channel must not strand the senders queued behind it.
+        let (tx, rx) = sync_channel::<int>(0);
+        let tx2 = tx.clone();
+        let (done_tx, done_rx) = channel();
+        spawn(proc() {
+            tx2.send(2);
+            done_tx.send(());
+        });
+        assert_eq!(tx.send_timeout(1, 50), Err(Full(1)));
+        assert_eq!(rx.recv(), 2);
+        done_rx.recv();
+    })
 }
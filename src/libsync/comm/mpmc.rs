@@ -0,0 +1,687 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Multi-producer, multi-consumer channels
+//!
+//! These channels are a bounded buffer built on the lock-free queue found in
+//! `mpmc_bounded_queue`. Both halves of the channel can be cloned, and each
+//! message sent is received by exactly one of the receivers.
+//!
+//! Sending and receiving never take a lock as long as there's room in the
+//! buffer and data to receive. A mutex is only used to keep track of the tasks
+//! which are blocked on the channel, and it's only taken by other tasks when
+//! some task is known to be blocked.
+//!
+//! The lists of blocked tasks are guarded by the mutex, but the number of
+//! blocked tasks is also available atomically. A task which is about to block
+//! adds itself to a list and then checks the queue again, while a task which
+//! has just pushed or popped checks the number of blocked tasks. There is a
+//! sequentially consistent fence on each side, so at least one of them will
+//! notice the other.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use alloc::owned::Box;
+use collections::Vec;
+use core::kinds::marker;
+use core::mem;
+use core::ty::Unsafe;
+use rustrt::local::Local;
+use rustrt::mutex::{NativeMutex, LockGuard};
+use rustrt::task::{Task, BlockedTask};
+
+use atomics;
+use comm::{TryRecvError, Empty, Disconnected};
+use comm::{TrySendError, Full, RecvDisconnected};
+use comm::select;
+use mpmc_bounded_queue::Queue;
+
+/// The sending half of a multi-producer, multi-consumer channel. This half can
+/// be cloned to send to the channel from many tasks.
+pub struct MpmcSender<T> {
+    inner: Arc<Unsafe<Packet<T>>>,
+    // can't share in an arc
+    marker: marker::NoShare,
+}
+
+/// The receiving half of a multi-producer, multi-consumer channel. This half
+/// can be cloned to receive from the channel in many tasks, and each message is
+/// received by only one of them.
+pub struct MpmcReceiver<T> {
+    inner: Arc<Unsafe<Packet<T>>>,
+    // identifies this receiver in the list of blocked receivers
+    id: uint,
+    // can't share in an arc
+    marker: marker::NoShare,
+}
+
+/// Creates a new multi-producer, multi-consumer channel with a buffer of at
+/// least `bound` messages.
+///
+/// The buffer's size is always a power of two, and at least two, so `bound` is
+/// rounded up accordingly. Sends will block while the buffer is full.
+///
+/// # Example
+///
+/// ```
+/// use std::comm::mpmc_channel;
+///
+/// let (tx, rx) = mpmc_channel(16);
+/// for _ in range(0u, 4) {
+///     let rx = rx.clone();
+///     spawn(proc() {
+///         for job in rx.iter() {
+///             println!("working on job {}", job);
+///         }
+///     });
+/// }
+/// for i in range(0u, 100) {
+///     tx.send(i);
+/// }
+/// ```
+pub fn mpmc_channel<T: Send>(bound: uint) -> (MpmcSender<T>, MpmcReceiver<T>) {
+    let packet = Arc::new(Unsafe::new(Packet::new(bound)));
+    let id = unsafe { (*packet.get()).next_id() };
+    (MpmcSender { inner: packet.clone(), marker: marker::NoShare },
+     MpmcReceiver { inner: packet, id: id, marker: marker::NoShare })
+}
+
+struct Packet<T> {
+    queue: Queue<T>,
+    senders: atomics::AtomicUint,
+    receivers: atomics::AtomicUint,
+    next_id: atomics::AtomicUint,
+
+    // The number of tasks in each of the lists in `state`. These are only
+    // modified with the lock held, but they're read without it so tasks can
+    // avoid the lock when nobody is blocked.
+    blocked_receivers: atomics::AtomicUint,
+    blocked_senders: atomics::AtomicUint,
+
+    lock: NativeMutex,
+    state: Unsafe<State>,
+}
+
+struct State {
+    receivers: Vec<(uint, BlockedTask)>,
+    senders: Vec<BlockedTask>,
+}
+
+impl<T: Send> Packet<T> {
+    fn new(bound: uint) -> Packet<T> {
+        Packet {
+            queue: Queue::with_capacity(bound),
+            senders: atomics::AtomicUint::new(1),
+            receivers: atomics::AtomicUint::new(1),
+            next_id: atomics::AtomicUint::new(0),
+            blocked_receivers: atomics::AtomicUint::new(0),
+            blocked_senders: atomics::AtomicUint::new(0),
+            lock: unsafe { NativeMutex::new() },
+            state: Unsafe::new(State {
+                receivers: Vec::new(),
+                senders: Vec::new(),
+            }),
+        }
+    }
+
+    // See sync.rs for why it's ok to hand out the state like this.
+    fn lock<'a>(&'a self) -> (LockGuard<'a>, &'a mut State) {
+        unsafe {
+            let guard = self.lock.lock();
+            (guard, &mut *self.state.get())
+        }
+    }
+
+    fn next_id(&self) -> uint {
+        self.next_id.fetch_add(1, atomics::SeqCst)
+    }
+
+    fn send(&self, t: T) -> Result<(), T> {
+        let mut t = t;
+        loop {
+            match self.try_send(t) {
+                Ok(()) => return Ok(()),
+                Err(RecvDisconnected(t)) => return Err(t),
+                Err(Full(data)) => t = data,
+            }
+            let task: Box<Task> = Local::take();
+            task.deschedule(1, |task| self.block_sender(task));
+        }
+    }
+
+    fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        if self.receivers.load(atomics::SeqCst) == 0 {
+            return Err(RecvDisconnected(t))
+        }
+        match self.queue.push_opt(t) {
+            Ok(()) => { self.wake_receiver(); Ok(()) }
+            Err(t) => Err(Full(t)),
+        }
+    }
+
+    fn recv(&self, id: uint) -> Result<T, ()> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(Disconnected) => return Err(()),
+                Err(Empty) => {}
+            }
+            let task: Box<Task> = Local::take();
+            task.deschedule(1, |task| self.block_receiver(id, task));
+        }
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.queue.pop() {
+            Some(t) => { self.wake_sender(); Ok(t) }
+
+            // The last sender may have pushed some data just before it went
+            // away, and now that no more data can arrive the queue needs to be
+            // checked once more.
+            None if self.senders.load(atomics::SeqCst) == 0 => {
+                match self.queue.pop() {
+                    Some(t) => Ok(t),
+                    None => Err(Disconnected),
+                }
+            }
+
+            None => Err(Empty),
+        }
+    }
+
+    // Adds a receiver to the list of blocked receivers, unless there's data
+    // available or the channel has disconnected in the meantime, in which case
+    // the task is handed back.
+    fn block_receiver(&self, id: uint,
+                      task: BlockedTask) -> Result<(), BlockedTask> {
+        let (_g, state) = self.lock();
+        state.receivers.push((id, task));
+        self.blocked_receivers.fetch_add(1, atomics::SeqCst);
+        atomics::fence(atomics::SeqCst);
+        if self.queue.is_empty() && self.senders.load(atomics::SeqCst) != 0 {
+            Ok(())
+        } else {
+            self.blocked_receivers.fetch_sub(1, atomics::SeqCst);
+            let (_, task) = state.receivers.pop().unwrap();
+            Err(task)
+        }
+    }
+
+    // The same as `block_receiver`, but waiting for room in the buffer.
+    fn block_sender(&self, task: BlockedTask) -> Result<(), BlockedTask> {
+        let (_g, state) = self.lock();
+        state.senders.push(task);
+        self.blocked_senders.fetch_add(1, atomics::SeqCst);
+        atomics::fence(atomics::SeqCst);
+        if self.queue.is_full() && self.receivers.load(atomics::SeqCst) != 0 {
+            Ok(())
+        } else {
+            self.blocked_senders.fetch_sub(1, atomics::SeqCst);
+            Err(state.senders.pop().unwrap())
+        }
+    }
+
+    // Wakes up the receiver which has been blocked the longest, if any.
+    //
+    // A selecting receiver may have already been woken up by some other
+    // channel, in which case waking it up fails and the next receiver in line
+    // is woken up instead, so the data doesn't sit in the buffer while others
+    // are blocked.
+    fn wake_receiver(&self) {
+        atomics::fence(atomics::SeqCst);
+        while self.blocked_receivers.load(atomics::SeqCst) != 0 {
+            let task = {
+                let (_g, state) = self.lock();
+                if state.receivers.len() == 0 { return }
+                self.blocked_receivers.fetch_sub(1, atomics::SeqCst);
+                let (_, task) = state.receivers.remove(0).unwrap();
+                task
+            };
+            match task.wake() {
+                Some(task) => { task.reawaken(); return }
+                None => {}
+            }
+        }
+    }
+
+    // The same as `wake_receiver`, but for a sender waiting for room.
+    fn wake_sender(&self) {
+        atomics::fence(atomics::SeqCst);
+        while self.blocked_senders.load(atomics::SeqCst) != 0 {
+            let task = {
+                let (_g, state) = self.lock();
+                if state.senders.len() == 0 { return }
+                self.blocked_senders.fetch_sub(1, atomics::SeqCst);
+                state.senders.remove(0).unwrap()
+            };
+            match task.wake() {
+                Some(task) => { task.reawaken(); return }
+                None => {}
+            }
+        }
+    }
+
+    fn clone_chan(&self) {
+        self.senders.fetch_add(1, atomics::SeqCst);
+    }
+
+    fn clone_port(&self) {
+        self.receivers.fetch_add(1, atomics::SeqCst);
+    }
+
+    // Once the last sender is gone, all blocked receivers are woken up to see
+    // the disconnection.
+    fn drop_chan(&self) {
+        match self.senders.fetch_sub(1, atomics::SeqCst) {
+            1 => {}
+            _ => return
+        }
+        let receivers = {
+            let (_g, state) = self.lock();
+            self.blocked_receivers.store(0, atomics::SeqCst);
+            mem::replace(&mut state.receivers, Vec::new())
+        };
+        for (_, task) in receivers.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    // Once the last receiver is gone, all blocked senders are woken up to see
+    // the disconnection. Any data left in the buffer is destroyed along with
+    // the packet.
+    fn drop_port(&self) {
+        match self.receivers.fetch_sub(1, atomics::SeqCst) {
+            1 => {}
+            _ => return
+        }
+        let senders = {
+            let (_g, state) = self.lock();
+            self.blocked_senders.store(0, atomics::SeqCst);
+            mem::replace(&mut state.senders, Vec::new())
+        };
+        for task in senders.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // select implementation
+    ////////////////////////////////////////////////////////////////////////////
+
+    fn can_recv(&self) -> bool {
+        !self.queue.is_empty() || self.senders.load(atomics::SeqCst) == 0
+    }
+
+    // Removes a selecting receiver from the list of blocked receivers. If it's
+    // no longer there then a sender has taken it to wake it up, but the data
+    // which the sender pushed may not end up being received by this receiver
+    // (the selection may pick another handle). The wakeup is passed on to
+    // another blocked receiver in that case. Either way, whether this handle is
+    // ready depends on what's left in the buffer, as another receiver may have
+    // taken the data since we were woken up.
+    fn abort_selection(&self, id: uint) -> bool {
+        let removed = {
+            let (_g, state) = self.lock();
+            match state.receivers.iter().position(|&(i, _)| i == id) {
+                Some(i) => {
+                    self.blocked_receivers.fetch_sub(1, atomics::SeqCst);
+                    let (_, task) = state.receivers.remove(i).unwrap();
+                    task.trash();
+                    true
+                }
+                None => false,
+            }
+        };
+        if !removed && !self.queue.is_empty() { self.wake_receiver() }
+        self.can_recv()
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for Packet<T> {
+    fn drop(&mut self) {
+        assert_eq!(self.senders.load(atomics::SeqCst), 0);
+        assert_eq!(self.receivers.load(atomics::SeqCst), 0);
+        let (_g, state) = self.lock();
+        assert!(state.receivers.len() == 0);
+        assert!(state.senders.len() == 0);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// MpmcSender
+////////////////////////////////////////////////////////////////////////////////
+
+impl<T: Send> MpmcSender<T> {
+    /// Sends a value on this channel.
+    ///
+    /// This function will *block* until there's room in the buffer for the
+    /// value. It's received by whichever receiver gets to it first.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if all receivers for this channel have
+    /// disconnected. The `send_opt` method can be used to avoid failing.
+    pub fn send(&self, t: T) {
+        if self.send_opt(t).is_err() {
+            fail!("sending on a closed channel");
+        }
+    }
+
+    /// Sends a value on this channel, returning it back if all receivers have
+    /// disconnected.
+    ///
+    /// This function will *block* until there's room in the buffer for the
+    /// value.
+    ///
+    /// # Failure
+    ///
+    /// This function cannot fail.
+    pub fn send_opt(&self, t: T) -> Result<(), T> {
+        unsafe { (*self.inner.get()).send(t) }
+    }
+
+    /// Attempts to send a value on this channel without blocking, returning
+    /// it back in the `Full` variant if the buffer is full.
+    ///
+    /// # Failure
+    ///
+    /// This function cannot fail.
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        unsafe { (*self.inner.get()).try_send(t) }
+    }
+}
+
+impl<T: Send> Clone for MpmcSender<T> {
+    fn clone(&self) -> MpmcSender<T> {
+        unsafe { (*self.inner.get()).clone_chan(); }
+        MpmcSender { inner: self.inner.clone(), marker: marker::NoShare }
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for MpmcSender<T> {
+    fn drop(&mut self) {
+        unsafe { (*self.inner.get()).drop_chan(); }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// MpmcReceiver
+////////////////////////////////////////////////////////////////////////////////
+
+impl<T: Send> MpmcReceiver<T> {
+    /// Blocks waiting for a value on this receiver.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if all senders have disconnected and there's no
+    /// more data to receive. The `recv_opt` method can be used to avoid
+    /// failing.
+    pub fn recv(&self) -> T {
+        match self.recv_opt() {
+            Ok(t) => t,
+            Err(()) => fail!("receiving on a closed channel"),
+        }
+    }
+
+    /// Blocks waiting for a value on this receiver, returning `Err` if all
+    /// senders have disconnected and there's no more data to receive.
+    pub fn recv_opt(&self) -> Result<T, ()> {
+        unsafe { (*self.inner.get()).recv(self.id) }
+    }
+
+    /// Attempts to receive a value on this receiver without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        unsafe { (*self.inner.get()).try_recv() }
+    }
+
+    /// Returns an iterator which will block waiting for messages, returning
+    /// `None` once all senders have disconnected.
+    pub fn iter<'a>(&'a self) -> MpmcMessages<'a, T> {
+        MpmcMessages { rx: self }
+    }
+}
+
+/// An iterator over messages on an `MpmcReceiver`, created by its `iter`
+/// method.
+pub struct MpmcMessages<'a, T> {
+    rx: &'a MpmcReceiver<T>,
+}
+
+impl<'a, T: Send> Iterator<T> for MpmcMessages<'a, T> {
+    fn next(&mut self) -> Option<T> { self.rx.recv_opt().ok() }
+}
+
+impl<T: Send> Clone for MpmcReceiver<T> {
+    fn clone(&self) -> MpmcReceiver<T> {
+        let packet = unsafe { &*self.inner.get() };
+        packet.clone_port();
+        MpmcReceiver {
+            inner: self.inner.clone(),
+            id: packet.next_id(),
+            marker: marker::NoShare,
+        }
+    }
+}
+
+impl<T: Send> select::Packet for MpmcReceiver<T> {
+    fn can_recv(&self) -> bool {
+        unsafe { (*self.inner.get()).can_recv() }
+    }
+
+    fn start_selection(&self, task: BlockedTask) -> Result<(), BlockedTask> {
+        unsafe { (*self.inner.get()).block_receiver(self.id, task) }
+    }
+
+    fn abort_selection(&self) -> bool {
+        unsafe { (*self.inner.get()).abort_selection(self.id) }
+    }
+}
+
+impl<T: Send> select::Receivable<T> for MpmcReceiver<T> {
+    fn recv_opt(&self) -> Result<T, ()> { self.recv_opt() }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for MpmcReceiver<T> {
+    fn drop(&mut self) {
+        unsafe { (*self.inner.get()).drop_port(); }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::*;
+    use std::os;
+
+    pub fn stress_factor() -> uint {
+        match os::getenv("RUST_TEST_STRESS") {
+            Some(val) => from_str::<uint>(val.as_slice()).unwrap(),
+            None => 1,
+        }
+    }
+
+    test!(fn smoke() {
+        let (tx, rx) = mpmc_channel::<int>(1);
+        tx.send(1);
+        assert_eq!(rx.recv(), 1);
+    })
+
+    test!(fn try_send_full() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        assert_eq!(tx.try_send(1), Ok(()));
+        assert_eq!(tx.try_send(2), Ok(()));
+        assert_eq!(tx.try_send(3), Err(Full(3)));
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(tx.try_send(3), Ok(()));
+        drop(rx);
+        assert_eq!(tx.try_send(4), Err(RecvDisconnected(4)));
+    })
+
+    test!(fn try_recv_states() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        assert_eq!(rx.try_recv(), Err(Empty));
+        tx.send(1);
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Err(Disconnected));
+        assert_eq!(rx.recv_opt(), Err(()));
+    })
+
+    test!(fn port_gone() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        drop(rx);
+        assert_eq!(tx.send_opt(1), Err(1));
+    })
+
+    test!(fn smoke_fail() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        drop(rx);
+        tx.send(1);
+    } #[should_fail])
+
+    test!(fn blocked_sender_woken_by_recv() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        let (done_tx, done_rx) = channel();
+        tx.send(1);
+        tx.send(2);
+        spawn(proc() {
+            tx.send(3);
+            done_tx.send(());
+        });
+        for _ in range(0u, 100) { task::deschedule() }
+        assert_eq!(rx.recv(), 1);
+        done_rx.recv();
+        assert_eq!(rx.recv(), 2);
+        assert_eq!(rx.recv(), 3);
+        assert_eq!(rx.recv_opt(), Err(()));
+    })
+
+    test!(fn blocked_receivers_disconnect() {
+        let (tx, rx) = mpmc_channel::<int>(2);
+        let (done_tx, done_rx) = channel();
+        for _ in range(0u, 4) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            spawn(proc() {
+                assert_eq!(rx.recv_opt(), Err(()));
+                done_tx.send(());
+            });
+        }
+        drop(rx);
+        for _ in range(0u, 100) { task::deschedule() }
+        drop(tx);
+        for _ in range(0u, 4) { done_rx.recv(); }
+    })
+
+    test!(fn many_senders_many_receivers() {
+        let amt = 1000 * stress_factor();
+        let (tx, rx) = mpmc_channel::<uint>(8);
+        let (total_tx, total_rx) = channel();
+        for _ in range(0u, 4) {
+            let rx = rx.clone();
+            let total_tx = total_tx.clone();
+            spawn(proc() {
+                let mut n = 0u;
+                for i in rx.iter() { n += i; }
+                total_tx.send(n);
+            });
+        }
+        drop(rx);
+        for _ in range(0u, 4) {
+            let tx = tx.clone();
+            spawn(proc() {
+                for i in range(0, amt) { tx.send(i); }
+            });
+        }
+        drop(tx);
+        let mut total = 0u;
+        for _ in range(0u, 4) { total += total_rx.recv(); }
+        assert_eq!(total, 4 * amt * (amt - 1) / 2);
+    })
+
+    test!(fn select_with_other_channels() {
+        let (tx1, rx1) = mpmc_channel::<int>(2);
+        let (tx2, rx2) = channel::<int>();
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx1.send(1);
+        });
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h2 = s.handle(&rx2);
+        unsafe { h1.add(); h2.add(); }
+        assert_eq!(s.wait(), h1.id());
+        assert_eq!(h1.recv(), 1);
+        tx2.send(2);
+        assert_eq!(s.wait(), h2.id());
+        assert_eq!(h2.recv(), 2);
+        assert_eq!(s.wait(), h1.id());
+        assert_eq!(h1.recv_opt(), Err(()));
+    })
+
+    test!(fn selecting_receivers_share_data() {
+        // Receivers blocked in select must not strand data in the buffer when
+        // the selection picks a different handle.
+        let (tx, rx) = mpmc_channel::<int>(2);
+        let (done_tx, done_rx) = channel();
+        for _ in range(0u, 2) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            spawn(proc() {
+                let (_other_tx, other_rx) = channel::<int>();
+                let s = Select::new();
+                let mut h1 = s.handle(&rx);
+                let mut h2 = s.handle(&other_rx);
+                unsafe { h1.add(); h2.add(); }
+                let id = s.wait();
+                assert_eq!(id, h1.id());
+                done_tx.send(h1.recv());
+            });
+        }
+        drop(rx);
+        for _ in range(0u, 100) { task::deschedule() }
+        tx.send(1);
+        tx.send(2);
+        let a = done_rx.recv();
+        let b = done_rx.recv();
+        assert_eq!(a + b, 3);
+    })
+    test!(fn select_after_data_was_taken() {
+        // The selecting task can be woken up for the mpmc channel with another
+        // receiver taking the data before the selection is aborted. The mpmc
+        // handle mustn't be reported as ready then, and the selection has to
+        // carry on to the plain channel which fires afterwards.
+        let (tx, rx) = mpmc_channel::<int>(1);
+        let rx2 = rx.clone();
+        let (tx2, rx3) = channel();
+        let (done_tx, done_rx) = channel();
+        spawn(proc() {
+            let s = Select::new();
+            let mut h1 = s.handle(&rx3);
+            let mut h2 = s.handle(&rx);
+            unsafe { h1.add(); h2.add(); }
+            if s.wait() == h1.id() {
+                assert_eq!(h1.recv(), 2);
+                done_tx.send(rx.try_recv().is_ok());
+            } else {
+                done_tx.send(h2.recv_opt().is_ok());
+            }
+        });
+        for _ in range(0u, 100) { task::deschedule() }
+        tx.send(1);
+        let taken = rx2.try_recv().is_ok();
+        tx2.send(2);
+        drop(tx);
+        assert!(taken != done_rx.recv());
+    })
+}
@@ -58,9 +58,11 @@ use alloc::owned::Box;
 use core::cell::Cell;
 use core::kinds::marker;
 use core::mem;
+use core::num::Saturating;
 use core::uint;
 use rustrt::local::Local;
 use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
 
 use comm::Receiver;
 
@@ -88,7 +90,7 @@ pub struct Handle<'rx, T> {
 
     // due to our fun transmutes, we be sure to place this at the end. (nothing
     // previous relies on T)
-    rx: &'rx Receiver<T>,
+    rx: &'rx Receivable<T>,
 }
 
 struct Packets { cur: *mut Handle<'static, ()> }
@@ -100,6 +102,18 @@ pub trait Packet {
     fn abort_selection(&self) -> bool;
 }
 
+/// The receiving half of a channel which can be a member of a `Select` set.
+/// This is implemented by `Receiver` as well as the receiving halves of the
+/// other kinds of channels in this module.
+#[doc(hidden)]
+pub trait Receivable<T>: Packet {
+    fn recv_opt(&self) -> Result<T, ()>;
+}
+
+impl<T: Send> Receivable<T> for Receiver<T> {
+    fn recv_opt(&self) -> Result<T, ()> { self.recv_opt() }
+}
+
 impl Select {
     /// Creates a new selection structure. This set is initially empty and
     /// `wait` will fail!() if called.
@@ -118,7 +132,8 @@ impl Select {
     /// Creates a new handle into this receiver set for a new receiver. Note
     /// that this does *not* add the receiver to the receiver set, for that you
     /// must call the `add` method on the handle itself.
-    pub fn handle<'a, T: Send>(&'a self, rx: &'a Receiver<T>) -> Handle<'a, T> {
+    pub fn handle<'a, T: Send, R: Receivable<T> + Send>(&'a self, rx: &'a R)
+                                                       -> Handle<'a, T> {
         let id = self.next_id.get();
         self.next_id.set(id + 1);
         Handle {
@@ -142,8 +157,27 @@ impl Select {
         self.wait2(true)
     }
 
+    /// Waits for an event on this receiver set as `wait` does, but for at most
+    /// `timeout_ms` milliseconds.
+    ///
+    /// If an event happens in time then the id of the handle with the event is
+    /// returned, otherwise `None` is returned once the timeout has elapsed.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if the current task's runtime is unable to time
+    /// out, as `Receiver::recv_timeout` does.
+    pub fn wait_timeout(&self, timeout_ms: u64) -> Option<uint> {
+        self.select(true, Some(timeout_ms))
+    }
+
     /// Helper method for skipping the preflight checks during testing
     fn wait2(&self, do_preflight_checks: bool) -> uint {
+        self.select(do_preflight_checks, None).unwrap()
+    }
+
+    fn select(&self, do_preflight_checks: bool,
+              timeout_ms: Option<u64>) -> Option<uint> {
         // Note that this is currently an inefficient implementation. We in
         // theory have knowledge about all receivers in the set ahead of time,
         // so this method shouldn't really have to iterate over all of them yet
@@ -169,61 +203,88 @@ impl Select {
             for p in self.iter() {
                 amt += 1;
                 if do_preflight_checks && (*p).packet.can_recv() {
-                    return (*p).id;
+                    return Some((*p).id);
                 }
             }
             assert!(amt > 0);
 
-            let mut ready_index = amt;
-            let mut ready_id = uint::MAX;
-            let mut iter = self.iter().enumerate();
-
-            // Acquire a number of blocking contexts, and block on each one
-            // sequentially until one fails. If one fails, then abort
-            // immediately so we can go unblock on all the other receivers.
-            let task: Box<Task> = Local::take();
-            task.deschedule(amt, |task| {
-                // Prepare for the block
-                let (i, handle) = iter.next().unwrap();
-                match (*handle).packet.start_selection(task) {
-                    Ok(()) => Ok(()),
-                    Err(task) => {
-                        ready_index = i;
+            let deadline = timeout_ms.map(|ms| {
+                time::precise_time_ms().saturating_add(ms)
+            });
+            let mut timeout_ms = timeout_ms;
+            loop {
+                let mut ready_index = amt;
+                let mut ready_id = uint::MAX;
+                let mut iter = self.iter().enumerate();
+
+                // Acquire a number of blocking contexts, and block on each one
+                // sequentially until one fails. If one fails, then abort
+                // immediately so we can go unblock on all the other receivers.
+                let task: Box<Task> = Local::take();
+                let block = |task: BlockedTask| {
+                    // Prepare for the block
+                    let (i, handle) = iter.next().unwrap();
+                    match (*handle).packet.start_selection(task) {
+                        Ok(()) => Ok(()),
+                        Err(task) => {
+                            ready_index = i;
+                            ready_id = (*handle).id;
+                            Err(task)
+                        }
+                    }
+                };
+                match timeout_ms {
+                    Some(ms) => match task.deschedule_timeout(amt, ms, block) {
+                        Ok(()) => {}
+                        Err(e) => super::timeout_unavailable(e),
+                    },
+                    None => task.deschedule(amt, block),
+                }
+
+                // Abort the selection process on each receiver. If the abort
+                // process returns `true`, then that means that the receiver is
+                // ready to receive some data. Note that this also means that
+                // the receiver may have yet to have fully read the `to_wake`
+                // field and woken us up (although the wakeup is guaranteed to
+                // fail).
+                //
+                // This situation happens in the window of where a sender
+                // invokes increment(), sees -1, and then decides to wake up the
+                // task. After all this is done, the sending thread will set
+                // `selecting` to `false`. Until this is done, we cannot return.
+                // If we were to return, then a sender could wake up a receiver
+                // which has gone back to sleep after this call to `select`.
+                //
+                // Note that it is a "fairly small window" in which an
+                // increment() views that it should wake a thread up until the
+                // `selecting` bit is set to false. For now, the implementation
+                // currently just spins in a yield loop. This is very
+                // distasteful, but this implementation is already nowhere near
+                // what it should ideally be. A rewrite should focus on avoiding
+                // a yield loop, and for now this implementation is tying us
+                // over to a more efficient "don't iterate over everything every
+                // time" implementation.
+                for handle in self.iter().take(ready_index) {
+                    if (*handle).packet.abort_selection() {
                         ready_id = (*handle).id;
-                        Err(task)
                     }
                 }
-            });
 
-            // Abort the selection process on each receiver. If the abort
-            // process returns `true`, then that means that the receiver is
-            // ready to receive some data. Note that this also means that the
-            // receiver may have yet to have fully read the `to_wake` field and
-            // woken us up (although the wakeup is guaranteed to fail).
-            //
-            // This situation happens in the window of where a sender invokes
-            // increment(), sees -1, and then decides to wake up the task. After
-            // all this is done, the sending thread will set `selecting` to
-            // `false`. Until this is done, we cannot return. If we were to
-            // return, then a sender could wake up a receiver which has gone
-            // back to sleep after this call to `select`.
-            //
-            // Note that it is a "fairly small window" in which an increment()
-            // views that it should wake a thread up until the `selecting` bit
-            // is set to false. For now, the implementation currently just spins
-            // in a yield loop. This is very distasteful, but this
-            // implementation is already nowhere near what it should ideally be.
-            // A rewrite should focus on avoiding a yield loop, and for now this
-            // implementation is tying us over to a more efficient "don't
-            // iterate over everything every time" implementation.
-            for handle in self.iter().take(ready_index) {
-                if (*handle).packet.abort_selection() {
-                    ready_id = (*handle).id;
+                if ready_id != uint::MAX { return Some(ready_id) }
+
+                // Nothing is ready, which can happen if the timeout elapsed or
+                // if we were woken up for data that was then taken by another
+                // receiver of the same channel (as with mpmc channels). Only
+                // the former means that we're done.
+                match deadline {
+                    Some(deadline) => {
+                        let now = time::precise_time_ms();
+                        if now >= deadline { return None }
+                        timeout_ms = Some(deadline - now);
+                    }
+                    None => {}
                 }
             }
-
-            assert!(ready_id != uint::MAX);
-            return ready_id;
         }
     }
 
@@ -237,7 +298,12 @@ impl<'rx, T: Send> Handle<'rx, T> {
 
     /// Receive a value on the underlying receiver. Has the same semantics as
     /// `Receiver.recv`
-    pub fn recv(&mut self) -> T { self.rx.recv() }
+    pub fn recv(&mut self) -> T {
+        match self.rx.recv_opt() {
+            Ok(t) => t,
+            Err(()) => fail!("receiving on a closed channel"),
+        }
+    }
     /// Block to receive a value on the underlying receiver, returning `Some` on
     /// success or `None` if the channel disconnects. This function has the same
     /// semantics as `Receiver.recv_opt`
@@ -708,4 +774,35 @@ mod test {
             }
         }
     })
+
+    test!(fn wait_timeout_elapses() {
+        let (_tx1, rx1) = channel::<int>();
+        let (_tx2, rx2) = sync_channel::<int>(0);
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h2 = s.handle(&rx2);
+        unsafe { h1.add(); h2.add(); }
+        assert_eq!(s.wait_timeout(10), None);
+        assert_eq!(s.wait_timeout(0), None);
+    })
+
+    test!(fn wait_timeout_ready() {
+        let (tx1, rx1) = channel::<int>();
+        let (tx2, rx2) = channel::<int>();
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h2 = s.handle(&rx2);
+        unsafe { h1.add(); h2.add(); }
+        tx2.send(2);
+        assert_eq!(s.wait_timeout(0), Some(h2.id()));
+        assert_eq!(h2.recv(), 2);
+
+        spawn(proc() {
+            for _ in range(0u, 100) { task::deschedule() }
+            tx1.send(1);
+        });
+        assert_eq!(s.wait_timeout(60000), Some(h1.id()));
+        assert_eq!(h1.recv(), 1);
+        assert_eq!(s.wait_timeout(10), None);
+    })
 }
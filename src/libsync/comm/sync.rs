@@ -39,10 +39,13 @@ use alloc::owned::Box;
 use collections::Vec;
 use collections::Collection;
 use core::mem;
+use core::num::Saturating;
 use core::ty::Unsafe;
 use rustrt::local::Local;
 use rustrt::mutex::{NativeMutex, LockGuard};
+use rustrt::rtio::IoResult;
 use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
 
 use atomics;
 
@@ -120,6 +123,25 @@ fn wait(slot: &mut Blocker, f: fn(BlockedTask) -> Blocker,
     unsafe { lock.lock_noguard(); }
 }
 
+/// As `wait`, but the task is woken up after at most `timeout_ms` milliseconds.
+/// If the timeout elapsed first then the task is still in `slot` upon returning
+/// and it's up to the caller to remove it. If the runtime can't time out at
+/// all then an error is returned straight away, with the lock still held.
+fn wait_timeout(slot: &mut Blocker, f: fn(BlockedTask) -> Blocker,
+                timeout_ms: u64, lock: &NativeMutex) -> IoResult<()> {
+    let me: Box<Task> = Local::take();
+    try!(me.deschedule_timeout(1, timeout_ms, |task| {
+        match mem::replace(slot, f(task)) {
+            NoneBlocked => {}
+            _ => unreachable!(),
+        }
+        unsafe { lock.unlock_noguard(); }
+        Ok(())
+    }));
+    unsafe { lock.lock_noguard(); }
+    Ok(())
+}
+
 /// Wakes up a task, dropping the lock at the correct time
 fn wakeup(task: BlockedTask, guard: LockGuard) {
     // We need to be careful to wake up the waiting task *outside* of the mutex
@@ -198,6 +220,71 @@ impl<T: Send> Packet<T> {
         }
     }
 
+    pub fn send_timeout(&self, t: T,
+                        timeout_ms: u64) -> Result<(), super::TrySendError<T>> {
+        let deadline = time::precise_time_ms().saturating_add(timeout_ms);
+        let (guard, state) = self.lock();
+
+        // wait for a slot to become available, giving up once the deadline
+        // has passed
+        while !state.disconnected && state.buf.size() == state.buf.cap() {
+            let now = time::precise_time_ms();
+            if now >= deadline { return Err(super::Full(t)) }
+            match state.queue.enqueue_timeout(&self.lock, deadline - now) {
+                Ok(()) => {}
+                Err(e) => super::timeout_unavailable(e),
+            }
+        }
+        if state.disconnected { return Err(super::RecvDisconnected(t)) }
+        state.buf.enqueue(t);
+
+        match mem::replace(&mut state.blocker, NoneBlocked) {
+            // As with `send`, a capacity of 0 means waiting for a receiver to
+            // take our data. If we're still the blocked sender once we've
+            // woken up then no receiver came along in time, so the data is
+            // taken back out of the buffer. This frees up the buffer, so the
+            // next sender in line (if any) is woken up to try its luck.
+            NoneBlocked if state.cap == 0 => {
+                let mut canceled = false;
+                assert!(state.canceled.is_none());
+                state.canceled = Some(unsafe { mem::transmute(&mut canceled) });
+                let timeout = deadline.saturating_sub(time::precise_time_ms());
+                match wait_timeout(&mut state.blocker, BlockedSender, timeout,
+                                   &self.lock) {
+                    Ok(()) => {}
+                    Err(e) => {
+                        state.canceled.take();
+                        mem::drop(state.buf.dequeue());
+                        super::timeout_unavailable(e)
+                    }
+                }
+                if canceled {
+                    return Err(super::RecvDisconnected(state.buf.dequeue()))
+                }
+                match mem::replace(&mut state.blocker, NoneBlocked) {
+                    NoneBlocked => Ok(()),
+                    BlockedSender(..) => {
+                        state.canceled.take();
+                        let t = state.buf.dequeue();
+                        let pending_sender = state.queue.dequeue();
+                        mem::drop((state, guard));
+                        pending_sender.map(|t| t.wake().map(|t| t.reawaken()));
+                        Err(super::Full(t))
+                    }
+                    BlockedReceiver(..) => unreachable!(),
+                }
+            }
+
+            // success, we buffered some data
+            NoneBlocked => Ok(()),
+
+            // success, someone's about to receive our buffered data.
+            BlockedReceiver(task) => { wakeup(task, guard); Ok(()) }
+
+            BlockedSender(..) => fail!("lolwut"),
+        }
+    }
+
     pub fn try_send(&self, t: T) -> Result<(), super::TrySendError<T>> {
         let (guard, state) = self.lock();
         if state.disconnected {
@@ -465,6 +552,60 @@ impl Queue {
         assert!(node.next.is_null());
     }
 
+    // As `enqueue`, but the task is woken up after at most `timeout_ms`
+    // milliseconds. If nobody dequeued the task in that time then its node is
+    // unlinked from the queue here, as it's about to go out of scope. As with
+    // `wait_timeout`, an error means that nothing happened.
+    fn enqueue_timeout(&mut self, lock: &NativeMutex,
+                       timeout_ms: u64) -> IoResult<()> {
+        let task: Box<Task> = Local::take();
+        let mut node = Node {
+            task: None,
+            next: 0 as *mut Node,
+        };
+        try!(task.deschedule_timeout(1, timeout_ms, |task| {
+            node.task = Some(task);
+            if self.tail.is_null() {
+                self.head = &mut node as *mut Node;
+                self.tail = &mut node as *mut Node;
+            } else {
+                unsafe {
+                    (*self.tail).next = &mut node as *mut Node;
+                    self.tail = &mut node as *mut Node;
+                }
+            }
+            unsafe { lock.unlock_noguard(); }
+            Ok(())
+        }));
+        unsafe { lock.lock_noguard(); }
+        if node.task.is_some() {
+            self.remove(&mut node as *mut Node);
+        }
+        assert!(node.next.is_null());
+        Ok(())
+    }
+
+    fn remove(&mut self, node: *mut Node) {
+        unsafe {
+            let mut prev = 0 as *mut Node;
+            let mut cur = self.head;
+            while cur != node {
+                assert!(!cur.is_null());
+                prev = cur;
+                cur = (*cur).next;
+            }
+            if prev.is_null() {
+                self.head = (*node).next;
+            } else {
+                (*prev).next = (*node).next;
+            }
+            if self.tail == node {
+                self.tail = prev;
+            }
+            (*node).next = 0 as *mut Node;
+        }
+    }
+
     fn dequeue(&mut self) -> Option<BlockedTask> {
         if self.head.is_null() {
             return None
@@ -0,0 +1,457 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A concurrent hash map
+//!
+//! `ConcurrentHashMap` splits its entries between a number of shards, each of
+//! which is a small hash table behind its own `RWLock`. An operation on a key
+//! only locks the shard which the key hashes to, so tasks working with
+//! different keys rarely contend, and tasks reading from the same shard never
+//! block each other. The locks are those from this crate, so the map works
+//! for both native and green tasks, and a task which fails in the middle of
+//! updating an entry poisons the shard holding it.
+//!
+//! All of the operations on the map only need a shared reference to it, so it
+//! is meant to be shared between tasks with an `Arc`. The closures passed to
+//! the `update`-style operations run with the shard's lock held, so they must
+//! not access the map themselves.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use std::sync::concurrent_hashmap::ConcurrentHashMap;
+//!
+//! let map = Arc::new(ConcurrentHashMap::new());
+//! let (tx, rx) = channel();
+//! for i in range(0u, 4) {
+//!     let (map, tx) = (map.clone(), tx.clone());
+//!     spawn(proc() {
+//!         map.insert(i, i * 2);
+//!         map.insert_or_update_with(100, 1, |_, count| *count += 1);
+//!         tx.send(());
+//!     });
+//! }
+//! for _ in range(0u, 4) { rx.recv(); }
+//! assert_eq!(map.get(&3), Some(6));
+//! assert_eq!(map.get(&100), Some(4));
+//! ```
+
+use core::prelude::*;
+
+use collections::Vec;
+use collections::hash::{Hash, Hasher};
+use collections::hash::sip::SipHasher;
+use core::mem;
+
+use atomics::{AtomicUint, SeqCst};
+use lock::{RWLock, RWLockReadGuard};
+
+static DEFAULT_SHARDS: uint = 16;
+static INITIAL_BUCKETS: uint = 8;
+
+/// A hash map which can be shared and updated between tasks. See the module
+/// documentation for details.
+///
+/// Unlike `HashMap`, the default hasher for this map uses fixed keys, as this
+/// crate has no source of randomness. Maps holding keys which come from
+/// untrusted input should be created with `with_hasher`, passing in a
+/// `RandomSipHasher`.
+pub struct ConcurrentHashMap<K, V, H = SipHasher> {
+    hasher: H,
+    shards: Vec<RWLock<Shard<K, V>>>,
+    len: AtomicUint,
+}
+
+// One of the independently locked parts of a map. This is a hash table with
+// separate chaining, which doubles its number of buckets whenever it holds as
+// many entries as it has buckets.
+struct Shard<K, V> {
+    buckets: Vec<Vec<Entry<K, V>>>,
+    len: uint,
+}
+
+struct Entry<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+impl<K: Eq, V> Shard<K, V> {
+    fn new() -> Shard<K, V> {
+        Shard {
+            buckets: Vec::from_fn(INITIAL_BUCKETS, |_| Vec::new()),
+            len: 0,
+        }
+    }
+
+    fn bucket(&self, hash: u64) -> uint {
+        (hash % self.buckets.len() as u64) as uint
+    }
+
+    fn find<'a>(&'a self, hash: u64, k: &K) -> Option<&'a V> {
+        self.buckets.get(self.bucket(hash)).iter()
+            .find(|e| e.hash == hash && e.key == *k)
+            .map(|e| &e.value)
+    }
+
+    fn find_mut<'a>(&'a mut self, hash: u64, k: &K) -> Option<&'a mut V> {
+        let idx = self.bucket(hash);
+        self.buckets.get_mut(idx).mut_iter()
+            .find(|e| e.hash == hash && e.key == *k)
+            .map(|e| &mut e.value)
+    }
+
+    // Inserts an entry for a key which isn't in the shard yet.
+    fn insert_new(&mut self, hash: u64, k: K, v: V) {
+        if self.len == self.buckets.len() { self.grow() }
+        let idx = self.bucket(hash);
+        self.buckets.get_mut(idx).push(Entry { hash: hash, key: k, value: v });
+        self.len += 1;
+    }
+
+    fn remove(&mut self, hash: u64, k: &K) -> Option<V> {
+        let idx = self.bucket(hash);
+        let bucket = self.buckets.get_mut(idx);
+        match bucket.iter().position(|e| e.hash == hash && e.key == *k) {
+            Some(i) => {
+                self.len -= 1;
+                bucket.swap_remove(i).map(|e| e.value)
+            }
+            None => None,
+        }
+    }
+
+    fn grow(&mut self) {
+        let buckets = Vec::from_fn(self.buckets.len() * 2, |_| Vec::new());
+        let old = mem::replace(&mut self.buckets, buckets);
+        for bucket in old.move_iter() {
+            for entry in bucket.move_iter() {
+                let idx = self.bucket(entry.hash);
+                self.buckets.get_mut(idx).push(entry);
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash + Send + Share, V: Send + Share> ConcurrentHashMap<K, V, SipHasher> {
+    /// Creates an empty map with the default number of shards.
+    pub fn new() -> ConcurrentHashMap<K, V, SipHasher> {
+        ConcurrentHashMap::with_hasher(SipHasher::new())
+    }
+
+    /// Creates an empty map which spreads its entries over `shards` shards.
+    /// More shards allow more tasks to update the map at the same time.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if `shards` is 0.
+    pub fn with_shards(shards: uint) -> ConcurrentHashMap<K, V, SipHasher> {
+        ConcurrentHashMap::with_hasher_and_shards(SipHasher::new(), shards)
+    }
+}
+
+impl<K: Eq + Hash<S> + Send + Share, V: Send + Share, S, H: Hasher<S>>
+        ConcurrentHashMap<K, V, H> {
+    /// Creates an empty map which uses `hasher` to hash its keys.
+    pub fn with_hasher(hasher: H) -> ConcurrentHashMap<K, V, H> {
+        ConcurrentHashMap::with_hasher_and_shards(hasher, DEFAULT_SHARDS)
+    }
+
+    /// Creates an empty map which uses `hasher` to hash its keys and spreads
+    /// its entries over `shards` shards.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if `shards` is 0.
+    pub fn with_hasher_and_shards(hasher: H, shards: uint)
+                                  -> ConcurrentHashMap<K, V, H> {
+        assert!(shards > 0);
+        ConcurrentHashMap {
+            hasher: hasher,
+            shards: Vec::from_fn(shards, |_| RWLock::new(Shard::new())),
+            len: AtomicUint::new(0),
+        }
+    }
+
+    // Returns the shard which a key belongs in, along with the key's hash
+    // within that shard.
+    fn locate<'a>(&'a self, k: &K) -> (&'a RWLock<Shard<K, V>>, u64) {
+        let hash = self.hasher.hash(k);
+        let shards = self.shards.len() as u64;
+        (self.shards.get((hash % shards) as uint), hash / shards)
+    }
+
+    /// Returns the number of entries in the map. Other tasks may change this
+    /// at any time, so it is only a snapshot.
+    pub fn len(&self) -> uint { self.len.load(SeqCst) }
+
+    /// Returns whether the map has no entries. Like `len`, this is only a
+    /// snapshot.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns whether the map has an entry for the key `k`.
+    pub fn contains_key(&self, k: &K) -> bool {
+        let (shard, hash) = self.locate(k);
+        let shard = shard.read();
+        shard.find(hash, k).is_some()
+    }
+
+    /// Calls `f` with a reference to the value for the key `k`, returning
+    /// what it returns, or `None` if there is no such entry.
+    pub fn find_with<R>(&self, k: &K, f: |&V| -> R) -> Option<R> {
+        let (shard, hash) = self.locate(k);
+        let shard = shard.read();
+        shard.find(hash, k).map(f)
+    }
+
+    /// Inserts a key-value pair into the map, returning the value which was
+    /// previously there, if any.
+    pub fn insert(&self, k: K, v: V) -> Option<V> {
+        let (shard, hash) = self.locate(&k);
+        let mut shard = shard.write();
+        match shard.find_mut(hash, &k) {
+            Some(old) => return Some(mem::replace(old, v)),
+            None => {}
+        }
+        shard.insert_new(hash, k, v);
+        self.len.fetch_add(1, SeqCst);
+        None
+    }
+
+    /// Removes the entry for the key `k`, returning its value if there was
+    /// one.
+    pub fn remove(&self, k: &K) -> Option<V> {
+        let (shard, hash) = self.locate(k);
+        let mut shard = shard.write();
+        let ret = shard.remove(hash, k);
+        if ret.is_some() {
+            self.len.fetch_sub(1, SeqCst);
+        }
+        ret
+    }
+
+    /// Calls `f` with a mutable reference to the value for the key `k`, as a
+    /// single atomic operation. Returns whether there was such an entry.
+    pub fn update(&self, k: &K, f: |&mut V|) -> bool {
+        let (shard, hash) = self.locate(k);
+        let mut shard = shard.write();
+        match shard.find_mut(hash, k) {
+            Some(v) => { f(v); true }
+            None => false,
+        }
+    }
+
+    /// Inserts the value `v` for the key `k` if there is no entry for it yet,
+    /// or otherwise calls `f` with the key and a mutable reference to the
+    /// existing value, as a single atomic operation.
+    pub fn insert_or_update_with(&self, k: K, v: V, f: |&K, &mut V|) {
+        let (shard, hash) = self.locate(&k);
+        let mut shard = shard.write();
+        match shard.find_mut(hash, &k) {
+            Some(old) => return f(&k, old),
+            None => {}
+        }
+        shard.insert_new(hash, k, v);
+        self.len.fetch_add(1, SeqCst);
+    }
+
+    /// Removes the entry for the key `k` if `f` returns true when given its
+    /// value, as a single atomic operation. Returns the removed value.
+    pub fn remove_if(&self, k: &K, f: |&V| -> bool) -> Option<V> {
+        let (shard, hash) = self.locate(k);
+        let mut shard = shard.write();
+        let remove = match shard.find(hash, k) {
+            Some(v) => f(v),
+            None => false,
+        };
+        if !remove { return None }
+        self.len.fetch_sub(1, SeqCst);
+        shard.remove(hash, k)
+    }
+
+    /// Removes every entry from the map.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.write();
+            self.len.fetch_sub(shard.len, SeqCst);
+            *shard = Shard::new();
+        }
+    }
+}
+
+impl<K: Eq + Hash<S> + Send + Share, V: Clone + Send + Share, S, H: Hasher<S>>
+        ConcurrentHashMap<K, V, H> {
+    /// Returns a copy of the value for the key `k`, if there is one.
+    pub fn get(&self, k: &K) -> Option<V> {
+        self.find_with(k, |v| v.clone())
+    }
+
+    /// Returns a copy of the value for the key `k`, first inserting the value
+    /// returned by `f` if there is no entry for it yet, as a single atomic
+    /// operation.
+    pub fn find_or_insert_with(&self, k: K, f: |&K| -> V) -> V {
+        match self.get(&k) {
+            Some(v) => return v,
+            None => {}
+        }
+
+        let (shard, hash) = self.locate(&k);
+        let mut shard = shard.write();
+        match shard.find(hash, &k) {
+            Some(v) => return v.clone(),
+            None => {}
+        }
+        let v = f(&k);
+        let ret = v.clone();
+        shard.insert_new(hash, k, v);
+        self.len.fetch_add(1, SeqCst);
+        ret
+    }
+}
+
+impl<K: Eq + Hash<S> + Clone + Send + Share, V: Clone + Send + Share, S,
+     H: Hasher<S>> ConcurrentHashMap<K, V, H> {
+    /// Returns a copy of every entry in the map, in no particular order.
+    ///
+    /// All of the shards are locked for reading while the entries are copied,
+    /// so the snapshot is consistent: it reflects the map as it was at a single
+    /// point in time. Updates to the map block until the copy is complete.
+    pub fn snapshot(&self) -> Vec<(K, V)> {
+        let shards: Vec<RWLockReadGuard<Shard<K, V>>> =
+            self.shards.iter().map(|shard| shard.read()).collect();
+        let mut entries = Vec::with_capacity(shards.iter().fold(0, |n, shard| {
+            n + shard.len
+        }));
+        for shard in shards.iter() {
+            for bucket in shard.buckets.iter() {
+                for e in bucket.iter() {
+                    entries.push((e.key.clone(), e.value.clone()));
+                }
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::*;
+    use std::task;
+
+    use Arc;
+    use super::ConcurrentHashMap;
+
+    #[test]
+    fn smoke() {
+        let map = ConcurrentHashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(1i, "one"), None);
+        assert_eq!(map.insert(2i, "two"), None);
+        assert_eq!(map.insert(1i, "uno"), Some("one"));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some("uno"));
+        assert!(map.contains_key(&2));
+        assert_eq!(map.remove(&2), Some("two"));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.len(), 1);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn grow() {
+        let map = ConcurrentHashMap::with_shards(2);
+        for i in range(0u, 1000) {
+            map.insert(i, i * 2);
+        }
+        assert_eq!(map.len(), 1000);
+        for i in range(0u, 1000) {
+            assert_eq!(map.get(&i), Some(i * 2));
+        }
+        for i in range(0u, 1000).filter(|i| i % 2 == 0) {
+            assert_eq!(map.remove(&i), Some(i * 2));
+        }
+        assert_eq!(map.len(), 500);
+        assert_eq!(map.get(&10), None);
+        assert_eq!(map.get(&11), Some(22));
+    }
+
+    #[test]
+    fn entry_operations() {
+        let map = ConcurrentHashMap::new();
+        assert!(!map.update(&1i, |v| *v += 1));
+        map.insert_or_update_with(1i, 10i, |_, v| *v += 1);
+        map.insert_or_update_with(1i, 10i, |_, v| *v += 1);
+        assert_eq!(map.get(&1), Some(11));
+        assert!(map.update(&1i, |v| *v *= 2));
+        assert_eq!(map.find_with(&1, |v| *v + 1), Some(23));
+        assert_eq!(map.find_or_insert_with(1, |_| fail!()), 22);
+        assert_eq!(map.find_or_insert_with(2, |k| *k * 5), 10);
+        assert_eq!(map.remove_if(&1, |v| *v > 100), None);
+        assert_eq!(map.remove_if(&1, |v| *v == 22), Some(22));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn snapshot() {
+        let map = ConcurrentHashMap::new();
+        for i in range(0u, 100) {
+            map.insert(i, i.to_str());
+        }
+        let mut entries = map.snapshot();
+        map.clear();
+        entries.sort();
+        assert_eq!(entries.len(), 100);
+        for (i, &(k, ref v)) in entries.iter().enumerate() {
+            assert_eq!(k, i);
+            assert_eq!(*v, i.to_str());
+        }
+    }
+
+    #[test]
+    fn many_tasks() {
+        let map = Arc::new(ConcurrentHashMap::with_shards(4));
+        let (tx, rx) = channel();
+        for t in range(0u, 8) {
+            let map = map.clone();
+            let tx = tx.clone();
+            task::spawn(proc() {
+                for i in range(0u, 100) {
+                    map.insert(t * 100 + i, t);
+                    task::deschedule();
+                }
+                tx.send(());
+            });
+        }
+        for _ in range(0u, 8) { rx.recv(); }
+        assert_eq!(map.len(), 800);
+        for (k, t) in map.snapshot().move_iter() {
+            assert_eq!(k / 100, t);
+        }
+    }
+
+    #[test]
+    fn poisoned_by_failed_update() {
+        let map = Arc::new(ConcurrentHashMap::with_shards(1));
+        map.insert(1i, 1i);
+        let map2 = map.clone();
+        let ret = task::try(proc() {
+            map2.update(&1, |_| fail!());
+        });
+        assert!(ret.is_err());
+        let ret = task::try(proc() {
+            map.get(&1);
+        });
+        assert!(ret.is_err());
+    }
+}
@@ -28,7 +28,7 @@
        html_root_url = "http://doc.rust-lang.org/0.11.0/",
        html_playground_url = "http://play.rust-lang.org/")]
 
-#![feature(phase, globs, macro_rules, unsafe_destructor)]
+#![feature(phase, globs, macro_rules, unsafe_destructor, default_type_params)]
 #![deny(missing_doc)]
 #![no_std]
 #![allow(unused_attribute)] // NOTE: remove after stage0
@@ -60,6 +60,7 @@ pub mod spsc_queue;
 pub mod mpsc_queue;
 pub mod mpmc_bounded_queue;
 pub mod deque;
+pub mod concurrent_hashmap;
 
 // Low-level concurrency primitives
 
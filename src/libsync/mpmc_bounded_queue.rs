@@ -86,7 +86,7 @@ impl<T: Send> State<T> {
         }
     }
 
-    fn push(&self, value: T) -> bool {
+    fn push(&self, value: T) -> Result<(), T> {
         let mask = self.mask;
         let mut pos = self.enqueue_pos.load(Relaxed);
         loop {
@@ -106,12 +106,12 @@ impl<T: Send> State<T> {
                     pos = enqueue_pos;
                 }
             } else if diff < 0 {
-                return false
+                return Err(value)
             } else {
                 pos = self.enqueue_pos.load(Relaxed);
             }
         }
-        true
+        Ok(())
     }
 
     fn pop(&self) -> Option<T> {
@@ -139,6 +139,20 @@ impl<T: Send> State<T> {
             }
         }
     }
+
+    fn is_empty(&self) -> bool {
+        let pos = self.dequeue_pos.load(Relaxed);
+        let node = self.buffer.get(pos & self.mask);
+        let seq = unsafe { (*node.get()).sequence.load(Acquire) };
+        (seq as int - (pos + 1) as int) < 0
+    }
+
+    fn is_full(&self) -> bool {
+        let pos = self.enqueue_pos.load(Relaxed);
+        let node = self.buffer.get(pos & self.mask);
+        let seq = unsafe { (*node.get()).sequence.load(Acquire) };
+        (seq as int - pos as int) < 0
+    }
 }
 
 impl<T: Send> Queue<T> {
@@ -149,12 +163,29 @@ impl<T: Send> Queue<T> {
     }
 
     pub fn push(&self, value: T) -> bool {
+        self.state.push(value).is_ok()
+    }
+
+    /// Pushes a value onto the queue, handing it back if the queue is full.
+    pub fn push_opt(&self, value: T) -> Result<(), T> {
         self.state.push(value)
     }
 
     pub fn pop(&self) -> Option<T> {
         self.state.pop()
     }
+
+    /// Returns whether the next `pop` would find the queue empty. Other
+    /// threads may push or pop at any time, so this is only a snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.state.is_empty()
+    }
+
+    /// Returns whether the next `push` would find the queue full. As with
+    /// `is_empty`, this is only a snapshot.
+    pub fn is_full(&self) -> bool {
+        self.state.is_full()
+    }
 }
 
 impl<T: Send> Clone for Queue<T> {
@@ -217,4 +248,21 @@ mod tests {
             rx.recv();
         }
     }
+
+    #[test]
+    fn full_and_empty() {
+        let q = Queue::with_capacity(2);
+        assert!(q.is_empty());
+        assert!(!q.is_full());
+        assert_eq!(q.push_opt(1i), Ok(()));
+        assert!(!q.is_empty());
+        assert_eq!(q.push_opt(2), Ok(()));
+        assert!(q.is_full());
+        assert_eq!(q.push_opt(3), Err(3));
+        assert_eq!(q.pop(), Some(1));
+        assert!(!q.is_full());
+        assert_eq!(q.pop(), Some(2));
+        assert!(q.is_empty());
+        assert_eq!(q.pop(), None);
+    }
 }
@@ -18,6 +18,7 @@ use std::fmt;
 use std::io;
 use std::iter::range;
 use std::string::String;
+use serialize::json;
 use term::WriterWrapper;
 use term;
 
@@ -59,7 +60,7 @@ pub enum ColorConfig {
 
 pub trait Emitter {
     fn emit(&mut self, cmsp: Option<(&codemap::CodeMap, Span)>,
-            msg: &str, lvl: Level);
+            msg: &str, code: Option<&str>, lvl: Level);
     fn custom_emit(&mut self, cm: &codemap::CodeMap,
                    sp: RenderSpan, msg: &str, lvl: Level);
 }
@@ -90,9 +91,16 @@ impl SpanHandler {
         self.handler.emit(Some((&self.cm, sp)), msg, Error);
         self.handler.bump_err_count();
     }
+    pub fn span_err_with_code(&self, sp: Span, msg: &str, code: &str) {
+        self.handler.emit_with_code(Some((&self.cm, sp)), msg, code, Error);
+        self.handler.bump_err_count();
+    }
     pub fn span_warn(&self, sp: Span, msg: &str) {
         self.handler.emit(Some((&self.cm, sp)), msg, Warning);
     }
+    pub fn span_warn_with_code(&self, sp: Span, msg: &str, code: &str) {
+        self.handler.emit_with_code(Some((&self.cm, sp)), msg, code, Warning);
+    }
     pub fn span_note(&self, sp: Span, msg: &str) {
         self.handler.emit(Some((&self.cm, sp)), msg, Note);
     }
@@ -102,6 +110,12 @@ impl SpanHandler {
     pub fn fileline_note(&self, sp: Span, msg: &str) {
         self.handler.custom_emit(&self.cm, FileLine(sp), msg, Note);
     }
+    pub fn span_help(&self, sp: Span, msg: &str) {
+        self.handler.emit(Some((&self.cm, sp)), msg, Help);
+    }
+    pub fn fileline_help(&self, sp: Span, msg: &str) {
+        self.handler.custom_emit(&self.cm, FileLine(sp), msg, Help);
+    }
     pub fn span_bug(&self, sp: Span, msg: &str) -> ! {
         self.handler.emit(Some((&self.cm, sp)), msg, Bug);
         fail!(ExplicitBug);
@@ -124,11 +138,11 @@ pub struct Handler {
 
 impl Handler {
     pub fn fatal(&self, msg: &str) -> ! {
-        self.emit.borrow_mut().emit(None, msg, Fatal);
+        self.emit.borrow_mut().emit(None, msg, None, Fatal);
         fail!(FatalError);
     }
     pub fn err(&self, msg: &str) {
-        self.emit.borrow_mut().emit(None, msg, Error);
+        self.emit.borrow_mut().emit(None, msg, None, Error);
         self.bump_err_count();
     }
     pub fn bump_err_count(&self) {
@@ -153,13 +167,16 @@ impl Handler {
         self.fatal(s.as_slice());
     }
     pub fn warn(&self, msg: &str) {
-        self.emit.borrow_mut().emit(None, msg, Warning);
+        self.emit.borrow_mut().emit(None, msg, None, Warning);
     }
     pub fn note(&self, msg: &str) {
-        self.emit.borrow_mut().emit(None, msg, Note);
+        self.emit.borrow_mut().emit(None, msg, None, Note);
+    }
+    pub fn help(&self, msg: &str) {
+        self.emit.borrow_mut().emit(None, msg, None, Help);
     }
     pub fn bug(&self, msg: &str) -> ! {
-        self.emit.borrow_mut().emit(None, msg, Bug);
+        self.emit.borrow_mut().emit(None, msg, None, Bug);
         fail!(ExplicitBug);
     }
     pub fn unimpl(&self, msg: &str) -> ! {
@@ -169,7 +186,14 @@ impl Handler {
                 cmsp: Option<(&codemap::CodeMap, Span)>,
                 msg: &str,
                 lvl: Level) {
-        self.emit.borrow_mut().emit(cmsp, msg, lvl);
+        self.emit.borrow_mut().emit(cmsp, msg, None, lvl);
+    }
+    pub fn emit_with_code(&self,
+                          cmsp: Option<(&codemap::CodeMap, Span)>,
+                          msg: &str,
+                          code: &str,
+                          lvl: Level) {
+        self.emit.borrow_mut().emit(cmsp, msg, Some(code), lvl);
     }
     pub fn custom_emit(&self, cm: &codemap::CodeMap,
                        sp: RenderSpan, msg: &str, lvl: Level) {
@@ -202,6 +226,7 @@ pub enum Level {
     Error,
     Warning,
     Note,
+    Help,
 }
 
 impl fmt::Show for Level {
@@ -213,6 +238,7 @@ impl fmt::Show for Level {
             Fatal | Error => "error".fmt(f),
             Warning => "warning".fmt(f),
             Note => "note".fmt(f),
+            Help => "help".fmt(f),
         }
     }
 }
@@ -222,7 +248,8 @@ impl Level {
         match self {
             Bug | Fatal | Error => term::color::BRIGHT_RED,
             Warning => term::color::BRIGHT_YELLOW,
-            Note => term::color::BRIGHT_GREEN
+            Note => term::color::BRIGHT_GREEN,
+            Help => term::color::BRIGHT_CYAN,
         }
     }
 }
@@ -262,8 +289,8 @@ fn print_maybe_styled(w: &mut EmitterWriter,
     }
 }
 
-fn print_diagnostic(dst: &mut EmitterWriter,
-                    topic: &str, lvl: Level, msg: &str) -> io::IoResult<()> {
+fn print_diagnostic(dst: &mut EmitterWriter, topic: &str, lvl: Level,
+                    msg: &str, code: Option<&str>) -> io::IoResult<()> {
     if !topic.is_empty() {
         try!(write!(&mut dst.dst, "{} ", topic));
     }
@@ -271,9 +298,15 @@ fn print_diagnostic(dst: &mut EmitterWriter,
     try!(print_maybe_styled(dst,
                             format!("{}: ", lvl.to_str()).as_slice(),
                             term::attr::ForegroundColor(lvl.color())));
-    try!(print_maybe_styled(dst,
-                            format!("{}\n", msg).as_slice(),
-                            term::attr::Bold));
+    try!(print_maybe_styled(dst, msg, term::attr::Bold));
+    match code {
+        Some(code) => {
+            let style = term::attr::ForegroundColor(term::color::BRIGHT_MAGENTA);
+            try!(print_maybe_styled(dst, format!(" [{}]", code).as_slice(), style));
+        }
+        None => {}
+    }
+    try!(write!(&mut dst.dst, "\n"));
     Ok(())
 }
 
@@ -325,10 +358,11 @@ impl Emitter for EmitterWriter {
     fn emit(&mut self,
             cmsp: Option<(&codemap::CodeMap, Span)>,
             msg: &str,
+            code: Option<&str>,
             lvl: Level) {
         let error = match cmsp {
-            Some((cm, sp)) => emit(self, cm, FullSpan(sp), msg, lvl, false),
-            None => print_diagnostic(self, "", lvl, msg),
+            Some((cm, sp)) => emit(self, cm, FullSpan(sp), msg, code, lvl, false),
+            None => print_diagnostic(self, "", lvl, msg, code),
         };
 
         match error {
@@ -339,15 +373,169 @@ impl Emitter for EmitterWriter {
 
     fn custom_emit(&mut self, cm: &codemap::CodeMap,
                    sp: RenderSpan, msg: &str, lvl: Level) {
-        match emit(self, cm, sp, msg, lvl, true) {
+        match emit(self, cm, sp, msg, None, lvl, true) {
             Ok(()) => {}
             Err(e) => fail!("failed to print diagnostics: {}", e),
         }
     }
 }
 
+/// An emitter which writes each diagnostic as a single line of JSON, for
+/// consumption by tools wrapping the compiler rather than by humans.
+///
+/// Notes and help messages are not written on their own, they are attached as
+/// children of the error or warning they follow. Consequently a diagnostic is
+/// only written out once the next top-level diagnostic arrives or the emitter
+/// is dropped.
+pub struct JsonEmitter {
+    dst: Box<Writer + Send>,
+    pending: Option<JsonDiagnostic>,
+}
+
+#[deriving(Encodable)]
+struct JsonDiagnostic {
+    message: String,
+    code: Option<String>,
+    level: String,
+    spans: Vec<JsonSpan>,
+    children: Vec<JsonDiagnostic>,
+}
+
+#[deriving(Encodable)]
+struct JsonSpan {
+    file_name: String,
+    byte_start: uint,
+    byte_end: uint,
+    // Lines and columns are both 1-based, columns are counted in characters.
+    line_start: uint,
+    line_end: uint,
+    column_start: uint,
+    column_end: uint,
+    is_primary: bool,
+    label: Option<String>,
+}
+
+impl JsonEmitter {
+    pub fn stderr() -> JsonEmitter {
+        JsonEmitter::new(box io::stderr())
+    }
+
+    pub fn new(dst: Box<Writer + Send>) -> JsonEmitter {
+        JsonEmitter { dst: dst, pending: None }
+    }
+
+    fn add(&mut self, cmsp: Option<(&codemap::CodeMap, Span)>,
+           msg: &str, code: Option<&str>, lvl: Level) -> io::IoResult<()> {
+        let diag = JsonDiagnostic {
+            message: msg.to_string(),
+            code: code.map(|c| c.to_string()),
+            level: lvl.to_str(),
+            spans: match cmsp {
+                Some((cm, sp)) => JsonSpan::from_span(cm, sp),
+                None => Vec::new(),
+            },
+            children: Vec::new(),
+        };
+
+        match lvl {
+            Note | Help if self.pending.is_some() => {
+                self.pending.get_mut_ref().children.push(diag);
+                Ok(())
+            }
+            _ => {
+                try!(self.flush_pending());
+                self.pending = Some(diag);
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_pending(&mut self) -> io::IoResult<()> {
+        match self.pending.take() {
+            Some(diag) => {
+                try!(self.dst.write_str(json::encode(&diag).as_slice()));
+                try!(self.dst.write_str("\n"));
+                self.dst.flush()
+            }
+            None => Ok(())
+        }
+    }
+}
+
+impl JsonSpan {
+    /// Returns the primary span for `sp`, followed by a secondary span for
+    /// each macro expansion site it originated from.
+    fn from_span(cm: &codemap::CodeMap, sp: Span) -> Vec<JsonSpan> {
+        let mut spans = vec!(JsonSpan::new(cm, sp, true, None));
+        let mut expn_info = sp.expn_info;
+        loop {
+            let ei = match expn_info {
+                Some(ei) => ei,
+                None => break,
+            };
+            let (pre, post) = match ei.callee.format {
+                codemap::MacroAttribute => ("#[", "]"),
+                codemap::MacroBang => ("", "!")
+            };
+            let label = format!("in expansion of {}{}{}", pre,
+                                ei.callee.name, post);
+            spans.push(JsonSpan::new(cm, ei.call_site, false, Some(label)));
+            expn_info = ei.call_site.expn_info;
+        }
+        spans
+    }
+
+    fn new(cm: &codemap::CodeMap, sp: Span, is_primary: bool,
+           label: Option<String>) -> JsonSpan {
+        let lo = cm.lookup_char_pos(sp.lo);
+        let hi = cm.lookup_char_pos(sp.hi);
+        JsonSpan {
+            file_name: lo.file.name.clone(),
+            byte_start: cm.lookup_byte_offset(sp.lo).pos.to_uint(),
+            byte_end: cm.lookup_byte_offset(sp.hi).pos.to_uint(),
+            line_start: lo.line,
+            line_end: hi.line,
+            column_start: lo.col.to_uint() + 1,
+            column_end: hi.col.to_uint() + 1,
+            is_primary: is_primary,
+            label: label,
+        }
+    }
+}
+
+impl Emitter for JsonEmitter {
+    fn emit(&mut self,
+            cmsp: Option<(&codemap::CodeMap, Span)>,
+            msg: &str,
+            code: Option<&str>,
+            lvl: Level) {
+        match self.add(cmsp, msg, code, lvl) {
+            Ok(()) => {}
+            Err(e) => fail!("failed to print diagnostics: {}", e),
+        }
+    }
+
+    fn custom_emit(&mut self, cm: &codemap::CodeMap,
+                   sp: RenderSpan, msg: &str, lvl: Level) {
+        match self.add(Some((cm, sp.span())), msg, None, lvl) {
+            Ok(()) => {}
+            Err(e) => fail!("failed to print diagnostics: {}", e),
+        }
+    }
+}
+
+impl Drop for JsonEmitter {
+    fn drop(&mut self) {
+        // Fatal errors unwind straight past the emitter, so whatever is still
+        // pending is written out here. There's nobody left to report a
+        // failure to, so errors are ignored.
+        let _ = self.flush_pending();
+    }
+}
+
 fn emit(dst: &mut EmitterWriter, cm: &codemap::CodeMap, rsp: RenderSpan,
-        msg: &str, lvl: Level, custom: bool) -> io::IoResult<()> {
+        msg: &str, code: Option<&str>, lvl: Level,
+        custom: bool) -> io::IoResult<()> {
     let sp = rsp.span();
     let ss = cm.span_to_str(sp);
     let lines = cm.span_to_lines(sp);
@@ -357,12 +545,12 @@ fn emit(dst: &mut EmitterWriter, cm: &codemap::CodeMap, rsp: RenderSpan,
         // the span)
         let span_end = Span { lo: sp.hi, hi: sp.hi, expn_info: sp.expn_info};
         let ses = cm.span_to_str(span_end);
-        try!(print_diagnostic(dst, ses.as_slice(), lvl, msg));
+        try!(print_diagnostic(dst, ses.as_slice(), lvl, msg, code));
         if rsp.is_full_span() {
             try!(custom_highlight_lines(dst, cm, sp, lvl, lines));
         }
     } else {
-        try!(print_diagnostic(dst, ss.as_slice(), lvl, msg));
+        try!(print_diagnostic(dst, ss.as_slice(), lvl, msg, code));
         if rsp.is_full_span() {
             try!(highlight_lines(dst, cm, sp, lvl, lines));
         }
@@ -501,9 +689,10 @@ fn print_macro_backtrace(w: &mut EmitterWriter,
         try!(print_diagnostic(w, ss.as_slice(), Note,
                               format!("in expansion of {}{}{}", pre,
                                       ei.callee.name,
-                                      post).as_slice()));
+                                      post).as_slice(), None));
         let ss = cm.span_to_str(ei.call_site);
-        try!(print_diagnostic(w, ss.as_slice(), Note, "expansion site"));
+        try!(print_diagnostic(w, ss.as_slice(), Note, "expansion site",
+                              None));
         try!(print_macro_backtrace(w, cm, ei.call_site));
     }
     Ok(())
@@ -0,0 +1,69 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Macros for emitting diagnostics with an error code.
+//!
+//! A crate defines its error codes, along with a long-form explanation of
+//! each, with a single `register_diagnostics!` invocation in a top-level
+//! module named `diagnostics`. The `span_err!` and `span_warn!` macros refer
+//! to the codes defined there, so emitting a code which hasn't been
+//! registered (or which has no explanation) fails to compile.
+
+#![macro_escape]
+
+/// Defines a static for each error code, along with a `DIAGNOSTICS` table
+/// mapping each code to its explanation.
+///
+/// ```ignore
+/// register_diagnostics!(
+///     E0001: r##"
+/// This error suggests that ...
+/// "##,
+///     E0002: r##"
+/// ...
+/// "##
+/// )
+/// ```
+#[macro_export]
+macro_rules! register_diagnostics(
+    ($($code:ident: $description:expr),*) => (
+        $(
+            #[allow(non_uppercase_statics)]
+            pub static $code: &'static str = stringify!($code);
+        )*
+
+        /// Every error code of the crate, along with its explanation.
+        pub static DIAGNOSTICS: &'static [(&'static str, &'static str)] = &[
+            $((stringify!($code), $description)),*
+        ];
+    )
+)
+
+/// Emits an error with an error code registered in the crate's `diagnostics`
+/// module, e.g. `span_err!(sess, sp, E0001, "unreachable pattern")`.
+#[macro_export]
+macro_rules! span_err(
+    ($session:expr, $span:expr, $code:ident, $($message:tt)*) => ({
+        ($session).span_err_with_code($span,
+                                      format!($($message)*).as_slice(),
+                                      ::diagnostics::$code)
+    })
+)
+
+/// Emits a warning with an error code registered in the crate's
+/// `diagnostics` module.
+#[macro_export]
+macro_rules! span_warn(
+    ($session:expr, $span:expr, $code:ident, $($message:tt)*) => ({
+        ($session).span_warn_with_code($span,
+                                       format!($($message)*).as_slice(),
+                                       ::diagnostics::$code)
+    })
+)
@@ -21,7 +21,8 @@ use parse::token::{InternedString, intern, str_to_ident};
 use util::small_vector::SmallVector;
 use ext::mtwt;
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::gc::{Gc, GC};
 
 // new-style macro! tt code:
@@ -629,11 +630,16 @@ struct MapChainFrame {
 // Only generic to make it easy to test
 pub struct SyntaxEnv {
     chain: Vec<MapChainFrame> ,
+    // The names of all the extensions that were looked up successfully.
+    used: RefCell<HashSet<Name>>,
 }
 
 impl SyntaxEnv {
     pub fn new() -> SyntaxEnv {
-        let mut map = SyntaxEnv { chain: Vec::new() };
+        let mut map = SyntaxEnv {
+            chain: Vec::new(),
+            used: RefCell::new(HashSet::new()),
+        };
         map.push_frame();
         map
     }
@@ -662,13 +668,21 @@ impl SyntaxEnv {
     pub fn find<'a>(&'a self, k: &Name) -> Option<&'a SyntaxExtension> {
         for frame in self.chain.iter().rev() {
             match frame.map.find(k) {
-                Some(v) => return Some(v),
+                Some(v) => {
+                    self.used.borrow_mut().insert(*k);
+                    return Some(v)
+                }
                 None => {}
             }
         }
         None
     }
 
+    /// Returns whether an extension named `k` has been found by `find`.
+    pub fn was_used(&self, k: &Name) -> bool {
+        self.used.borrow().contains(k)
+    }
+
     pub fn insert(&mut self, k: Name, v: SyntaxExtension) {
         self.find_escape_frame().map.insert(k, v);
     }
@@ -9,7 +9,7 @@
 // except according to those terms.
 
 use ast::{P, Block, Crate, DeclLocal, ExprMac, PatMac};
-use ast::{Local, Ident, MacInvocTT};
+use ast::{Local, Ident, Name, MacInvocTT};
 use ast::{ItemMac, Mrk, Stmt, StmtDecl, StmtMac, StmtExpr, StmtSemi};
 use ast::TokenTree;
 use ast;
@@ -992,38 +992,72 @@ pub struct ExportedMacros {
     pub macros: Vec<String>,
 }
 
+/// What the expansion of a crate made use of.
+pub struct ExpansionUses {
+    /// The crates at least one of whose exported macros was invoked.
+    pub macro_crates: Vec<Ident>,
+    /// The names of the user supplied syntax extensions that were invoked.
+    pub extensions: Vec<Name>,
+}
+
 pub fn expand_crate(parse_sess: &parse::ParseSess,
                     cfg: ExpansionConfig,
                     macros: Vec<ExportedMacros>,
                     user_exts: Vec<NamedSyntaxExtension>,
                     c: Crate) -> Crate {
+    let (ret, _) = expand_crate_with_uses(parse_sess, cfg, macros, user_exts, c);
+    ret
+}
+
+/// Like `expand_crate`, but also reports which of the exported macros and
+/// user extensions were used.
+pub fn expand_crate_with_uses(parse_sess: &parse::ParseSess,
+                              cfg: ExpansionConfig,
+                              macros: Vec<ExportedMacros>,
+                              user_exts: Vec<NamedSyntaxExtension>,
+                              c: Crate) -> (Crate, ExpansionUses) {
     let mut cx = ExtCtxt::new(parse_sess, c.config.clone(), cfg);
     let mut expander = MacroExpander {
         extsbox: syntax_expander_table(),
         cx: &mut cx,
     };
 
+    let mut macro_names = Vec::new();
     for ExportedMacros { crate_name, macros } in macros.move_iter() {
         let name = format!("<{} macros>", token::get_ident(crate_name))
             .into_string();
 
+        let mut names = Vec::new();
         for source in macros.move_iter() {
             let item = parse::parse_item_from_source_str(name.clone(),
                                                          source,
                                                          expander.cx.cfg(),
                                                          expander.cx.parse_sess())
                     .expect("expected a serialized item");
+            names.push(item.ident.name);
             expand_item_mac(item, &mut expander);
         }
+        macro_names.push((crate_name, names));
     }
 
+    let mut ext_names = Vec::new();
     for (name, extension) in user_exts.move_iter() {
+        ext_names.push(name);
         expander.extsbox.insert(name, extension);
     }
 
     let ret = expander.fold_crate(c);
     parse_sess.span_diagnostic.handler().abort_if_errors();
-    return ret;
+
+    let uses = ExpansionUses {
+        macro_crates: macro_names.move_iter().filter(|&(_, ref names)| {
+            names.iter().any(|n| expander.extsbox.was_used(n))
+        }).map(|(crate_name, _)| crate_name).collect(),
+        extensions: ext_names.move_iter().filter(|n| {
+            expander.extsbox.was_used(n)
+        }).collect(),
+    };
+    (ret, uses)
 }
 
 // HYGIENIC CONTEXT EXTENSION:
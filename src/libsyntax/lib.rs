@@ -40,6 +40,13 @@ extern crate term;
 extern crate fmt_macros;
 extern crate debug;
 
+// NB: This module needs to be declared first so its macros are defined before
+// they are used.
+pub mod diagnostics {
+    pub mod macros;
+    pub mod registry;
+}
+
 pub mod util {
     pub mod interner;
     #[cfg(test)]
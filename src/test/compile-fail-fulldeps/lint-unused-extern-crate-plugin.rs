@@ -0,0 +1,30 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:macro_crate_test.rs
+// ignore-stage1
+// ignore-android
+
+#![feature(phase)]
+#![deny(unused_extern_crates)]
+
+#[phase(plugin)]
+extern crate macro_crate_test; //~ ERROR unused extern crate
+
+#[phase(plugin)]
+extern crate fourcc; // used through the `fourcc!` extension
+
+#[phase(plugin, link)]
+extern crate log; // used through the `info!` macro
+
+fn main() {
+    let x = fourcc!("foo ");
+    info!("{}", x);
+}
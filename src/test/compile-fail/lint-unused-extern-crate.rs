@@ -0,0 +1,32 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![deny(unused_extern_crates)]
+#![allow(unused_variable)]
+
+extern crate libc; //~ ERROR unused extern crate
+
+extern crate collections; //~ ERROR unused extern crate
+
+extern crate rand; // used by the path below
+extern crate time; // used through the reexport below
+
+pub use time::now;
+
+mod foo {
+    extern crate libc; // used by the path below
+
+    pub fn bar() -> libc::c_int { 1 }
+}
+
+fn main() {
+    let x: rand::XorShiftRng = rand::XorShiftRng::new_unseeded();
+    let y = foo::bar();
+}
@@ -0,0 +1,38 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+static GREETING: &'static str = "hello";
+
+pub static LIMIT: uint = 3;
+
+// Uses of a static in constant expressions and patterns, which may well be
+// translated by another codegen unit than the static itself.
+static LIMITS: [uint, ..2] = [LIMIT, LIMIT * 2];
+
+pub fn classify(x: uint) -> &'static str {
+    match x {
+        LIMIT => "limit",
+        _ => "other",
+    }
+}
+
+pub fn limits() -> &'static [uint] { LIMITS.as_slice() }
+
+fn helper(x: uint) -> uint { x * 2 }
+
+pub fn greeting() -> &'static str { GREETING }
+
+pub fn double(x: uint) -> uint { helper(x) }
+
+pub fn sum<T: Iterator<uint>>(it: T) -> uint {
+    it.fold(0, |a, b| a + b)
+}
@@ -0,0 +1,33 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate lib;
+
+static mut COUNTER: uint = 0;
+
+fn bump() -> uint {
+    unsafe { COUNTER += 1; COUNTER }
+}
+
+fn one() -> uint { bump() }
+fn two() -> uint { one() + bump() }
+fn three() -> uint { two() + bump() }
+
+fn main() {
+    assert_eq!(three(), 6);
+    assert_eq!(lib::greeting(), "hello");
+    assert_eq!(lib::double(21), 42);
+    assert_eq!(lib::sum(range(0u, 5)), 10);
+    assert_eq!(lib::classify(lib::LIMIT), "limit");
+    assert_eq!(lib::classify(4), "other");
+    assert_eq!(lib::limits(), [3u, 6].as_slice());
+    let v: Vec<uint> = range(0u, 3).map(|i| lib::double(i)).collect();
+    assert_eq!(v, vec!(0, 2, 4));
+}
@@ -0,0 +1,33 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn refutable_arg(Some(x): Option<int>) -> int { x }
+
+fn main() {
+    let x = Some(1i);
+
+    match x {
+        Some(_) => {}
+        None => {}
+        Some(1) => {}
+    }
+
+    match x {
+        Some(_) => {}
+    }
+
+    let Some(y) = x;
+
+    let z = Some("z".to_string());
+    match z {
+        whole @ Some(inner) => {}
+        None => {}
+    }
+}
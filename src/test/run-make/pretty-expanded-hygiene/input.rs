@@ -0,0 +1,21 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(macro_rules)]
+
+// The `x` bound by the macro is distinct from the one in `main`, which
+// shows up as a different syntax context.
+macro_rules! negate(($e:expr) => ({ let x = 2i; -$e + x - x }))
+
+fn main() {
+    let x = 1i;
+    let _y =
+    negate!(x);
+}
@@ -0,0 +1,33 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-stage1
+
+// Checks that every error code rustc knows about is well-formed, unique and
+// has an explanation for `rustc --explain`.
+
+extern crate rustc;
+
+use std::collections::HashSet;
+
+pub fn main() {
+    let registry = rustc::driver::diagnostics_registry();
+    let mut seen = HashSet::new();
+    for &(code, description) in rustc::diagnostics::DIAGNOSTICS.iter() {
+        assert!(code.len() == 5 && code.starts_with("E") &&
+                code.slice_from(1).chars().all(|c| c.is_digit()),
+                "malformed error code {}", code);
+        assert!(seen.insert(code), "duplicate error code {}", code);
+        assert!(!description.trim().is_empty(),
+                "error code {} has an empty explanation", code);
+        assert_eq!(registry.find_description(code), Some(description));
+    }
+    assert_eq!(registry.find_description("E9999"), None);
+}
@@ -0,0 +1,47 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Timed channel operations on green tasks are woken up by a timer on the
+// scheduler's event loop.
+
+extern crate green;
+extern crate rustuv;
+
+use std::comm::{Select, Empty, Full};
+use std::task;
+
+#[start]
+fn start(argc: int, argv: *const *const u8) -> int {
+    green::start(argc, argv, rustuv::event_loop, main)
+}
+
+fn main() {
+    let (tx, rx) = channel::<int>();
+    assert_eq!(rx.recv_timeout(10), Err(Empty));
+    spawn(proc() {
+        for _ in range(0u, 100) { task::deschedule() }
+        tx.send(1);
+    });
+    assert_eq!(rx.recv_timeout(60000), Ok(1));
+
+    let (stx, srx) = sync_channel::<int>(0);
+    assert_eq!(stx.send_timeout(2, 10), Err(Full(2)));
+
+    let s = Select::new();
+    let mut h = s.handle(&srx);
+    unsafe { h.add(); }
+    assert_eq!(s.wait_timeout(10), None);
+    spawn(proc() {
+        for _ in range(0u, 100) { task::deschedule() }
+        stx.send(3);
+    });
+    assert_eq!(s.wait_timeout(60000), Some(h.id()));
+    assert_eq!(h.recv(), 3);
+}
@@ -0,0 +1,45 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-win32
+// ignore-android
+
+// Process groups and resource limits are set up between fork and exec, which
+// only the native runtime can do, so this test runs on it.
+
+extern crate libc;
+
+use std::io::process;
+use std::io::process::{Command, OpenFiles};
+use std::str;
+
+fn main() {
+    // The limit is visible to the child.
+    let output = Command::new("/bin/sh").arg("-c").arg("ulimit -n")
+                         .rlimit(OpenFiles, 42, 42)
+                         .output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(str::from_utf8(output.output.as_slice()), Some("42\n"));
+
+    // Signalling the group reaches the grandchild as well, which would
+    // otherwise keep the shell waiting.
+    let mut p = Command::new("/bin/sh").arg("-c").arg("sleep 1000 & wait")
+                        .process_group(0)
+                        .spawn().unwrap();
+    let pid = p.id();
+    assert_eq!(unsafe { getpgid(pid) }, pid);
+    p.signal_group(libc::SIGKILL as int).unwrap();
+    match p.wait().unwrap() {
+        process::ExitSignal(9) => {}
+        status => fail!("unexpected exit status: {}", status),
+    }
+
+    extern { fn getpgid(pid: libc::pid_t) -> libc::pid_t; }
+}
@@ -0,0 +1,53 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-windows
+// ignore-android needs extra network permissions
+
+extern crate libc;
+
+use std::io::net::tcp::{TcpListener, TcpStream};
+use std::io::net::udp::UdpSocket;
+use std::io::{Acceptor, Listener, AsRawFd};
+
+fn main() {
+    // Hand a bound socket over to a new listener, as a supervisor handing
+    // down its sockets would.
+    let mut listener = TcpListener::bind("127.0.0.1", 0).unwrap();
+    let addr = listener.socket_name().unwrap();
+    let fd = unsafe { libc::dup(listener.as_raw_fd()) };
+    assert!(fd >= 0);
+    drop(listener);
+
+    let listener = TcpListener::from_raw_fd(fd).unwrap();
+    assert_eq!(listener.as_raw_fd(), fd);
+    let mut acceptor = listener.listen().unwrap();
+
+    let (tx, rx) = channel();
+    spawn(proc() {
+        let ip = format!("{}", addr.ip);
+        let mut stream = TcpStream::connect(ip.as_slice(), addr.port).unwrap();
+        stream.write([1, 2, 3]).unwrap();
+        tx.send(());
+    });
+
+    let stream = acceptor.accept().unwrap();
+    rx.recv();
+
+    // Adopting a stream socket as a datagram socket is an error
+    let fd = unsafe { libc::dup(stream.as_raw_fd()) };
+    assert!(UdpSocket::from_raw_fd(fd).is_err());
+
+    let fd = unsafe { libc::dup(stream.as_raw_fd()) };
+    let mut stream = TcpStream::from_raw_fd(fd).unwrap();
+    let mut buf = [0, ..3];
+    stream.read_at_least(3, buf).unwrap();
+    assert!(buf == [1, 2, 3]);
+}
@@ -0,0 +1,85 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-windows
+
+// Passing file descriptors and credentials over unix domain sockets, and
+// unix datagram sockets, which are only supported by the native runtime.
+
+extern crate libc;
+
+use std::io::net::unix::{UnixListener, UnixStream, UnixDatagram};
+use std::io::{File, TempDir, Listener, Acceptor, AsRawFd};
+
+fn fds_and_credentials(dir: &Path) {
+    let file_path = dir.join("file");
+    File::create(&file_path).write(b"passed along").unwrap();
+
+    let addr = dir.join("stream");
+    let mut acceptor = UnixListener::bind(&addr).listen().unwrap();
+    let (tx, rx) = channel();
+    spawn(proc() {
+        let mut s = UnixStream::connect(&addr).unwrap();
+        let file = File::open(&file_path).unwrap();
+        s.send_with_fds(b"x", [file.as_raw_fd()]).unwrap();
+        drop(file);
+        rx.recv();
+    });
+
+    let mut s = acceptor.accept().unwrap();
+    let creds = s.peer_credentials().unwrap();
+    assert_eq!(creds.uid, unsafe { libc::getuid() } as u64);
+    assert_eq!(creds.gid, unsafe { libc::getgid() } as u64);
+    if cfg!(target_os = "linux") {
+        assert_eq!(creds.pid, Some(unsafe { libc::getpid() }));
+    }
+
+    let mut buf = [0u8, ..1];
+    let mut fds = [-1, ..2];
+    assert_eq!(s.recv_with_fds(buf, fds).unwrap(), (1, 1));
+    assert_eq!(buf.as_slice(), b"x");
+    tx.send(());
+
+    // the descriptor outlives the sender's copy
+    let mut file = File::from_raw_fd(fds[0]).unwrap();
+    assert_eq!(file.read_to_end().unwrap(), b"passed along".to_vec());
+}
+
+fn datagrams(dir: &Path) {
+    let server_path = dir.join("server");
+    let client_path = dir.join("client");
+    let mut server = UnixDatagram::bind(&server_path).unwrap();
+    let mut client = UnixDatagram::bind(&client_path).unwrap();
+    let mut buf = [0u8, ..16];
+
+    client.send_to(b"ping", &server_path).unwrap();
+    let (n, src) = server.recv_from(buf).unwrap();
+    assert_eq!(buf.slice_to(n), b"ping");
+    assert_eq!(src, Some(client_path.clone()));
+
+    server.send_to(b"pong", &src.unwrap()).unwrap();
+    let (n, _) = client.recv_from(buf).unwrap();
+    assert_eq!(buf.slice_to(n), b"pong");
+
+    let mut unbound = UnixDatagram::unbound().unwrap();
+    unbound.send_to(b"anonymous", &server_path).unwrap();
+    let (n, src) = server.recv_from(buf).unwrap();
+    assert_eq!(buf.slice_to(n), b"anonymous");
+    assert!(src.is_none());
+
+    drop(server);
+    assert!(!server_path.exists());
+}
+
+fn main() {
+    let dir = TempDir::new("unix-socket-ancillary").unwrap();
+    fds_and_credentials(dir.path());
+    datagrams(dir.path());
+}